@@ -0,0 +1,2146 @@
+use anyhow::{Context, Result, bail};
+
+use crate::error::ApiError;
+use futures_util::StreamExt;
+pub use reqwest::Method;
+pub use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// How long an idle pooled connection to the bridge is kept open.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Idle connections kept per host by default.
+const DEFAULT_MAX_IDLE_PER_HOST: usize = 8;
+
+/// Request bodies larger than this are sent gzipped. The bridge refuses
+/// bodies over 1 MiB as sent, but takes larger ones once compressed.
+const GZIP_BODY_THRESHOLD: usize = 1024 * 1024;
+
+/// Header carrying a per-request id, which the bridge records on its logs
+/// for the request and echoes back.
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// How long a stream ended by Ctrl-C or `--duration` waits for the bridge
+/// to acknowledge its close frame.
+const CLOSE_GRACE: Duration = Duration::from_secs(1);
+
+/// Header carrying the auth token; never printed.
+const TOKEN_HEADER: &str = "X-Debug-Bridge-Token";
+
+/// A file to attach to an invoke or event as a multipart part.
+pub struct FileArg {
+    /// Key the file's contents appear under in the args or payload.
+    pub key: String,
+    pub path: PathBuf,
+}
+
+/// Window settings for [`BridgeClient::set_window_state`]; `None` leaves
+/// one as it is.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WindowStateChange {
+    pub always_on_top: Option<bool>,
+    pub fullscreen: Option<bool>,
+    pub maximized: Option<bool>,
+}
+
+/// What `/snapshot` returns beyond the default tree.
+#[derive(Clone, Debug, Default)]
+pub struct SnapshotOptions {
+    /// Only interactive elements (and the branches leading to them).
+    pub interactive: bool,
+    /// A flat list of Playwright locators instead of the tree.
+    pub locators: bool,
+    /// Each element's `id` and `class` attributes.
+    pub attrs: bool,
+    /// Hidden elements too, each element marked `visible` or not.
+    pub include_hidden: bool,
+    /// Wait for `document.readyState` to be `complete` first.
+    pub wait_ready: bool,
+    /// Remove refs left by earlier snapshots before assigning new ones.
+    pub reset_refs: bool,
+    /// Only these element fields (e.g. `ref`, `text`), plus `children`.
+    /// Empty for all of them.
+    pub fields: Vec<String>,
+}
+
+/// A request that injects JS, to preview with `?dry_run=true`.
+pub enum DryRunRequest<'a> {
+    Eval {
+        code: &'a str,
+        bindings: &'a serde_json::Map<String, Value>,
+        wait_ready: bool,
+    },
+    Click(&'a str),
+    Fill {
+        selector: &'a str,
+        text: &'a str,
+    },
+    Snapshot(SnapshotOptions),
+    Screenshot {
+        wait_ready: bool,
+        full_page: bool,
+    },
+}
+
+/// Fetches a current token after the bridge rejected the one in use.
+type TokenRefresh = Box<dyn Fn() -> Option<String> + Send + Sync>;
+
+/// Captures from [`BridgeClient::screenshot_all`].
+#[derive(Debug, Default)]
+pub struct WindowScreenshots {
+    /// PNG bytes per window label.
+    pub screenshots: BTreeMap<String, Vec<u8>>,
+    /// Why each window that couldn't be captured failed, by label.
+    pub errors: BTreeMap<String, String>,
+}
+
+/// An operation [`BridgeClient::timed`] can measure.
+#[derive(Clone, Debug)]
+pub enum BenchOp {
+    Eval(String),
+    Snapshot(SnapshotOptions),
+    Screenshot,
+}
+
+/// How long one [`BridgeClient::timed`] call took.
+#[derive(Clone, Copy, Debug)]
+pub struct Timing {
+    /// The round trip as the client saw it, body included.
+    pub total: Duration,
+    /// Time the bridge spent between injecting scripts and their callbacks,
+    /// from its `Server-Timing` header. `None` when no script ran, as for
+    /// screenshots.
+    pub eval: Option<Duration>,
+}
+
+/// A connected byte stream to the bridge, over TCP or a Unix socket.
+trait BridgeStream: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send {}
+impl<T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send> BridgeStream for T {}
+
+/// HTTP/WS client for communicating with the debug bridge plugin.
+///
+/// All requests share one keep-alive connection pool, so rapid sequential
+/// calls reuse an open socket instead of reconnecting each time.
+pub struct BridgeClient {
+    base_url: String,
+    ws_url: String,
+    tcp_addr: String,
+    #[cfg(unix)]
+    socket: Option<PathBuf>,
+    http: reqwest::Client,
+    token: RwLock<Option<String>>,
+    token_refresh: Option<TokenRefresh>,
+    /// Prefix shared by this client's request ids, so one CLI run's requests
+    /// can be picked out of the bridge's logs.
+    session: String,
+    next_request: AtomicU64,
+    verbose: bool,
+    /// Extra headers for WebSocket handshakes; HTTP requests get them as
+    /// the reqwest client's defaults.
+    headers: HeaderMap,
+}
+
+/// Builder for [`BridgeClient`] with connection pool tuning.
+pub struct BridgeClientBuilder {
+    host: String,
+    port: u16,
+    #[cfg(unix)]
+    socket: Option<PathBuf>,
+    token: Option<String>,
+    token_refresh: Option<TokenRefresh>,
+    max_idle_per_host: usize,
+    verbose: bool,
+    headers: HeaderMap,
+}
+
+impl BridgeClientBuilder {
+    pub fn token(mut self, token: Option<&str>) -> Self {
+        self.token = token.map(String::from);
+        self
+    }
+
+    /// Called when the bridge rejects the token (a 401), e.g. to re-read the
+    /// discovery file after the app restarted with a new one. If it returns
+    /// a different token, that replaces the current one and the request is
+    /// retried, at most once per request.
+    pub fn token_refresh(
+        mut self,
+        refresh: impl Fn() -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.token_refresh = Some(Box::new(refresh));
+        self
+    }
+
+    /// Host to reach the bridge on over TCP. Defaults to `127.0.0.1`.
+    pub fn host(mut self, host: Option<&str>) -> Self {
+        if let Some(host) = host {
+            self.host = host.to_string();
+        }
+        self
+    }
+
+    /// Maximum idle connections kept open to the bridge. `0` disables
+    /// pooling, opening a new connection for every request.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.max_idle_per_host = max;
+        self
+    }
+
+    /// Print each request's method, URL, and headers (the token redacted)
+    /// to stderr as it is sent, then the response status.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Send `name: value` with every request and WebSocket handshake, e.g.
+    /// for a proxy in front of the bridge. The auth token and request id
+    /// headers are set per request and can't be replaced this way.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Connect over the bridge's Unix socket instead of TCP.
+    #[cfg(unix)]
+    pub fn unix_socket(mut self, path: Option<PathBuf>) -> Self {
+        self.socket = path;
+        self
+    }
+
+    pub fn build(self) -> BridgeClient {
+        let http = reqwest::Client::builder()
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .pool_max_idle_per_host(self.max_idle_per_host)
+            .tcp_keepalive(POOL_IDLE_TIMEOUT)
+            .tcp_nodelay(true)
+            .default_headers(self.headers.clone());
+        #[cfg(unix)]
+        let http = match &self.socket {
+            Some(path) => http.unix_socket(path.as_path()),
+            None => http,
+        };
+        let http = http.build().expect("building HTTP client");
+
+        let tcp_addr = format!("{}:{}", self.host, self.port);
+        // Over a Unix socket the host only fills the Host header.
+        #[cfg(unix)]
+        let host = match self.socket {
+            Some(_) => "localhost".to_string(),
+            None => tcp_addr.clone(),
+        };
+        #[cfg(not(unix))]
+        let host = tcp_addr.clone();
+        BridgeClient {
+            base_url: format!("http://{host}"),
+            ws_url: format!("ws://{host}"),
+            tcp_addr,
+            #[cfg(unix)]
+            socket: self.socket,
+            http,
+            token: RwLock::new(self.token),
+            token_refresh: self.token_refresh,
+            session: session_id(),
+            next_request: AtomicU64::new(1),
+            verbose: self.verbose,
+            headers: self.headers,
+        }
+    }
+}
+
+/// A short id for this process, from its pid and the clock.
+fn session_id() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    format!("{:08x}", std::process::id().rotate_left(16) ^ nanos)
+}
+
+/// Parse a `Name: Value` header line, as given to `--header`.
+pub fn parse_header(line: &str) -> Result<(HeaderName, HeaderValue)> {
+    let Some((name, value)) = line.split_once(':') else {
+        bail!("invalid header '{line}': expected 'Name: Value'");
+    };
+    let name = name.trim();
+    let name = HeaderName::from_bytes(name.as_bytes())
+        .with_context(|| format!("invalid header name '{name}'"))?;
+    let value = HeaderValue::from_str(value.trim())
+        .with_context(|| format!("invalid value for header '{name}'"))?;
+    Ok((name, value))
+}
+
+/// A request as verbose mode prints it: `> METHOD URL`, then one
+/// `> name: value` line per header, with credential values redacted: the
+/// token, and custom headers whose names look like one.
+fn describe_request(method: &str, url: &str, headers: &HeaderMap) -> String {
+    let mut out = format!("> {method} {url}\n");
+    for (name, value) in headers {
+        let value = if is_credential_header(name.as_str()) {
+            "[redacted]"
+        } else {
+            value.to_str().unwrap_or("[binary]")
+        };
+        out.push_str(&format!("> {name}: {value}\n"));
+    }
+    out
+}
+
+/// Whether a header name suggests its value is a credential. Covers
+/// [`TOKEN_HEADER`] too.
+fn is_credential_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    ["auth", "token", "key", "secret", "cookie", "password"]
+        .iter()
+        .any(|word| name.contains(word))
+}
+
+impl BridgeClient {
+    pub fn builder(port: u16) -> BridgeClientBuilder {
+        BridgeClientBuilder {
+            host: "127.0.0.1".to_string(),
+            port,
+            #[cfg(unix)]
+            socket: None,
+            token: None,
+            token_refresh: None,
+            max_idle_per_host: DEFAULT_MAX_IDLE_PER_HOST,
+            verbose: false,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// A fresh id for the next request.
+    fn request_id(&self) -> String {
+        let n = self.next_request.fetch_add(1, Ordering::Relaxed);
+        format!("{}-{n}", self.session)
+    }
+
+    fn token(&self) -> Option<String> {
+        self.token.read().unwrap().clone()
+    }
+
+    /// Ask the refresh hook for a new token after `rejected` got a 401.
+    /// Returns it, once stored, if it differs from the rejected one, even
+    /// when another request has already stored it.
+    fn refresh_token(&self, rejected: Option<&str>) -> Option<String> {
+        let fresh = (self.token_refresh.as_ref()?)()?;
+        if rejected == Some(fresh.as_str()) {
+            return None;
+        }
+        *self.token.write().unwrap() = Some(fresh.clone());
+        if self.verbose {
+            eprintln!("* token rejected; retrying with a refreshed one");
+        }
+        Some(fresh)
+    }
+
+    /// Send `req`. On a 401, refresh the token and retry once, if a refresh
+    /// hook is set and the body can be replayed.
+    async fn send(&self, req: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+        let req = req.build()?;
+        let retry = self.token_refresh.as_ref().and_then(|_| req.try_clone());
+        let sent = req
+            .headers()
+            .get(TOKEN_HEADER)
+            .and_then(|t| t.to_str().ok())
+            .map(str::to_string);
+        let resp = self.execute(req).await?;
+        if resp.status() != reqwest::StatusCode::UNAUTHORIZED {
+            return Ok(resp);
+        }
+        let (Some(mut retry), Some(token)) = (retry, self.refresh_token(sent.as_deref())) else {
+            return Ok(resp);
+        };
+        match HeaderValue::from_str(&token) {
+            Ok(value) => {
+                retry.headers_mut().insert(TOKEN_HEADER, value);
+                self.execute(retry).await
+            }
+            Err(_) => Ok(resp),
+        }
+    }
+
+    /// Execute `req`, printing it and the response status when verbose.
+    async fn execute(&self, req: reqwest::Request) -> reqwest::Result<reqwest::Response> {
+        if !self.verbose {
+            return self.http.execute(req).await;
+        }
+        // Custom headers are the client's defaults, so list them too, as
+        // the WebSocket handshake does.
+        let mut headers = self.headers.clone();
+        headers.extend(req.headers().clone());
+        eprint!(
+            "{}",
+            describe_request(req.method().as_str(), req.url().as_str(), &headers)
+        );
+        let start = std::time::Instant::now();
+        let result = self.http.execute(req).await;
+        match &result {
+            Ok(resp) => eprintln!("< {} ({}ms)", resp.status(), start.elapsed().as_millis()),
+            Err(e) => eprintln!("< {e}"),
+        }
+        result
+    }
+
+    /// Build a GET request with auth and request-id headers.
+    fn authed_get(&self, url: &str) -> reqwest::RequestBuilder {
+        let mut req = self
+            .http
+            .get(url)
+            .header(REQUEST_ID_HEADER, self.request_id());
+        if let Some(t) = self.token() {
+            req = req.header(TOKEN_HEADER, t);
+        }
+        req
+    }
+
+    /// Build a POST request with auth and request-id headers.
+    fn authed_post(&self, url: &str) -> reqwest::RequestBuilder {
+        let mut req = self
+            .http
+            .post(url)
+            .header(REQUEST_ID_HEADER, self.request_id());
+        if let Some(t) = self.token() {
+            req = req.header(TOKEN_HEADER, t);
+        }
+        req
+    }
+
+    /// Build an authed POST with a JSON body, gzipped when it's too large
+    /// to send as is.
+    fn authed_post_json(&self, url: &str, body: &Value) -> Result<reqwest::RequestBuilder> {
+        let req = self
+            .authed_post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        let (encoding, body) = encode_body(serde_json::to_vec(body)?)?;
+        Ok(match encoding {
+            Some(encoding) => req.header(reqwest::header::CONTENT_ENCODING, encoding),
+            None => req,
+        }
+        .body(body))
+    }
+
+    /// Open a raw connection to the bridge for a WebSocket handshake.
+    async fn connect_stream(&self) -> Result<Box<dyn BridgeStream>> {
+        #[cfg(unix)]
+        if let Some(path) = &self.socket {
+            let stream = tokio::net::UnixStream::connect(path)
+                .await
+                .with_context(|| format!("connecting to {}", path.display()))?;
+            return Ok(Box::new(stream));
+        }
+        let stream = tokio::net::TcpStream::connect(&self.tcp_addr)
+            .await
+            .with_context(|| format!("connecting to {}", self.tcp_addr))?;
+        stream.set_nodelay(true)?;
+        Ok(Box::new(stream))
+    }
+
+    /// Connect a WebSocket with auth header, refreshing the token and
+    /// retrying once if the handshake is refused with a 401.
+    async fn authed_ws(
+        &self,
+        url: &str,
+    ) -> Result<tokio_tungstenite::WebSocketStream<Box<dyn BridgeStream>>> {
+        use tokio_tungstenite::tungstenite::Error;
+        let sent = self.token();
+        match self.ws_handshake(url, sent.as_deref()).await {
+            Err(e)
+                if matches!(
+                    e.downcast_ref::<Error>(),
+                    Some(Error::Http(resp)) if resp.status() == reqwest::StatusCode::UNAUTHORIZED
+                ) =>
+            {
+                match self.refresh_token(sent.as_deref()) {
+                    Some(fresh) => self.ws_handshake(url, Some(&fresh)).await,
+                    None => Err(e),
+                }
+            }
+            result => result,
+        }
+    }
+
+    async fn ws_handshake(
+        &self,
+        url: &str,
+        token: Option<&str>,
+    ) -> Result<tokio_tungstenite::WebSocketStream<Box<dyn BridgeStream>>> {
+        use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+        let mut request = url.into_client_request().context("building WS request")?;
+        request.headers_mut().extend(self.headers.clone());
+        request
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER, self.request_id().parse().unwrap());
+        if let Some(t) = token {
+            request
+                .headers_mut()
+                .insert(TOKEN_HEADER, t.parse().unwrap());
+        }
+        if self.verbose {
+            eprint!("{}", describe_request("GET", url, request.headers()));
+        }
+        let stream = self.connect_stream().await?;
+        let (ws, response) = tokio_tungstenite::client_async(request, stream)
+            .await
+            .context("connecting to WebSocket")?;
+        if self.verbose {
+            eprintln!("< {}", response.status());
+        }
+        Ok(ws)
+    }
+
+    /// Send `method` to `path` (a path and query, like `/snapshot?interactive=true`)
+    /// with `headers` and `body`, authed like every other call, and return
+    /// the raw response whatever its status. For proxies relaying requests
+    /// they didn't build.
+    pub async fn forward(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        headers: HeaderMap,
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response> {
+        let mut req = self
+            .http
+            .request(method, format!("{}{path}", self.base_url))
+            .headers(headers)
+            .header(REQUEST_ID_HEADER, self.request_id());
+        if let Some(t) = self.token() {
+            req = req.header(TOKEN_HEADER, t);
+        }
+        self.send(req.body(body))
+            .await
+            .context("connecting to debug bridge — is the app running with the plugin enabled?")
+    }
+
+    pub async fn health(&self) -> Result<Value> {
+        let resp = self
+            .send(self.http.get(format!("{}/health", self.base_url)))
+            .await
+            .context("connecting to debug bridge — is the app running with the plugin enabled?")?;
+        Ok(resp.json().await?)
+    }
+
+    /// Returns the PNG bytes and any warning the bridge attached (e.g. a
+    /// full-page capture that was capped). Use [`Self::screenshot_to`] to
+    /// avoid holding a large capture in memory.
+    pub async fn screenshot(
+        &self,
+        wait_ready: bool,
+        full_page: bool,
+    ) -> Result<(Vec<u8>, Option<String>)> {
+        let (resp, warning) = self.screenshot_response(wait_ready, full_page).await?;
+        Ok((resp.bytes().await?.to_vec(), warning))
+    }
+
+    /// Writes the PNG to `out` chunk by chunk as it arrives and returns any
+    /// warning the bridge attached.
+    pub async fn screenshot_to<W>(
+        &self,
+        wait_ready: bool,
+        full_page: bool,
+        out: &mut W,
+    ) -> Result<Option<String>>
+    where
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut resp, warning) = self.screenshot_response(wait_ready, full_page).await?;
+        while let Some(chunk) = resp.chunk().await? {
+            out.write_all(&chunk).await?;
+        }
+        out.flush().await?;
+        Ok(warning)
+    }
+
+    /// [`Self::screenshot_to`] a file. A partly written file is removed if
+    /// the capture fails.
+    pub async fn screenshot_to_file(
+        &self,
+        path: impl AsRef<Path>,
+        wait_ready: bool,
+        full_page: bool,
+    ) -> Result<Option<String>> {
+        let path = path.as_ref();
+        let mut file = tokio::fs::File::create(path)
+            .await
+            .with_context(|| format!("writing screenshot to {}", path.display()))?;
+        let result = self.screenshot_to(wait_ready, full_page, &mut file).await;
+        if result.is_err() {
+            drop(file);
+            let _ = tokio::fs::remove_file(path).await;
+        }
+        result.with_context(|| format!("writing screenshot to {}", path.display()))
+    }
+
+    async fn screenshot_response(
+        &self,
+        wait_ready: bool,
+        full_page: bool,
+    ) -> Result<(reqwest::Response, Option<String>)> {
+        let resp = self
+            .send(
+                self.authed_get(&format!("{}/screenshot", self.base_url))
+                    .query(&screenshot_query(wait_ready, full_page)),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("screenshot", resp).await);
+        }
+        let warning = resp
+            .headers()
+            .get("x-debug-bridge-warning")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        Ok((resp, warning))
+    }
+
+    pub async fn snapshot(&self, options: SnapshotOptions) -> Result<Value> {
+        let resp = self
+            .send(
+                self.authed_get(&format!("{}/snapshot", self.base_url))
+                    .query(&snapshot_query(&options)),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("snapshot", resp).await);
+        }
+        // `format=locators` is a bare array, so a cut-short walk is flagged
+        // in a header rather than a `truncated` field.
+        if let Some(warning) = resp
+            .headers()
+            .get("x-debug-bridge-warning")
+            .and_then(|v| v.to_str().ok())
+        {
+            eprintln!("warning: {warning}");
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// A viewport capture of every open window. Windows that fail are listed
+    /// in `errors` rather than failing the call.
+    pub async fn screenshot_all(&self) -> Result<WindowScreenshots> {
+        use base64::Engine;
+
+        let resp = self
+            .send(self.authed_get(&format!("{}/screenshot/all", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("screenshot all", resp).await);
+        }
+        let body: Value = resp.json().await?;
+        let mut captures = WindowScreenshots::default();
+        for (label, png) in body["screenshots"].as_object().into_iter().flatten() {
+            let png = base64::engine::general_purpose::STANDARD
+                .decode(png.as_str().unwrap_or_default())
+                .with_context(|| format!("decoding the screenshot of window '{label}'"))?;
+            captures.screenshots.insert(label.clone(), png);
+        }
+        for (label, error) in body["errors"].as_object().into_iter().flatten() {
+            let error = error.as_str().unwrap_or_default().to_string();
+            captures.errors.insert(label.clone(), error);
+        }
+        Ok(captures)
+    }
+
+    /// URL, title, load state, focus, and element counts of a window.
+    pub async fn page_state(&self, window: Option<&str>) -> Result<Value> {
+        let mut req = self.authed_get(&format!("{}/pagestate", self.base_url));
+        if let Some(w) = window {
+            req = req.query(&[("window", w)]);
+        }
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("pagestate", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Refs from earlier snapshots that still exist in the DOM.
+    pub async fn live_refs(&self, window: Option<&str>) -> Result<Value> {
+        let mut req = self.authed_get(&format!("{}/refs", self.base_url));
+        if let Some(w) = window {
+            req = req.query(&[("window", w)]);
+        }
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("refs", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Remove every ref from `window`'s DOM; the answer counts them.
+    pub async fn clear_refs(&self, window: Option<&str>) -> Result<Value> {
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/refs/clear", self.base_url))
+                    .json(&serde_json::json!({ "window": window })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("clear refs", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn click(&self, selector: &str) -> Result<Value> {
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/click", self.base_url))
+                    .json(&serde_json::json!({ "selector": selector })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("click", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn fill(&self, selector: &str, text: &str) -> Result<Value> {
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/fill", self.base_url))
+                    .json(&serde_json::json!({ "selector": selector, "text": text })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("fill", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Select `start..end` of an element's text (all of it when omitted) and
+    /// return the selected string.
+    pub async fn select_text(
+        &self,
+        selector: &str,
+        start: Option<u32>,
+        end: Option<u32>,
+    ) -> Result<Value> {
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/select-text", self.base_url))
+                    .json(&serde_json::json!({ "selector": selector, "start": start, "end": end })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("select-text", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Wait for the webview to navigate and finish loading, e.g. after a
+    /// click that follows a link. With `url_contains`, wait for a URL
+    /// containing it instead, which also succeeds if the page is already
+    /// there. The bridge gives up after `timeout` (5s by default). Pass the
+    /// `loads_started` a click returned as `since` to also count a load the
+    /// click started before the wait arrived.
+    pub async fn wait_navigation(
+        &self,
+        url_contains: Option<&str>,
+        timeout: Option<Duration>,
+        since: Option<u64>,
+    ) -> Result<Value> {
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/wait/navigation", self.base_url))
+                    .query(&[("since", since)])
+                    .json(&serde_json::json!({
+                        "url_contains": url_contains,
+                        "timeout_ms": timeout.map(|t| t.as_millis() as u64),
+                    })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("wait navigation", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// The scripts `request` would inject, wrapped as the bridge would run
+    /// them. Nothing is executed.
+    pub async fn dry_run(&self, request: DryRunRequest<'_>) -> Result<Value> {
+        let dry_run = [("dry_run", "true")];
+        let req = match request {
+            DryRunRequest::Eval {
+                code,
+                bindings,
+                wait_ready,
+            } => self
+                .authed_post_json(
+                    &format!("{}/eval", self.base_url),
+                    &eval_body(code, bindings, wait_ready),
+                )?
+                .query(&dry_run),
+            DryRunRequest::Click(selector) => self
+                .authed_post(&format!("{}/click", self.base_url))
+                .query(&dry_run)
+                .json(&serde_json::json!({ "selector": selector })),
+            DryRunRequest::Fill { selector, text } => self
+                .authed_post(&format!("{}/fill", self.base_url))
+                .query(&dry_run)
+                .json(&serde_json::json!({ "selector": selector, "text": text })),
+            DryRunRequest::Snapshot(options) => self
+                .authed_get(&format!("{}/snapshot", self.base_url))
+                .query(&snapshot_query(&options))
+                .query(&dry_run),
+            DryRunRequest::Screenshot {
+                wait_ready,
+                full_page,
+            } => self
+                .authed_get(&format!("{}/screenshot", self.base_url))
+                .query(&screenshot_query(wait_ready, full_page))
+                .query(&dry_run),
+        };
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("dry run", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn computed_styles(&self, selector: &str, props: &[String]) -> Result<Value> {
+        let mut req = self
+            .authed_get(&format!("{}/styles", self.base_url))
+            .query(&[("selector", selector)]);
+        if !props.is_empty() {
+            req = req.query(&[("props", props.join(","))]);
+        }
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("styles", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Whether a click on `selector` would land on it: `{"clickable": bool,
+    /// "reason": "..."}`, the reason saying what's in the way when it wouldn't.
+    pub async fn is_clickable(&self, selector: &str) -> Result<Value> {
+        let req = self
+            .authed_get(&format!("{}/clickable", self.base_url))
+            .query(&[("selector", selector)]);
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("clickable", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Markup of the page, or of `selector`'s element. `inner` returns
+    /// `innerHTML` instead of `outerHTML`.
+    pub async fn html(&self, selector: Option<&str>, inner: bool) -> Result<Value> {
+        let mut req = self.authed_get(&format!("{}/html", self.base_url));
+        if let Some(selector) = selector {
+            req = req.query(&[("selector", selector)]);
+        }
+        if inner {
+            req = req.query(&[("inner", "true")]);
+        }
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("html", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn clipboard_read(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_get(&format!("{}/clipboard", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("clipboard read", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn clipboard_write(&self, text: &str) -> Result<Value> {
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/clipboard", self.base_url))
+                    .json(&serde_json::json!({ "text": text })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("clipboard write", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// The zoom factor of a window (1.0 = 100%).
+    pub async fn zoom(&self, window: Option<&str>) -> Result<Value> {
+        let mut req = self.authed_get(&format!("{}/zoom", self.base_url));
+        if let Some(w) = window {
+            req = req.query(&[("window", w)]);
+        }
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("zoom", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Set a window's zoom factor; the bridge reports the value it applied.
+    pub async fn set_zoom(&self, factor: f64, window: Option<&str>) -> Result<Value> {
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/zoom", self.base_url))
+                    .json(&serde_json::json!({ "factor": factor, "window": window })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("zoom", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn run_js(&self, code: &str) -> Result<Value> {
+        self.run_js_with_bindings(code, &serde_json::Map::new())
+            .await
+    }
+
+    /// Run `code` with `bindings` readable as `__env.<key>`. Values are sent
+    /// as JSON, so they never need quoting into the code.
+    pub async fn run_js_with_bindings(
+        &self,
+        code: &str,
+        bindings: &serde_json::Map<String, Value>,
+    ) -> Result<Value> {
+        self.eval(&eval_body(code, bindings, false)).await
+    }
+
+    /// [`run_js_with_bindings`](Self::run_js_with_bindings), once
+    /// `document.readyState` is `complete`. The wait counts towards the
+    /// bridge's eval timeout.
+    pub async fn run_js_when_ready(
+        &self,
+        code: &str,
+        bindings: &serde_json::Map<String, Value>,
+    ) -> Result<Value> {
+        self.eval(&eval_body(code, bindings, true)).await
+    }
+
+    /// Run `code` and return its value as text, unparsed: strings verbatim,
+    /// anything else as JSON. Suited to HTML or CSV built in the page. A
+    /// thrown error is returned as the `Err`.
+    pub async fn run_js_raw(
+        &self,
+        code: &str,
+        bindings: &serde_json::Map<String, Value>,
+        wait_ready: bool,
+    ) -> Result<String> {
+        let req = self
+            .authed_post_json(
+                &format!("{}/eval", self.base_url),
+                &eval_body(code, bindings, wait_ready),
+            )?
+            .query(&[("raw", "true")]);
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("eval", resp).await);
+        }
+        Ok(resp.text().await?)
+    }
+
+    async fn eval(&self, body: &Value) -> Result<Value> {
+        let resp = self
+            .send(self.authed_post_json(&format!("{}/eval", self.base_url), body)?)
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("eval", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Run `op` once with `?timing=true`, discarding its result, and report
+    /// how long it took end to end and inside the webview.
+    pub async fn timed(&self, op: &BenchOp) -> Result<Timing> {
+        let (what, req) = match op {
+            BenchOp::Eval(code) => (
+                "eval",
+                self.authed_post_json(
+                    &format!("{}/eval", self.base_url),
+                    &eval_body(code, &serde_json::Map::new(), false),
+                )?,
+            ),
+            BenchOp::Snapshot(options) => (
+                "snapshot",
+                self.authed_get(&format!("{}/snapshot", self.base_url))
+                    .query(&snapshot_query(options)),
+            ),
+            BenchOp::Screenshot => (
+                "screenshot",
+                self.authed_get(&format!("{}/screenshot", self.base_url)),
+            ),
+        };
+        let start = std::time::Instant::now();
+        let resp = self.send(req.query(&[("timing", "true")])).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response(what, resp).await);
+        }
+        let eval = resp
+            .headers()
+            .get("server-timing")
+            .and_then(|v| v.to_str().ok())
+            .and_then(eval_duration);
+        resp.bytes().await?;
+        Ok(Timing {
+            total: start.elapsed(),
+            eval,
+        })
+    }
+
+    pub async fn invoke(&self, command: &str, args: &str) -> Result<Value> {
+        let args: Value = serde_json::from_str(args).context("invalid JSON args")?;
+        let resp = self
+            .send(self.authed_post_json(
+                &format!("{}/invoke", self.base_url),
+                &serde_json::json!({ "command": command, "args": args }),
+            )?)
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("invoke", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Invoke with files attached, sent as multipart. Each file's contents
+    /// are added to `args` under its key: base64, or byte arrays with `bytes`.
+    pub async fn invoke_with_files(
+        &self,
+        command: &str,
+        args: &str,
+        files: &[FileArg],
+        bytes: bool,
+    ) -> Result<Value> {
+        let args: Value = serde_json::from_str(args).context("invalid JSON args")?;
+        let form = multipart_form(
+            &serde_json::json!({ "command": command, "args": args }),
+            files,
+            bytes,
+        )
+        .await?;
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/invoke", self.base_url))
+                    .multipart(form),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("invoke", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn invoke_batch(&self, calls: &str, stop_on_error: bool) -> Result<Value> {
+        let calls: Value = serde_json::from_str(calls).context("invalid JSON batch")?;
+        if !calls.is_array() {
+            bail!("batch must be a JSON array of {{\"command\", \"args\"}} objects");
+        }
+        let resp = self
+            .send(self.authed_post_json(
+                &format!("{}/invoke/batch", self.base_url),
+                &serde_json::json!({ "calls": calls, "stop_on_error": stop_on_error }),
+            )?)
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("invoke batch", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn state(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_get(&format!("{}/state", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("state", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn commands(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_get(&format!("{}/commands", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("commands", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// The app's data, config, cache, and resource directories.
+    pub async fn paths(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_get(&format!("{}/paths", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("paths", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// The host app's name, version, identifier, and Tauri version.
+    pub async fn app_info(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_get(&format!("{}/app", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("app", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Whether the app is a dev or bundled build, and the app and platform
+    /// it's running as.
+    pub async fn env(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_get(&format!("{}/env", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("env", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// The app's Tauri config (`tauri.conf.json` as loaded), unredacted.
+    pub async fn config(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_get(&format!("{}/config", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("config", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// The bridge's built-in endpoints, whether each works in this build and
+    /// configuration, and its optional features. Plugins older than this
+    /// endpoint answer 404.
+    pub async fn capabilities(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_get(&format!("{}/capabilities", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("capabilities", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Contents of a `tauri-plugin-store` store, e.g. "settings.json".
+    pub async fn store(&self, path: &str) -> Result<Value> {
+        let resp = self
+            .send(
+                self.authed_get(&format!("{}/store", self.base_url))
+                    .query(&[("path", path)]),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("store", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn windows(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_get(&format!("{}/windows", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("windows", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Whether `window` is always on top, fullscreen, and maximized.
+    pub async fn window_state(&self, window: Option<&str>) -> Result<Value> {
+        let mut req = self.authed_get(&format!("{}/windows/state", self.base_url));
+        if let Some(w) = window {
+            req = req.query(&[("window", w)]);
+        }
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("window state", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Apply `change` to `window`; the bridge answers with the state
+    /// afterwards.
+    pub async fn set_window_state(
+        &self,
+        change: WindowStateChange,
+        window: Option<&str>,
+    ) -> Result<Value> {
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/windows/state", self.base_url))
+                    .json(&serde_json::json!({
+                        "window": window,
+                        "always_on_top": change.always_on_top,
+                        "fullscreen": change.fullscreen,
+                        "maximized": change.maximized,
+                    })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("window state", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn shutdown(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_post(&format!("{}/shutdown", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("shutdown", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn dialog_arm(&self, kind: &str, response: &str) -> Result<Value> {
+        // Accept bare text (e.g. a file path) without requiring JSON quoting.
+        let response: Value =
+            serde_json::from_str(response).unwrap_or_else(|_| Value::String(response.into()));
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/dialog/arm", self.base_url))
+                    .json(&serde_json::json!({ "type": kind, "response": response })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("dialog arm", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn dialog_armed(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_get(&format!("{}/dialog", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("dialog list", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn menu(&self, window: Option<&str>) -> Result<Value> {
+        let mut req = self.authed_get(&format!("{}/menu", self.base_url));
+        if let Some(w) = window {
+            req = req.query(&[("window", w)]);
+        }
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("menu", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn menu_click(&self, id: &str, window: Option<&str>) -> Result<Value> {
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/menu/click", self.base_url))
+                    .json(&serde_json::json!({ "id": id, "window": window })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("menu click", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn event_emit(&self, name: &str, payload: &str) -> Result<Value> {
+        let payload: Value = serde_json::from_str(payload).context("invalid JSON payload")?;
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/events/emit", self.base_url))
+                    .json(&serde_json::json!({ "event": name, "payload": payload })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("event emit", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Emit an event with files added to its payload, as for
+    /// [`BridgeClient::invoke_with_files`].
+    pub async fn event_emit_with_files(
+        &self,
+        name: &str,
+        payload: &str,
+        files: &[FileArg],
+        bytes: bool,
+    ) -> Result<Value> {
+        let payload: Value = serde_json::from_str(payload).context("invalid JSON payload")?;
+        let form = multipart_form(
+            &serde_json::json!({ "event": name, "payload": payload }),
+            files,
+            bytes,
+        )
+        .await?;
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/events/emit", self.base_url))
+                    .multipart(form),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("event emit", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Emit an event and wait for the frontend's `<name>:ack` event.
+    pub async fn event_emit_await(
+        &self,
+        name: &str,
+        payload: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Value> {
+        let payload: Value = serde_json::from_str(payload).context("invalid JSON payload")?;
+        let resp = self
+            .send(
+                self.authed_post(&format!("{}/events/emit-await", self.base_url))
+                    .json(&serde_json::json!({
+                        "event": name,
+                        "payload": payload,
+                        "timeout_ms": timeout.map(|t| t.as_millis() as u64),
+                    })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("event emit", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn event_list(&self) -> Result<Value> {
+        let resp = self
+            .send(self.authed_get(&format!("{}/events/list", self.base_url)))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("event list", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Print events named `name` as they arrive. With `once`, stop after
+    /// the first. Ends cleanly on Ctrl-C or after `duration`.
+    pub async fn event_listen(
+        &self,
+        name: &str,
+        once: bool,
+        duration: Option<Duration>,
+    ) -> Result<()> {
+        let url = format!("{}/events/listen?name={name}", self.ws_url);
+        print_text_frames(
+            self.authed_ws(&url).await?,
+            &mut std::io::stdout(),
+            once,
+            duration,
+        )
+        .await
+    }
+
+    /// The last `n` buffered console messages, oldest first.
+    pub async fn console_recent(&self, n: usize, window: Option<&str>) -> Result<Value> {
+        let mut req = self
+            .authed_get(&format!("{}/console/recent", self.base_url))
+            .query(&[("n", n)]);
+        if let Some(w) = window {
+            req = req.query(&[("window", w)]);
+        }
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("console recent", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// One `/console/poll` round: console messages from `window` after the
+    /// cursor `since` (from now on without one), waiting up to the bridge's
+    /// poll timeout for the first. The answer's `cursor` is the next `since`.
+    pub async fn console_poll(&self, since: Option<u64>, window: Option<&str>) -> Result<Value> {
+        let mut req = self.authed_get(&format!("{}/console/poll", self.base_url));
+        if let Some(since) = since {
+            req = req.query(&[("since", since)]);
+        }
+        if let Some(w) = window {
+            req = req.query(&[("window", w)]);
+        }
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("console poll", resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Poll `/console/poll` until `done` returns true for a message, or
+    /// until Ctrl-C or `duration`.
+    async fn poll_console(
+        &self,
+        window: Option<&str>,
+        duration: Option<Duration>,
+        mut done: impl FnMut(Value) -> bool,
+    ) -> Result<()> {
+        let stop = stop_signal(duration);
+        tokio::pin!(stop);
+        let mut since = None;
+        loop {
+            let poll = tokio::select! {
+                poll = self.console_poll(since, window) => poll?,
+                () = &mut stop => return Ok(()),
+            };
+            if let Some(dropped) = poll["dropped"].as_u64() {
+                eprintln!("{dropped} console messages dropped (slow consumer)");
+            }
+            for message in poll["messages"].as_array().into_iter().flatten() {
+                if done(message.clone()) {
+                    return Ok(());
+                }
+            }
+            since = poll["cursor"].as_u64();
+        }
+    }
+
+    /// Stream console output from `window` (default: the bridge's default
+    /// window, `*` for all) until Ctrl-C or `duration`. Falls back to polling
+    /// when the WebSocket upgrade is refused, as some proxies do.
+    pub async fn stream_console(
+        &self,
+        window: Option<&str>,
+        once: bool,
+        duration: Option<Duration>,
+    ) -> Result<()> {
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/console", self.ws_url),
+            window.map(|w| ("window", w)),
+        )?;
+        match self.authed_ws(url.as_str()).await {
+            Ok(ws) => print_text_frames(ws, &mut std::io::stdout(), once, duration).await,
+            Err(e) if upgrade_refused(&e) => {
+                eprintln!("{e:#}; polling /console/poll instead");
+                self.poll_console(window, duration, |message| {
+                    println!("{message}");
+                    once
+                })
+                .await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Wait for the first console message from `window` (as for
+    /// [`stream_console`](Self::stream_console)) that `matches` accepts, and
+    /// return it. Only messages logged after the stream opens are seen. Fails
+    /// straight away if the bridge couldn't hook any of the windows.
+    pub async fn wait_console(
+        &self,
+        window: Option<&str>,
+        mut matches: impl FnMut(&Value) -> bool,
+    ) -> Result<Value> {
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/console", self.ws_url),
+            window.map(|w| ("window", w)),
+        )?;
+        let mut ws = match self.authed_ws(url.as_str()).await {
+            Ok(ws) => ws,
+            Err(e) if upgrade_refused(&e) => {
+                let mut found = None;
+                self.poll_console(window, None, |message| {
+                    let hit = matches(&message);
+                    if hit {
+                        found = Some(message);
+                    }
+                    hit
+                })
+                .await?;
+                return found.context("interrupted");
+            }
+            Err(e) => return Err(e),
+        };
+        while let Some(msg) = ws.next().await {
+            match msg? {
+                tokio_tungstenite::tungstenite::Message::Text(text) => {
+                    let Ok(message) = serde_json::from_str::<Value>(&text) else {
+                        continue;
+                    };
+                    if is_hook_status(&message)? {
+                        continue;
+                    }
+                    if matches(&message) {
+                        let _ = ws.close(None).await;
+                        return Ok(message);
+                    }
+                }
+                tokio_tungstenite::tungstenite::Message::Close(_) => break,
+                _ => {}
+            }
+        }
+        bail!("console stream closed before a matching message arrived")
+    }
+
+    /// Print bridge-internal errors as they happen, until Ctrl-C.
+    pub async fn stream_diagnostics(&self) -> Result<()> {
+        let url = format!("{}/diagnostics", self.ws_url);
+        print_text_frames(
+            self.authed_ws(&url).await?,
+            &mut std::io::stdout(),
+            false,
+            None,
+        )
+        .await
+    }
+
+    pub async fn stream_errors(&self) -> Result<()> {
+        self.stream_console(None, false, None).await
+    }
+
+    /// Print Rust-side log lines until Ctrl-C or `duration`.
+    pub async fn stream_logs(&self, _level: &str, duration: Option<Duration>) -> Result<()> {
+        let url = format!("{}/logs", self.ws_url);
+        print_text_frames(
+            self.authed_ws(&url).await?,
+            &mut std::io::stdout(),
+            false,
+            duration,
+        )
+        .await
+    }
+}
+
+/// Resolves on Ctrl-C, or once `duration` has passed: when a stream should
+/// end cleanly rather than run until the bridge closes it.
+async fn stop_signal(duration: Option<Duration>) {
+    let elapsed = async {
+        match duration {
+            Some(duration) => tokio::time::sleep(duration).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        () = elapsed => {}
+    }
+}
+
+/// Whether `message` is the hook status `/console` opens with rather than
+/// a console message. A status saying no window was hooked is an error,
+/// since no message is coming.
+fn is_hook_status(message: &Value) -> Result<bool> {
+    let Some(hooked) = message.get("hooked").and_then(Value::as_array) else {
+        return Ok(false);
+    };
+    if hooked.is_empty() {
+        bail!(
+            "{}",
+            message["message"]
+                .as_str()
+                .unwrap_or("console hook not installed")
+        );
+    }
+    Ok(true)
+}
+
+/// Print each text frame from a WebSocket stream to `out` until it closes,
+/// or after the first message with `once` (the console hook status doesn't
+/// count), closing the socket. With `once`, a stream that closes first is
+/// an error. Ctrl-C or `duration` ends it with
+/// a close frame, so the bridge tears down what it set up for the stream
+/// (event listeners, subscriptions) straight away.
+async fn print_text_frames(
+    mut ws: tokio_tungstenite::WebSocketStream<Box<dyn BridgeStream>>,
+    out: &mut impl std::io::Write,
+    once: bool,
+    duration: Option<Duration>,
+) -> Result<()> {
+    let stop = stop_signal(duration);
+    tokio::pin!(stop);
+    loop {
+        let msg = tokio::select! {
+            msg = ws.next() => msg,
+            () = &mut stop => {
+                let _ = ws.close(None).await;
+                // Wait briefly for the bridge's reply so the close completes
+                // before the process exits.
+                let _ = tokio::time::timeout(CLOSE_GRACE, async {
+                    while ws.next().await.is_some() {}
+                })
+                .await;
+                return Ok(());
+            }
+        };
+        let Some(msg) = msg else {
+            break;
+        };
+        match msg? {
+            tokio_tungstenite::tungstenite::Message::Text(text) => {
+                if once
+                    && serde_json::from_str(&text)
+                        .map_or(Ok(false), |message| is_hook_status(&message))?
+                {
+                    continue;
+                }
+                writeln!(out, "{text}")?;
+                out.flush()?;
+                if once {
+                    let _ = ws.close(None).await;
+                    return Ok(());
+                }
+            }
+            tokio_tungstenite::tungstenite::Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    if once {
+        bail!("stream closed before a message arrived");
+    }
+    Ok(())
+}
+
+/// Whether a WebSocket handshake reached an HTTP server that wouldn't
+/// upgrade, as a proxy stripping `Upgrade` does, rather than failing to
+/// connect or being refused the token.
+fn upgrade_refused(e: &anyhow::Error) -> bool {
+    use tokio_tungstenite::tungstenite::Error;
+    match e.downcast_ref::<Error>() {
+        Some(Error::Http(resp)) => resp.status() != reqwest::StatusCode::UNAUTHORIZED,
+        Some(Error::Protocol(_)) => true,
+        _ => false,
+    }
+}
+
+/// Sum of the `eval` metrics in a `Server-Timing` header, or `None` if it
+/// has none.
+fn eval_duration(header: &str) -> Option<Duration> {
+    let evals: Vec<f64> = header
+        .split(',')
+        .filter_map(|metric| {
+            let mut params = metric.trim().split(';');
+            (params.next()? == "eval").then_some(())?;
+            params.find_map(|p| p.trim().strip_prefix("dur=")?.parse().ok())
+        })
+        .collect();
+    (!evals.is_empty()).then(|| Duration::from_secs_f64(evals.iter().sum::<f64>() / 1000.0))
+}
+
+/// `POST /eval` body, leaving `bindings` out when there are none and
+/// `wait_ready` out unless it is set.
+fn eval_body(code: &str, bindings: &serde_json::Map<String, Value>, wait_ready: bool) -> Value {
+    let mut body = serde_json::json!({ "js": code });
+    if !bindings.is_empty() {
+        body["bindings"] = Value::Object(bindings.clone());
+    }
+    if wait_ready {
+        body["wait_ready"] = Value::Bool(true);
+    }
+    body
+}
+
+fn screenshot_query(wait_ready: bool, full_page: bool) -> Vec<(&'static str, &'static str)> {
+    let mut params = Vec::new();
+    if wait_ready {
+        params.push(("wait_ready", "true"));
+    }
+    if full_page {
+        params.push(("full_page", "true"));
+    }
+    params
+}
+
+fn snapshot_query(options: &SnapshotOptions) -> Vec<(&'static str, String)> {
+    let mut params = Vec::new();
+    if options.interactive {
+        params.push(("interactive", "true".to_string()));
+    }
+    if options.locators {
+        params.push(("format", "locators".to_string()));
+    }
+    if options.attrs {
+        params.push(("attrs", "true".to_string()));
+    }
+    if options.include_hidden {
+        params.push(("include_hidden", "true".to_string()));
+    }
+    if options.wait_ready {
+        params.push(("wait_ready", "true".to_string()));
+    }
+    if options.reset_refs {
+        params.push(("reset_refs", "true".to_string()));
+    }
+    if !options.fields.is_empty() {
+        params.push(("fields", options.fields.join(",")));
+    }
+    params
+}
+
+/// Build the multipart form the bridge takes for uploads: the JSON body as
+/// the `json` part and each file as `file:<key>`.
+async fn multipart_form(
+    body: &Value,
+    files: &[FileArg],
+    bytes: bool,
+) -> Result<reqwest::multipart::Form> {
+    let mut form = reqwest::multipart::Form::new()
+        .text("json", body.to_string())
+        .text("encoding", if bytes { "bytes" } else { "base64" });
+    for file in files {
+        let data = tokio::fs::read(&file.path)
+            .await
+            .with_context(|| format!("reading {}", file.path.display()))?;
+        let mut part = reqwest::multipart::Part::bytes(data);
+        if let Some(name) = file.path.file_name() {
+            part = part.file_name(name.to_string_lossy().into_owned());
+        }
+        form = form.part(format!("file:{}", file.key), part);
+    }
+    Ok(form)
+}
+
+/// Gzip a request body over [`GZIP_BODY_THRESHOLD`], returning the
+/// `Content-Encoding` to send with it.
+fn encode_body(body: Vec<u8>) -> Result<(Option<&'static str>, Vec<u8>)> {
+    use std::io::Write;
+
+    if body.len() <= GZIP_BODY_THRESHOLD {
+        return Ok((None, body));
+    }
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    encoder.write_all(&body)?;
+    Ok((Some("gzip"), encoder.finish()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+    #[test]
+    fn request_ids_share_a_session_prefix() {
+        let client = BridgeClient::builder(9229).build();
+        let first = client.request_id();
+        let second = client.request_id();
+        assert_ne!(first, second);
+        assert_eq!(first, format!("{}-1", client.session));
+        assert_eq!(second, format!("{}-2", client.session));
+    }
+
+    #[test]
+    fn verbose_requests_never_show_the_token() {
+        let client = BridgeClient::builder(9229).token(Some("s3cret")).build();
+        let req = client
+            .authed_get("http://127.0.0.1:9229/refs?window=main")
+            .build()
+            .unwrap();
+        let text = describe_request(req.method().as_str(), req.url().as_str(), req.headers());
+        assert!(text.starts_with("> GET http://127.0.0.1:9229/refs?window=main\n"));
+        assert!(text.contains("> x-debug-bridge-token: [redacted]\n"));
+        assert!(text.contains(&format!("> x-request-id: {}-1\n", client.session)));
+        assert!(!text.contains("s3cret"));
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in [
+            ("authorization", "Bearer abc"),
+            ("x-proxy-auth", "abc"),
+            ("x-api-key", "abc"),
+            ("cookie", "session=abc"),
+            ("x-tenant", "acme"),
+        ] {
+            headers.insert(
+                reqwest::header::HeaderName::from_static(name),
+                value.parse().unwrap(),
+            );
+        }
+        let text = describe_request("GET", "ws://127.0.0.1:9229/console", &headers);
+        assert!(!text.contains("abc"), "{text}");
+        assert!(text.contains("> x-proxy-auth: [redacted]\n"));
+        assert!(text.contains("> x-tenant: acme\n"));
+    }
+
+    #[tokio::test]
+    async fn custom_headers_are_parsed_and_sent() {
+        let (name, value) = parse_header("X-Proxy-Auth:  abc ").unwrap();
+        assert_eq!(name, "x-proxy-auth");
+        assert_eq!(value, "abc");
+        assert!(parse_header("X-Proxy-Auth abc").is_err());
+        assert!(parse_header("Bad Name: abc").is_err());
+        assert!(parse_header("X-Ok: line\nbreak").is_err());
+
+        // Echo the request head back as the response body.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0; 4096];
+            let n = socket.read(&mut request).await.unwrap();
+            let body = serde_json::to_string(&String::from_utf8_lossy(&request[..n])).unwrap();
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(body.as_bytes()).await.unwrap();
+        });
+
+        let client = BridgeClient::builder(port)
+            .token(Some("s3cret"))
+            .header(name, value)
+            .header(
+                HeaderName::from_static("x-debug-bridge-token"),
+                HeaderValue::from_static("spoofed"),
+            )
+            .build();
+        let head = client.state().await.unwrap();
+        let head = head.as_str().unwrap().to_ascii_lowercase();
+        assert!(head.contains("x-proxy-auth: abc\r\n"));
+        // The token is set per request and isn't replaced by a custom header.
+        assert!(head.contains("x-debug-bridge-token: s3cret\r\n"));
+        assert!(!head.contains("spoofed"));
+    }
+
+    #[tokio::test]
+    async fn rejected_tokens_are_refreshed_once() {
+        // Accept only the token "fresh"; count the requests that arrive.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let requests = std::sync::Arc::new(AtomicU64::new(0));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0; 4096];
+                let n = socket.read(&mut request).await.unwrap();
+                seen.fetch_add(1, Ordering::Relaxed);
+                let head = String::from_utf8_lossy(&request[..n]).to_ascii_lowercase();
+                let (status, body) = if head.contains("x-debug-bridge-token: fresh\r\n") {
+                    ("200 OK", "{}")
+                } else {
+                    (
+                        "401 Unauthorized",
+                        r#"{"error":"bad token","code":"unauthorized"}"#,
+                    )
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-type: application/json\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let client = BridgeClient::builder(port)
+            .token(Some("stale"))
+            .token_refresh(|| Some("fresh".to_string()))
+            .build();
+        client.state().await.unwrap();
+        assert_eq!(requests.load(Ordering::Relaxed), 2);
+        // The refreshed token sticks.
+        client.state().await.unwrap();
+        assert_eq!(requests.load(Ordering::Relaxed), 3);
+
+        // A refresh that can't do better doesn't loop.
+        let client = BridgeClient::builder(port)
+            .token(Some("stale"))
+            .token_refresh(|| Some("stale".to_string()))
+            .build();
+        assert!(client.state().await.is_err());
+        assert_eq!(requests.load(Ordering::Relaxed), 4);
+
+        // A request sent with the old token still retries after another
+        // request has already stored the fresh one.
+        let client = BridgeClient::builder(port)
+            .token(Some("stale"))
+            .token_refresh(|| Some("fresh".to_string()))
+            .build();
+        let in_flight = client.authed_get(&format!("{}/state", client.base_url));
+        *client.token.write().unwrap() = Some("fresh".to_string());
+        let resp = client.send(in_flight).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        assert_eq!(requests.load(Ordering::Relaxed), 6);
+    }
+
+    #[tokio::test]
+    async fn refused_upgrades_fall_back_to_polling() {
+        // A "proxy" that won't upgrade, in front of a bridge whose second
+        // poll has the message.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0; 4096];
+                let n = socket.read(&mut request).await.unwrap();
+                let head = String::from_utf8_lossy(&request[..n]).to_string();
+                let (status, body) = if head.starts_with("GET /console?") {
+                    ("400 Bad Request", "upgrades not allowed".to_string())
+                } else if head.starts_with("GET /console/poll?window=main ") {
+                    ("200 OK", r#"{"cursor":7,"messages":[]}"#.to_string())
+                } else if head.starts_with("GET /console/poll?since=7&window=main ") {
+                    let message = r#"{"level":"error","message":"boom","window":"main"}"#;
+                    (
+                        "200 OK",
+                        format!(r#"{{"cursor":8,"messages":[{message}]}}"#),
+                    )
+                } else {
+                    ("404 Not Found", head.lines().next().unwrap().to_string())
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let client = BridgeClient::builder(port).build();
+        let message = client
+            .wait_console(Some("main"), |m| m["level"] == "error")
+            .await
+            .unwrap();
+        assert_eq!(message["message"], "boom");
+    }
+
+    #[tokio::test]
+    async fn console_once_skips_the_hook_status() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            use futures_util::SinkExt;
+            use tokio_tungstenite::tungstenite::Message;
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            let status = r#"{"hooked":["main"],"elapsed_ms":3}"#;
+            let message = r#"{"level":"log","args":["ready"],"window":"main"}"#;
+            ws.send(Message::text(status)).await.unwrap();
+            ws.send(Message::text(message)).await.unwrap();
+            while ws.next().await.is_some() {}
+        });
+
+        let client = BridgeClient::builder(port).build();
+        let ws = client
+            .authed_ws(&format!("ws://127.0.0.1:{port}/console"))
+            .await
+            .unwrap();
+        let mut out = Vec::new();
+        print_text_frames(ws, &mut out, true, None).await.unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"level\":\"log\",\"args\":[\"ready\"],\"window\":\"main\"}\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn streams_close_cleanly_after_their_duration() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let bridge = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            while let Some(msg) = ws.next().await {
+                if let tokio_tungstenite::tungstenite::Message::Close(_) = msg.unwrap() {
+                    return true;
+                }
+            }
+            false
+        });
+
+        let client = BridgeClient::builder(port).build();
+        client
+            .event_listen("saved", false, Some(Duration::from_millis(100)))
+            .await
+            .unwrap();
+        assert!(
+            bridge.await.unwrap(),
+            "no close frame before the stream ended"
+        );
+    }
+
+    /// Minimal keep-alive HTTP/1.1 server answering every request like
+    /// `POST /eval`. Returns the port.
+    async fn keep_alive_server() -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut socket = BufReader::new(socket);
+                    loop {
+                        let mut content_length = 0;
+                        loop {
+                            let mut line = String::new();
+                            if socket.read_line(&mut line).await.unwrap_or(0) == 0 {
+                                return;
+                            }
+                            if line == "\r\n" {
+                                break;
+                            }
+                            if let Some(v) =
+                                line.to_ascii_lowercase().strip_prefix("content-length:")
+                            {
+                                content_length = v.trim().parse().unwrap();
+                            }
+                        }
+                        let mut body = vec![0; content_length];
+                        socket.read_exact(&mut body).await.unwrap();
+                        let reply = r#"{"success":true,"value":2}"#;
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{reply}",
+                            reply.len()
+                        );
+                        if socket
+                            .get_mut()
+                            .write_all(response.as_bytes())
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    /// HTTP server answering every request with a `len`-byte PNG body,
+    /// written in 64 KiB chunks. Returns the port.
+    async fn screenshot_server(len: usize) -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut request = [0; 4096];
+                    let _ = socket.read(&mut request).await;
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: image/png\r\nx-debug-bridge-warning: capped\r\ncontent-length: {len}\r\n\r\n"
+                    );
+                    socket.write_all(head.as_bytes()).await.unwrap();
+                    let chunk = [7u8; 64 * 1024];
+                    let mut sent = 0;
+                    while sent < len {
+                        let n = chunk.len().min(len - sent);
+                        if socket.write_all(&chunk[..n]).await.is_err() {
+                            return;
+                        }
+                        sent += n;
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn screenshots_stream_to_the_writer() {
+        let len = 1024 * 1024 + 3;
+        let client = BridgeClient::builder(screenshot_server(len).await).build();
+        let mut out = Vec::new();
+        let warning = client.screenshot_to(false, true, &mut out).await.unwrap();
+        assert_eq!(warning.as_deref(), Some("capped"));
+        assert_eq!(out.len(), len);
+        assert!(out.iter().all(|&b| b == 7));
+    }
+
+    /// Peak resident memory of this process, in KiB.
+    #[cfg(target_os = "linux")]
+    fn peak_rss_kib() -> u64 {
+        std::fs::read_to_string("/proc/self/status")
+            .unwrap()
+            .lines()
+            .find_map(|l| l.strip_prefix("VmHWM:"))
+            .and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok())
+            .unwrap()
+    }
+
+    /// Benchmark: peak memory saving a 256 MiB capture by streaming vs.
+    /// buffering it. Run with
+    /// `cargo test -p tauri-browser-client -- --ignored --nocapture streaming`.
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    #[ignore = "benchmark"]
+    async fn streaming_screenshots_bound_memory() {
+        let client = BridgeClient::builder(screenshot_server(256 * 1024 * 1024).await).build();
+
+        let before = peak_rss_kib();
+        client
+            .screenshot_to(false, true, &mut tokio::io::sink())
+            .await
+            .unwrap();
+        let streamed = peak_rss_kib() - before;
+
+        let before = peak_rss_kib();
+        let (data, _) = client.screenshot(false, true).await.unwrap();
+        let buffered = peak_rss_kib() - before;
+        drop(data);
+
+        println!("256 MiB screenshot: peak +{streamed} KiB streamed, +{buffered} KiB buffered");
+        assert!(streamed * 10 < buffered);
+    }
+
+    #[test]
+    fn server_timing_evals_are_summed() {
+        assert_eq!(
+            eval_duration("eval;dur=12.5, eval;dur=2.5, total;dur=20.0"),
+            Some(Duration::from_millis(15))
+        );
+        assert_eq!(eval_duration("total;dur=3.0"), None);
+        assert_eq!(eval_duration("evaluate;dur=3.0"), None);
+    }
+
+    #[test]
+    fn only_large_bodies_are_gzipped() {
+        let small = br#"{"js":"document.title"}"#.to_vec();
+        assert_eq!(encode_body(small.clone()).unwrap(), (None, small));
+
+        let large = serde_json::to_vec(&serde_json::json!({ "js": "x;".repeat(600_000) })).unwrap();
+        let (encoding, body) = encode_body(large.clone()).unwrap();
+        assert_eq!(encoding, Some("gzip"));
+        assert!(body.len() < GZIP_BODY_THRESHOLD);
+
+        let mut decoded = Vec::new();
+        std::io::Read::read_to_end(&mut flate2::read::GzDecoder::new(&body[..]), &mut decoded)
+            .unwrap();
+        assert_eq!(decoded, large);
+    }
+
+    async fn time_evals(client: &BridgeClient, n: usize) -> Duration {
+        let start = std::time::Instant::now();
+        for _ in 0..n {
+            let result = client.run_js("1 + 1").await.unwrap();
+            assert_eq!(result["value"], 2);
+        }
+        start.elapsed()
+    }
+
+    /// Benchmark: 100 sequential evals over the pooled client vs. a fresh
+    /// connection per request. Run with
+    /// `cargo test -p tauri-browser-client -- --ignored --nocapture pooling`.
+    #[tokio::test]
+    #[ignore = "benchmark"]
+    async fn pooling_reduces_sequential_latency() {
+        let port = keep_alive_server().await;
+        let pooled = BridgeClient::builder(port).build();
+        let unpooled = BridgeClient::builder(port)
+            .pool_max_idle_per_host(0)
+            .build();
+
+        // Warm up both paths once.
+        time_evals(&pooled, 1).await;
+        time_evals(&unpooled, 1).await;
+
+        let pooled_time = time_evals(&pooled, 100).await;
+        let unpooled_time = time_evals(&unpooled, 100).await;
+        println!("100 sequential evals: pooled {pooled_time:?}, unpooled {unpooled_time:?}");
+        assert!(pooled_time < unpooled_time);
+    }
+}
@@ -0,0 +1,193 @@
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+/// A non-success HTTP response from the bridge.
+#[derive(Debug)]
+pub struct ApiError {
+    /// What the CLI was doing, e.g. "snapshot".
+    pub action: &'static str,
+    pub status: reqwest::StatusCode,
+    pub message: String,
+}
+
+impl ApiError {
+    /// Consume a failed response into an error carrying its status and
+    /// message.
+    pub async fn from_response(action: &'static str, resp: reqwest::Response) -> anyhow::Error {
+        let status = resp.status();
+        match resp.text().await {
+            Ok(body) => ApiError {
+                action,
+                status,
+                message: error_message(&body),
+            }
+            .into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+/// The message from a bridge error body, `{"error": "...", "code": "..."}`.
+/// Older bridges answer in plain text, which is used as is.
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["error"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.to_string())
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} failed: {}", self.action, self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// A command failure as printed by `--json-errors`.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ErrorReport {
+    pub error: String,
+    pub kind: &'static str,
+    /// HTTP status, when the bridge answered with an error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+}
+
+impl ErrorReport {
+    pub fn new(err: &anyhow::Error) -> Self {
+        let status = err
+            .chain()
+            .find_map(|e| e.downcast_ref::<ApiError>())
+            .map(|e| e.status.as_u16());
+        Self {
+            error: format!("{err:#}"),
+            kind: kind(err, status),
+            status,
+        }
+    }
+
+    /// Exit code for this failure, so scripts can branch without parsing
+    /// output: 1 other, 3 can't reach the bridge, 4 request rejected (4xx),
+    /// 5 bridge-side failure (5xx). Usage errors exit 2 from clap.
+    pub fn exit_code(&self) -> u8 {
+        match (self.kind, self.status) {
+            ("connection", _) => 3,
+            (_, Some(400..=499)) => 4,
+            (_, Some(500..=599)) => 5,
+            _ => 1,
+        }
+    }
+}
+
+fn kind(err: &anyhow::Error, status: Option<u16>) -> &'static str {
+    if let Some(status) = status {
+        return match status {
+            400 => "bad_request",
+            401 => "unauthorized",
+            403 => "forbidden",
+            404 => "not_found",
+            409 => "conflict",
+            501 => "not_implemented",
+            503 => "unavailable",
+            504 => "timeout",
+            _ if status < 500 => "client_error",
+            _ => "server_error",
+        };
+    }
+    let unreachable = err.chain().any(|e| {
+        if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+            return e.is_connect();
+        }
+        e.downcast_ref::<std::io::Error>()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::ConnectionRefused)
+    });
+    if unreachable {
+        return "connection";
+    }
+    if err.chain().any(|e| {
+        e.downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_timeout())
+    }) {
+        return "timeout";
+    }
+    "error"
+}
+
+/// Print a command failure to stderr, as JSON with `--json-errors`, and
+/// return the matching exit code.
+pub fn report(err: &anyhow::Error, json: bool) -> ExitCode {
+    let report = ErrorReport::new(err);
+    if json {
+        eprintln!("{}", serde_json::to_string(&report).unwrap_or_default());
+    } else {
+        eprintln!("Error: {err:?}");
+    }
+    ExitCode::from(report.exit_code())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    fn api_error(status: u16) -> anyhow::Error {
+        ApiError {
+            action: "click",
+            status: reqwest::StatusCode::from_u16(status).unwrap(),
+            message: "element not found".to_string(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn api_errors_report_status_and_kind() {
+        let report = ErrorReport::new(&api_error(404));
+        assert_eq!(
+            report,
+            ErrorReport {
+                error: "click failed: element not found".to_string(),
+                kind: "not_found",
+                status: Some(404),
+            }
+        );
+        assert_eq!(report.exit_code(), 4);
+        assert_eq!(ErrorReport::new(&api_error(504)).exit_code(), 5);
+        assert_eq!(ErrorReport::new(&api_error(504)).kind, "timeout");
+    }
+
+    #[test]
+    fn error_bodies_yield_their_message() {
+        assert_eq!(
+            error_message(r#"{"error":"window 'prefs' not found","code":"not_found"}"#),
+            "window 'prefs' not found"
+        );
+        assert_eq!(
+            error_message("eval timed out after 10s"),
+            "eval timed out after 10s"
+        );
+    }
+
+    #[test]
+    fn unreachable_bridge_is_a_connection_error() {
+        let err = Err::<(), _>(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+            .context("connecting to 127.0.0.1:9229")
+            .unwrap_err();
+        let report = ErrorReport::new(&err);
+        assert_eq!(report.kind, "connection");
+        assert_eq!(report.status, None);
+        assert_eq!(report.exit_code(), 3);
+    }
+
+    #[test]
+    fn other_errors_exit_one() {
+        let report = ErrorReport::new(&anyhow::anyhow!("invalid JSON payload"));
+        assert_eq!(report.kind, "error");
+        assert_eq!(report.exit_code(), 1);
+        assert_eq!(
+            serde_json::to_string(&report).unwrap(),
+            r#"{"error":"invalid JSON payload","kind":"error"}"#
+        );
+    }
+}
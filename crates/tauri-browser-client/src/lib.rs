@@ -0,0 +1,36 @@
+//! Rust client for the [`tauri-plugin-debug-bridge`] HTTP/WS API, the same
+//! one the `tauri-browser` CLI is built on. Use it to drive a running Tauri
+//! app from integration tests or your own tools.
+//!
+//! ```no_run
+//! use tauri_browser_client::{BridgeClient, SnapshotOptions};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let client = BridgeClient::builder(9229).token(Some("a1b2c3...")).build();
+//! let snapshot = client
+//!     .snapshot(SnapshotOptions {
+//!         interactive: true,
+//!         ..Default::default()
+//!     })
+//!     .await?;
+//! client.click("@e3").await?;
+//! let title = client.run_js("document.title").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Methods return the bridge's JSON responses as [`serde_json::Value`].
+//! Failed requests return an [`ApiError`] (inside the [`anyhow::Error`])
+//! carrying the HTTP status and the bridge's message.
+//!
+//! [`tauri-plugin-debug-bridge`]: https://crates.io/crates/tauri-plugin-debug-bridge
+
+pub mod client;
+pub mod error;
+pub mod output;
+
+pub use client::{
+    BenchOp, BridgeClient, BridgeClientBuilder, DryRunRequest, FileArg, SnapshotOptions, Timing,
+    WindowScreenshots, WindowStateChange,
+};
+pub use error::ApiError;
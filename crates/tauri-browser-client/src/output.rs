@@ -0,0 +1,256 @@
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+
+/// File every printed value is also written to as JSON (`--tee`).
+static TEE: OnceLock<Mutex<std::fs::File>> = OnceLock::new();
+
+/// Output format for responses.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Format {
+    /// Human-readable text (default)
+    Text,
+    /// JSON output for programmatic consumption
+    Json,
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Text => write!(f, "text"),
+            Format::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// Also write every printed value to `path` as JSON, whatever `--format`
+/// says. Truncates the file; each value is one compact JSON document per
+/// line, so a single command's output is a valid JSON file.
+pub fn tee_to(path: &Path) -> std::io::Result<()> {
+    let file = std::fs::File::create(path)?;
+    let _ = TEE.set(Mutex::new(file));
+    Ok(())
+}
+
+/// Write a value to the `--tee` file, if there is one. `print` does this
+/// already; call it directly for output printed some other way.
+pub fn tee(value: &impl Serialize) {
+    if let Some(file) = TEE.get()
+        && let Err(e) = write_json_line(&mut *file.lock().unwrap(), value)
+    {
+        eprintln!("warning: writing --tee output failed: {e}");
+    }
+}
+
+fn write_json_line(mut out: impl Write, value: &impl Serialize) -> std::io::Result<()> {
+    serde_json::to_writer(&mut out, value)?;
+    out.write_all(b"\n")?;
+    out.flush()
+}
+
+/// Print a serializable value in the requested format.
+pub fn print(value: &impl Serialize, format: &Format) {
+    tee(value);
+    match format {
+        Format::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(value)
+                    .unwrap_or_else(|e| format!("{{\"error\":\"{e}\"}}")),
+            );
+        }
+        Format::Text => {
+            // For text format, use a compact representation.
+            // Specific commands can override this with custom formatting.
+            let json = serde_json::to_value(value).unwrap_or(serde_json::Value::Null);
+            print_value(&json, 0);
+        }
+    }
+}
+
+/// Print a dry run's scripts: as-is in text mode, ready to paste into
+/// `run-js` or devtools; JSON unchanged.
+pub fn print_dry_run(dry_run: &serde_json::Value, format: &Format) {
+    match format {
+        Format::Json => print(dry_run, format),
+        Format::Text => {
+            tee(dry_run);
+            let scripts: Vec<&str> = dry_run["scripts"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|s| s.as_str())
+                .collect();
+            if scripts.is_empty() {
+                eprintln!("(no scripts would be injected)");
+            }
+            println!("{}", scripts.join("\n\n"));
+        }
+    }
+}
+
+/// Print a snapshot: in text mode with a line counting its elements first,
+/// JSON unchanged.
+pub fn print_snapshot(snapshot: &serde_json::Value, format: &Format) {
+    if let (Format::Text, Some(summary)) = (format, snapshot_summary(snapshot)) {
+        println!("{summary}");
+    }
+    print(snapshot, format);
+}
+
+/// "120 elements, 14 interactive", for snapshots that carry counts.
+fn snapshot_summary(snapshot: &serde_json::Value) -> Option<String> {
+    let elements = snapshot["element_count"].as_u64()?;
+    let interactive = snapshot["interactive_count"].as_u64().unwrap_or(0);
+    let plural = if elements == 1 { "" } else { "s" };
+    let truncated = if snapshot["truncated"].as_bool() == Some(true) {
+        " (truncated)"
+    } else {
+        ""
+    };
+    Some(format!(
+        "{elements} element{plural}, {interactive} interactive{truncated}"
+    ))
+}
+
+/// Print the `windows` listing: a table in text mode, JSON unchanged.
+pub fn print_windows(windows: &serde_json::Value, format: &Format) {
+    match format {
+        Format::Json => print(windows, format),
+        Format::Text => {
+            tee(windows);
+            print!("{}", windows_table(windows));
+        }
+    }
+}
+
+/// One row per window, columns padded to the widest cell. The URL comes last
+/// so long ones don't push the other columns around.
+fn windows_table(windows: &serde_json::Value) -> String {
+    let flag = |v: &serde_json::Value| {
+        if v.as_bool() == Some(true) {
+            "yes"
+        } else {
+            "no"
+        }
+    };
+    let mut rows = vec![["LABEL", "TITLE", "VISIBLE", "FOCUSED", "URL"].map(String::from)];
+    for w in windows.as_array().into_iter().flatten() {
+        rows.push([
+            w["label"].as_str().unwrap_or_default().to_string(),
+            w["title"].as_str().unwrap_or_default().to_string(),
+            flag(&w["is_visible"]).to_string(),
+            flag(&w["is_focused"]).to_string(),
+            w["url"].as_str().unwrap_or_default().to_string(),
+        ]);
+    }
+
+    let mut widths = [0; 4];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut table = String::new();
+    for row in &rows {
+        let mut line = String::new();
+        for (width, cell) in widths.iter().zip(row) {
+            line.push_str(&format!("{cell:<width$}  "));
+        }
+        line.push_str(&row[4]);
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+fn print_value(value: &serde_json::Value, indent: usize) {
+    let pad = "  ".repeat(indent);
+    match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                match v {
+                    serde_json::Value::Object(_) | serde_json::Value::Array(_) => {
+                        println!("{pad}{k}:");
+                        print_value(v, indent + 1);
+                    }
+                    _ => {
+                        println!("{pad}{k}: {}", format_scalar(v));
+                    }
+                }
+            }
+        }
+        serde_json::Value::Array(arr) => {
+            for item in arr {
+                print_value(item, indent);
+                if indent == 0 {
+                    println!();
+                }
+            }
+        }
+        _ => println!("{pad}{}", format_scalar(value)),
+    }
+}
+
+fn format_scalar(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => "null".to_string(),
+        serde_json::Value::Bool(b) => b.to_string(),
+        serde_json::Value::Number(n) => n.to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_render_as_a_table() {
+        let windows = serde_json::json!([
+            {"label": "main", "title": "My App", "url": "tauri://localhost/", "is_visible": true, "is_focused": true},
+            {"label": "settings", "title": null, "url": null, "is_visible": false, "is_focused": false},
+        ]);
+        assert_eq!(
+            windows_table(&windows),
+            "LABEL     TITLE   VISIBLE  FOCUSED  URL\n\
+             main      My App  yes      yes      tauri://localhost/\n\
+             settings          no       no\n"
+        );
+    }
+
+    #[test]
+    fn snapshot_summaries_count_elements() {
+        let snapshot = serde_json::json!({"element_count": 120, "interactive_count": 14});
+        assert_eq!(
+            snapshot_summary(&snapshot).unwrap(),
+            "120 elements, 14 interactive"
+        );
+        let partial =
+            serde_json::json!({"element_count": 1, "interactive_count": 1, "truncated": true});
+        assert_eq!(
+            snapshot_summary(&partial).unwrap(),
+            "1 element, 1 interactive (truncated)"
+        );
+        assert_eq!(snapshot_summary(&serde_json::json!([])), None, "locators");
+    }
+
+    #[test]
+    fn tee_lines_are_json_documents() {
+        let mut out = Vec::new();
+        write_json_line(&mut out, &serde_json::json!({"title": "Home", "n": 2})).unwrap();
+        write_json_line(&mut out, &"plain text").unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let docs: Vec<serde_json::Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(docs[0]["title"], "Home");
+        assert_eq!(docs[1], "plain text");
+    }
+}
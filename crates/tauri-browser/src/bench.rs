@@ -0,0 +1,165 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::time::Duration;
+
+use tauri_browser_client::client::{BenchOp, BridgeClient};
+
+/// Width of the longest histogram bar, in characters.
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Latency summary, in milliseconds.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Stats {
+    pub min: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl Stats {
+    /// Nearest-rank percentiles of `samples`; `None` when there are none.
+    pub fn of(samples: &[f64]) -> Option<Self> {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let rank = |p: f64| sorted[((p * sorted.len() as f64).ceil() as usize).max(1) - 1];
+        Some(Self {
+            min: *sorted.first()?,
+            median: rank(0.5),
+            p95: rank(0.95),
+            max: *sorted.last()?,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    pub operation: &'static str,
+    pub iterations: usize,
+    /// Round trips as the CLI saw them.
+    pub client_ms: Stats,
+    /// Time inside the webview, from script injection to callback, as the
+    /// bridge reported it. Absent for operations that inject no script.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_eval_ms: Option<Stats>,
+    #[serde(skip)]
+    samples_ms: Vec<f64>,
+}
+
+fn ms(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+/// Time `iterations` runs of `op`, after `warmup` untimed ones.
+pub async fn run(
+    client: &BridgeClient,
+    operation: &'static str,
+    op: &BenchOp,
+    iterations: usize,
+    warmup: usize,
+) -> Result<Report> {
+    for _ in 0..warmup {
+        client.timed(op).await?;
+    }
+    let mut totals = Vec::with_capacity(iterations);
+    let mut evals = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let timing = client.timed(op).await?;
+        totals.push(ms(timing.total));
+        evals.extend(timing.eval.map(ms));
+    }
+    Ok(Report {
+        operation,
+        iterations,
+        client_ms: Stats::of(&totals).ok_or_else(|| anyhow::anyhow!("no iterations to time"))?,
+        server_eval_ms: Stats::of(&evals),
+        samples_ms: totals,
+    })
+}
+
+/// The report as text: the summaries, then a histogram of round trips.
+pub fn render(report: &Report) -> String {
+    let line = |label: &str, s: &Stats| {
+        format!(
+            "{label}: min {:.1}ms  median {:.1}ms  p95 {:.1}ms  max {:.1}ms\n",
+            s.min, s.median, s.p95, s.max
+        )
+    };
+    let mut out = line(
+        &format!("{} x{}", report.operation, report.iterations),
+        &report.client_ms,
+    );
+    if let Some(eval) = &report.server_eval_ms {
+        out.push_str(&line("  in webview", eval));
+    }
+    out.push('\n');
+    out.push_str(&histogram(&report.samples_ms, 10));
+    out
+}
+
+/// `buckets` equal-width bins from the fastest to the slowest sample, one
+/// line each: `  1.2 -   1.5 ms | ####### 7`.
+fn histogram(samples: &[f64], buckets: usize) -> String {
+    let Some(stats) = Stats::of(samples) else {
+        return String::new();
+    };
+    let span = stats.max - stats.min;
+    let buckets = if span > 0.0 { buckets.max(1) } else { 1 };
+    let mut counts = vec![0usize; buckets];
+    for &s in samples {
+        let i = if span > 0.0 {
+            (((s - stats.min) / span) * buckets as f64) as usize
+        } else {
+            0
+        };
+        counts[i.min(buckets - 1)] += 1;
+    }
+    let tallest = counts.iter().copied().max().unwrap_or(1).max(1);
+    let width = span / buckets as f64;
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            let from = stats.min + width * i as f64;
+            let bar = "#".repeat((count * HISTOGRAM_WIDTH).div_ceil(tallest));
+            format!("{from:>8.1} - {:>8.1} ms | {bar} {count}\n", from + width)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_use_nearest_rank_percentiles() {
+        let samples: Vec<f64> = (1..=20).rev().map(f64::from).collect();
+        let stats = Stats::of(&samples).unwrap();
+        assert_eq!(
+            stats,
+            Stats {
+                min: 1.0,
+                median: 10.0,
+                p95: 19.0,
+                max: 20.0
+            }
+        );
+        assert_eq!(Stats::of(&[]), None);
+        assert_eq!(Stats::of(&[4.0]).unwrap().p95, 4.0);
+    }
+
+    #[test]
+    fn histogram_bins_every_sample() {
+        let text = histogram(&[1.0, 1.1, 1.2, 3.0], 2);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(
+            lines[0].ends_with(&format!("| {} 3", "#".repeat(40))),
+            "{text}"
+        );
+        assert!(
+            lines[1].ends_with(&format!("| {} 1", "#".repeat(14))),
+            "{text}"
+        );
+        assert_eq!(histogram(&[2.0, 2.0], 10).lines().count(), 1);
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// DNS-SD service type the plugin advertises under (its `advertise` option).
+const SERVICE_TYPE: &str = "_tauri-debug._tcp.local.";
+
+/// A bridge found on the network.
+#[derive(Debug, Serialize)]
+pub struct Bridge {
+    pub identifier: String,
+    pub host: String,
+    pub addresses: Vec<String>,
+    pub port: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl From<&ServiceInfo> for Bridge {
+    fn from(info: &ServiceInfo) -> Self {
+        let mut addresses: Vec<String> = info
+            .get_addresses()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        addresses.sort();
+        Self {
+            identifier: info
+                .get_property_val_str("identifier")
+                .unwrap_or_else(|| info.get_fullname())
+                .to_string(),
+            host: info.get_hostname().trim_end_matches('.').to_string(),
+            addresses,
+            port: info.get_port(),
+            version: info.get_property_val_str("version").map(str::to_string),
+        }
+    }
+}
+
+/// Browse for advertised bridges for `timeout`, returning each one found,
+/// sorted by identifier.
+pub async fn browse(timeout: Duration) -> Result<Vec<Bridge>> {
+    tokio::task::spawn_blocking(move || {
+        let daemon = ServiceDaemon::new().context("starting mDNS daemon")?;
+        let events = daemon.browse(SERVICE_TYPE).context("browsing mDNS")?;
+        let deadline = Instant::now() + timeout;
+        let mut found = BTreeMap::new();
+        while let Ok(event) = events.recv_deadline(deadline) {
+            if let ServiceEvent::ServiceResolved(info) = event {
+                found.insert(info.get_fullname().to_string(), Bridge::from(&info));
+            }
+        }
+        let _ = daemon.shutdown();
+        let mut bridges: Vec<Bridge> = found.into_values().collect();
+        bridges.sort_by(|a, b| a.identifier.cmp(&b.identifier));
+        Ok(bridges)
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bridges_come_from_service_records() {
+        let properties = [("identifier", "com.example.app"), ("version", "0.4.0")];
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            "com-example-app",
+            "com-example-app.local.",
+            "192.168.1.20",
+            9229,
+            &properties[..],
+        )
+        .unwrap();
+        let bridge = Bridge::from(&info);
+        assert_eq!(bridge.identifier, "com.example.app");
+        assert_eq!(bridge.host, "com-example-app.local");
+        assert_eq!(bridge.addresses, ["192.168.1.20"]);
+        assert_eq!(bridge.port, 9229);
+        assert_eq!(bridge.version.as_deref(), Some("0.4.0"));
+    }
+}
@@ -1,9 +1,20 @@
 use anyhow::{Context, Result, bail};
 use clap::{Parser, Subcommand};
-use std::path::Path;
+use std::io::{BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 
-mod client;
-mod output;
+mod bench;
+#[cfg(feature = "mdns")]
+mod discover;
+mod profile;
+mod repl;
+mod report;
+mod script;
+mod serve;
+mod session;
+mod snapshot_html;
+
+use tauri_browser_client::{client, error, output};
 
 /// Well-known directory where the plugin writes discovery files.
 const DISCOVERY_DIR: &str = "/tmp/tauri-debug-bridge";
@@ -19,18 +30,49 @@ struct Cli {
     #[arg(short, long, global = true)]
     port: Option<u16>,
 
+    /// Debug bridge Unix socket (overrides discovery)
+    #[arg(long, global = true, conflicts_with = "port")]
+    socket: Option<PathBuf>,
+
     /// App identifier to connect to (reads from discovery file)
     #[arg(short = 'a', long, global = true)]
     app: Option<String>,
 
+    /// Named profile from ~/.config/tauri-browser/config.toml
+    #[arg(long, global = true, env = "TAURI_BROWSER_PROFILE")]
+    profile: Option<String>,
+
     /// Auth token (overrides discovery)
     #[arg(short = 't', long, global = true, env = "TAURI_BROWSER_TOKEN")]
     token: Option<String>,
 
+    /// Extra header for every request, e.g. for a proxy in front of the
+    /// bridge (repeatable)
+    #[arg(long = "header", global = true, value_name = "NAME: VALUE", value_parser = parse_header)]
+    headers: Vec<(client::HeaderName, client::HeaderValue)>,
+
     /// Output format
     #[arg(short, long, default_value = "text", global = true)]
     format: output::Format,
 
+    /// On failure, print `{"error", "kind", "status"}` JSON to stderr
+    #[arg(long, global = true)]
+    json_errors: bool,
+
+    /// Also write each result as JSON to this file, whatever --format is
+    #[arg(long, global = true, value_name = "FILE")]
+    tee: Option<PathBuf>,
+
+    /// Append each scriptable command and its result to this session file,
+    /// for `replay`
+    #[arg(long, global = true, value_name = "FILE", env = "TAURI_BROWSER_RECORD")]
+    record: Option<PathBuf>,
+
+    /// Print each request (method, URL, headers with the token redacted) and
+    /// its response status to stderr
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -38,12 +80,32 @@ struct Cli {
 #[derive(Subcommand)]
 enum Command {
     /// Check connection to debug bridge
-    Connect,
+    Connect {
+        /// Keep running and stream bridge-internal errors (bind failures, eval timeouts)
+        #[arg(long)]
+        watch: bool,
+    },
 
     /// Capture webview screenshot
     Screenshot {
         /// Save to file instead of stdout
         path: Option<String>,
+        /// Wait for the document to finish loading, then for web fonts and
+        /// images (adds up to 5s)
+        #[arg(long)]
+        wait_ready: bool,
+        /// Capture the whole scrollable page, not just the viewport
+        #[arg(long)]
+        full_page: bool,
+        /// Print the JS the capture would inject instead of capturing
+        #[arg(long)]
+        dry_run: bool,
+        /// Capture every open window, one <label>.png each, in --dir
+        #[arg(long, conflicts_with_all = ["path", "wait_ready", "full_page", "dry_run"])]
+        all: bool,
+        /// Directory for --all captures (created if missing)
+        #[arg(long, value_name = "DIR", default_value = ".", requires = "all")]
+        dir: PathBuf,
     },
 
     /// Dump DOM accessibility tree with element refs
@@ -51,30 +113,189 @@ enum Command {
         /// Only show interactive elements
         #[arg(short, long)]
         interactive: bool,
+        /// Emit a flat list of interactive elements with suggested Playwright
+        /// locators (role + name, then text, then CSS) instead of the tree
+        #[arg(long)]
+        locators: bool,
+        /// Include each element's id and class attributes
+        #[arg(long)]
+        attrs: bool,
+        /// Keep hidden elements, marking each element visible or not
+        #[arg(long)]
+        include_hidden: bool,
+        /// Wait for document.readyState to be "complete" before walking
+        #[arg(long)]
+        wait_ready: bool,
+        /// Remove refs left by earlier snapshots before assigning new ones
+        #[arg(long)]
+        reset_refs: bool,
+        /// Keep only these element fields, e.g. `ref,text` (children always
+        /// stay): tag, ref, role, text, name, value, id, class, interactive,
+        /// visible, testid, aria
+        #[arg(long, value_delimiter = ',', conflicts_with = "locators")]
+        fields: Vec<String>,
+        /// Print the snapshot JS instead of running it
+        #[arg(long)]
+        dry_run: bool,
+        /// Write a standalone HTML report (collapsible tree, refs highlighted)
+        /// to this file instead of printing the snapshot
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["locators", "dry_run"])]
+        html: Option<PathBuf>,
+        /// Embed a screenshot taken alongside in the --html report
+        #[arg(long, requires = "html")]
+        with_screenshot: bool,
+    },
+
+    /// One-call page overview: URL, title, load state, focus, element counts
+    Pagestate {
+        /// Window label (defaults to the app's default window, usually "main")
+        #[arg(short, long)]
+        window: Option<String>,
+    },
+
+    /// List @refs from earlier snapshots that are still in the DOM
+    Refs {
+        /// Window label (defaults to the app's default window, usually "main")
+        #[arg(short, long)]
+        window: Option<String>,
+        #[command(subcommand)]
+        action: Option<RefsAction>,
     },
 
     /// Click an element by @ref or CSS selector
     Click {
-        /// Element ref (@e1) or CSS selector
+        /// Element ref (@e1), testid=ID, role=button[name="Save"], text="Save", or CSS selector
         selector: String,
+        /// Print the JS that would be injected instead of clicking
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Fill an input element with text
     Fill {
-        /// Element ref (@e1) or CSS selector
+        /// Element ref (@e1), testid=ID, role=button[name="Save"], text="Save", or CSS selector
         selector: String,
         /// Text to fill
         text: String,
+        /// Print the JS that would be injected instead of filling
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Select text in an input, textarea, or contenteditable (all of it by
+    /// default) and print the selection
+    SelectText {
+        /// Element ref (@e1), testid=ID, role=button[name="Save"], text="Save", or CSS selector
+        selector: String,
+        /// Offset of the first character to select
+        #[arg(long)]
+        start: Option<u32>,
+        /// Offset just past the last character to select
+        #[arg(long)]
+        end: Option<u32>,
+    },
+
+    /// Show computed CSS styles of an element
+    Styles {
+        /// Element ref (@e1), testid=ID, role=button[name="Save"], text="Save", or CSS selector
+        selector: String,
+        /// Property names to return (all when omitted)
+        props: Vec<String>,
+    },
+
+    /// Check that an element is visible, in the viewport, and not covered,
+    /// so a click would land on it
+    Clickable {
+        /// Element ref (@e1), testid=ID, role=button[name="Save"], text="Save", or CSS selector
+        selector: String,
+    },
+
+    /// Print the raw HTML of the page or an element
+    Html {
+        /// Element ref (@e1) or CSS selector (whole document when omitted)
+        selector: Option<String>,
+        /// Print innerHTML instead of outerHTML
+        #[arg(long)]
+        inner: bool,
+    },
+
+    /// Read or write clipboard text
+    Clipboard {
+        #[command(subcommand)]
+        action: ClipboardAction,
+    },
+
+    /// Show or set the webview zoom factor (1.0 = 100%)
+    Zoom {
+        /// New zoom factor, clamped to 0.25-5.0
+        factor: Option<f64>,
+        /// Window label (defaults to the app's default window, usually "main")
+        #[arg(short, long)]
+        window: Option<String>,
     },
 
     /// Execute JavaScript in the webview
     RunJs {
         /// JavaScript code to execute
-        code: String,
+        #[arg(required_unless_present = "file")]
+        code: Option<String>,
+        /// Read the code from a file instead ("-" for stdin)
+        #[arg(long, conflicts_with = "code")]
+        file: Option<String>,
+        /// Expose a value to the code as `__env.KEY` (repeatable). VALUE is
+        /// parsed as JSON, falling back to a string
+        #[arg(long = "bind", value_name = "KEY=VALUE")]
+        bind: Vec<String>,
+        /// Wait for document.readyState to be "complete" before running
+        #[arg(long)]
+        wait_ready: bool,
+        /// Print the wrapped JS the bridge would inject instead of running it
+        #[arg(long)]
+        dry_run: bool,
+        /// Print the value verbatim instead of as JSON: strings unquoted,
+        /// for piping HTML or CSV built in the page
+        #[arg(long, conflicts_with = "dry_run")]
+        raw: bool,
     },
 
+    /// Interactive JavaScript REPL (.help for meta-commands)
+    Repl,
+
     /// View console output
-    Console,
+    Console {
+        /// Print the last N buffered messages and exit instead of streaming
+        #[arg(long, value_name = "N")]
+        tail: Option<usize>,
+        /// Only this window's messages ("*" for all; without it, the default
+        /// window is captured and every captured window is shown)
+        #[arg(short, long)]
+        window: Option<String>,
+        /// Exit after the first message
+        #[arg(long, conflicts_with = "tail")]
+        once: bool,
+        /// With --once, give up (and fail) after this many seconds
+        #[arg(long, value_name = "SECS", requires = "once")]
+        timeout: Option<u64>,
+        /// Stop after this many seconds, closing the stream cleanly
+        #[arg(long, value_name = "SECS", conflicts_with = "tail")]
+        duration: Option<u64>,
+    },
+
+    /// Wait for a console message containing PATTERN, print it, and exit
+    /// (fails on timeout). Start it before triggering the log line.
+    WaitConsole {
+        /// Text to look for in the message
+        pattern: String,
+        /// Treat PATTERN as a regular expression
+        #[arg(long)]
+        regex: bool,
+        /// Only this window's messages ("*" for all)
+        #[arg(short, long)]
+        window: Option<String>,
+        /// Give up (and fail) after this many seconds
+        #[arg(long, value_name = "SECS", default_value_t = 30)]
+        timeout: u64,
+    },
 
     /// View JavaScript errors
     Errors,
@@ -85,6 +306,44 @@ enum Command {
         command: String,
         /// JSON arguments
         args: Option<String>,
+        /// Argument as key=value (repeatable). Values are parsed as JSON when
+        /// possible (numbers, booleans, objects), otherwise taken as strings.
+        #[arg(long = "arg", value_name = "KEY=VALUE", conflicts_with = "args")]
+        arg: Vec<String>,
+        /// Attach a file's contents as argument KEY (repeatable; base64 unless --bytes)
+        #[arg(long = "file-arg", value_name = "KEY=PATH")]
+        file_arg: Vec<String>,
+        /// Send --file-arg contents as byte arrays (for Vec<u8> arguments)
+        #[arg(long, requires = "file_arg")]
+        bytes: bool,
+    },
+
+    /// Call several Tauri commands in order
+    InvokeBatch {
+        /// File with a JSON array of {"command", "args"} objects (stdin if omitted or "-")
+        file: Option<String>,
+        /// Stop at the first failing call
+        #[arg(long)]
+        stop_on_error: bool,
+    },
+
+    /// Run a JSON script of steps; {"parallel": [...]} groups run concurrently
+    Script {
+        /// Script file (stdin if omitted or "-")
+        file: Option<String>,
+        /// Max steps of a parallel group in flight at once
+        #[arg(long, default_value_t = 4)]
+        parallel: usize,
+    },
+
+    /// Re-run a session recorded with --record and compare the results
+    Replay {
+        /// Session file (stdin if omitted or "-")
+        file: Option<String>,
+        /// Keep the recorded timing, sped up by this factor (1 = real time);
+        /// steps run back to back without it
+        #[arg(long, value_parser = parse_speed)]
+        speed: Option<f64>,
     },
 
     /// Dump managed state
@@ -99,15 +358,127 @@ enum Command {
         action: EventAction,
     },
 
+    /// Pre-arm responses for native dialogs
+    Dialog {
+        #[command(subcommand)]
+        action: DialogAction,
+    },
+
+    /// Inspect and trigger native menu items
+    Menu {
+        #[command(subcommand)]
+        action: MenuAction,
+    },
+
     /// Stream Rust-side logs
     Logs {
         /// Minimum log level
         #[arg(long, default_value = "info")]
         level: String,
+        /// Stop after this many seconds, closing the stream cleanly
+        #[arg(long, value_name = "SECS")]
+        duration: Option<u64>,
+    },
+
+    /// List open windows, or show or change one's state
+    Windows {
+        #[command(subcommand)]
+        action: Option<WindowsAction>,
+    },
+
+    /// Show the app's data, config, cache, and resource directories
+    Paths,
+
+    /// Show the app's name, version, identifier, and Tauri version
+    App,
+
+    /// Show whether the app is a dev or bundled build, and what it runs as
+    Env,
+
+    /// List the bridge's endpoints, which are usable, and its optional features
+    Capabilities,
+
+    /// Time repeated runs of an operation and report latency percentiles
+    Bench {
+        /// Operation to time
+        #[arg(value_enum)]
+        operation: BenchTarget,
+        /// Timed runs
+        #[arg(short = 'n', long, default_value_t = 20, value_parser = clap::value_parser!(u64).range(1..))]
+        iterations: u64,
+        /// Untimed runs first, to warm up the connection and the page
+        #[arg(long, default_value_t = 1)]
+        warmup: u64,
+        /// Code to run for `eval`
+        #[arg(long, default_value = "document.title")]
+        js: String,
+    },
+
+    /// Collect health, config (redacted), windows, a snapshot, a screenshot,
+    /// recent console output, and capabilities into a directory to attach
+    /// to a bug report
+    Report {
+        /// Directory to write (must be new or empty; defaults to
+        /// ./tauri-browser-report-<timestamp>)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Serve a browser inspector on localhost that relays requests to the
+    /// bridge, adding the token so the page never sees it
+    Serve {
+        /// Local port to listen on (0 for any free port)
+        #[arg(long, default_value_t = 9230)]
+        listen: u16,
+    },
+
+    /// Wait for something to happen in the webview
+    Wait {
+        #[command(subcommand)]
+        target: WaitTarget,
+    },
+
+    /// List bridges advertised on the local network (plugin `advertise` option)
+    #[cfg(feature = "mdns")]
+    Discover {
+        /// How long to listen for advertisements, in seconds
+        #[arg(long, default_value_t = 3)]
+        timeout: u64,
+    },
+
+    /// Dump a tauri-plugin-store store (plugin needs the `store` feature)
+    Store {
+        /// Store path as the app loads it, e.g. "settings.json"
+        path: String,
     },
 
-    /// List open windows
-    Windows,
+    /// Stop the debug bridge server (the app keeps running; needs allow_shutdown)
+    Shutdown,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum BenchTarget {
+    Eval,
+    /// Interactive-only snapshot, as agents usually take
+    Snapshot,
+    Screenshot,
+}
+
+#[derive(Subcommand)]
+enum WaitTarget {
+    /// Wait until the webview navigates and the new page finishes loading
+    Navigation {
+        /// Wait for a URL containing this instead (succeeds if already there)
+        #[arg(long)]
+        url_contains: Option<String>,
+        /// Give up (and fail) after this many seconds
+        #[arg(long, value_name = "SECS", default_value_t = 5)]
+        timeout: u64,
+        /// Count page loads from this `loads_started` value, as printed by
+        /// `click`, to catch a navigation that began before the wait
+        #[arg(long, value_name = "N")]
+        since: Option<u64>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -118,159 +489,1474 @@ enum EventAction {
         name: String,
         /// JSON payload
         payload: Option<String>,
+        /// Wait for the frontend to emit `<name>:ack` and print its payload
+        #[arg(long = "await")]
+        wait: bool,
+        /// Seconds to wait for the ack (with --await)
+        #[arg(long, requires = "wait", default_value_t = 10)]
+        timeout: u64,
+        /// Attach a file's contents to the payload as KEY (repeatable; base64 unless --bytes)
+        #[arg(long = "file-arg", value_name = "KEY=PATH", conflicts_with = "wait")]
+        file_arg: Vec<String>,
+        /// Send --file-arg contents as byte arrays
+        #[arg(long, requires = "file_arg")]
+        bytes: bool,
     },
     /// Listen for events (streams via WebSocket)
     Listen {
         /// Event name
         name: String,
+        /// Exit after the first event
+        #[arg(long)]
+        once: bool,
+        /// With --once, give up (and fail) after this many seconds
+        #[arg(long, value_name = "SECS", requires = "once")]
+        timeout: Option<u64>,
+        /// Stop after this many seconds, closing the stream cleanly
+        #[arg(long, value_name = "SECS")]
+        duration: Option<u64>,
     },
     /// List known events
     List,
 }
 
-/// Read port and token from a discovery file written by the plugin.
-fn read_discovery_file(path: &Path) -> Option<(u16, String)> {
+#[derive(Subcommand)]
+enum RefsAction {
+    /// Remove every ref from the DOM (refs stop working until the next snapshot)
+    Clear {
+        /// Window label (defaults to the app's default window, usually "main")
+        #[arg(short, long)]
+        window: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum WindowsAction {
+    /// Show whether a window is always on top, fullscreen, and maximized,
+    /// after applying any of the flags given
+    State {
+        /// Window label (defaults to the app's default window, usually "main")
+        #[arg(short, long)]
+        window: Option<String>,
+        /// Keep the window above others (true) or not (false)
+        #[arg(long, value_name = "BOOL")]
+        on_top: Option<bool>,
+        /// Enter (true) or leave (false) fullscreen
+        #[arg(long, value_name = "BOOL")]
+        fullscreen: Option<bool>,
+        /// Maximize (true) or restore (false) the window
+        #[arg(long, value_name = "BOOL")]
+        maximize: Option<bool>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ClipboardAction {
+    /// Print the clipboard text
+    Get,
+    /// Replace the clipboard text
+    Set {
+        /// Text to copy
+        text: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DialogAction {
+    /// Queue a response for the next dialog of a type
+    Arm {
+        /// Dialog type the app asks for (confirm, message, open, save, ...)
+        r#type: String,
+        /// Response as JSON (true, "/path/file", ["/a", "/b"]); plain text is sent as a string
+        response: String,
+    },
+    /// Show responses that are armed but not yet consumed
+    List,
+}
+
+#[derive(Subcommand)]
+enum MenuAction {
+    /// Show the menu structure (ids, labels, enabled/checked state)
+    List {
+        /// Window whose menu to show (defaults to the app menu)
+        #[arg(short, long)]
+        window: Option<String>,
+    },
+    /// Trigger a menu item by id
+    Click {
+        /// Menu item id
+        id: String,
+        /// Window whose menu contains the item (defaults to the app menu)
+        #[arg(short, long)]
+        window: Option<String>,
+    },
+}
+
+/// Where the bridge listens and the token to present.
+#[derive(Debug, PartialEq)]
+struct Connection {
+    /// TCP host; `None` means the loopback address.
+    host: Option<String>,
+    port: u16,
+    /// Unix socket, preferred over `port` when set.
+    socket: Option<PathBuf>,
+    token: Option<String>,
+    /// Discovery file the token came from, re-read when the bridge rejects
+    /// it (the app restarted). `None` once a profile or flag sets the token.
+    discovery_file: Option<PathBuf>,
+}
+
+impl Connection {
+    fn new(token: Option<String>) -> Self {
+        Self {
+            host: None,
+            port: 9229,
+            socket: None,
+            token,
+            discovery_file: None,
+        }
+    }
+
+    /// Apply a profile's settings over what discovery found.
+    fn with_profile(mut self, profile: &profile::Profile) -> Self {
+        if let Some(port) = profile.port {
+            self.port = port;
+            self.socket = None;
+        }
+        if let Some(host) = &profile.host {
+            self.host = Some(host.clone());
+            self.socket = None;
+        }
+        if let Some(token) = &profile.token {
+            self.token = Some(token.clone());
+            self.discovery_file = None;
+        }
+        self
+    }
+
+    /// Apply `--port`/`--socket`. An explicit port means TCP, even when the
+    /// app also listens on a socket.
+    fn with_overrides(mut self, cli: &Cli) -> Self {
+        if let Some(token) = &cli.token {
+            self.token = Some(token.clone());
+            self.discovery_file = None;
+        }
+        if let Some(port) = cli.port {
+            self.port = port;
+            self.socket = None;
+        }
+        if let Some(socket) = &cli.socket {
+            self.socket = Some(socket.clone());
+        }
+        self
+    }
+}
+
+/// Read port, socket, and token from a discovery file written by the plugin.
+fn read_discovery_file(path: &Path) -> Option<Connection> {
     let content = std::fs::read_to_string(path).ok()?;
     let json: serde_json::Value = serde_json::from_str(&content).ok()?;
-    let port = json["port"].as_u64()? as u16;
+    let port = json["port"].as_u64().map(|p| p as u16);
+    let socket = json["socket"].as_str().map(PathBuf::from);
+    if port.is_none() && socket.is_none() {
+        return None;
+    }
     let token = json["token"].as_str()?.to_string();
-    Some((port, token))
+    Some(Connection {
+        host: None,
+        port: port.unwrap_or(9229),
+        socket,
+        token: Some(token),
+        discovery_file: Some(path.to_path_buf()),
+    })
 }
 
-/// Resolve connection parameters from CLI flags or discovery files.
-fn resolve_connection(cli: &Cli) -> Result<(u16, Option<String>)> {
+/// Resolve connection parameters. Precedence, highest first: CLI flags
+/// (and `TAURI_BROWSER_TOKEN`), the `--profile`, discovery files, defaults.
+fn resolve_connection(cli: &Cli) -> Result<Connection> {
+    let profile = match &cli.profile {
+        Some(name) => profile::load(name)?,
+        None => profile::Profile::default(),
+    };
+    connect_with_profile(cli, profile)
+}
+
+fn connect_with_profile(cli: &Cli, profile: profile::Profile) -> Result<Connection> {
+    // A profile's port and token belong to the app it was written for, so
+    // `--app` or `--port` pointing elsewhere sets them aside.
+    let profile = if cli.app.is_some() || cli.port.is_some() {
+        profile::Profile {
+            port: None,
+            token: None,
+            ..profile
+        }
+    } else {
+        profile
+    };
+
     // Explicit token provided — use manual mode.
-    if cli.token.is_some() {
-        return Ok((cli.port.unwrap_or(9229), cli.token.clone()));
+    if cli.token.is_some() || profile.token.is_some() {
+        return Ok(Connection::new(None)
+            .with_profile(&profile)
+            .with_overrides(cli));
     }
 
-    // Try discovery from /tmp/tauri-debug-bridge/.
-    let dir = Path::new(DISCOVERY_DIR);
+    let dir = profile
+        .discovery_dir
+        .as_deref()
+        .unwrap_or(Path::new(DISCOVERY_DIR));
+    Ok(discover(dir, cli.app.as_ref().or(profile.app.as_ref()))?
+        .with_profile(&profile)
+        .with_overrides(cli))
+}
 
-    if let Some(app_id) = &cli.app {
+/// Find the running app's connection in the discovery directory.
+fn discover(dir: &Path, app: Option<&String>) -> Result<Connection> {
+    if let Some(app_id) = app {
         // Target a specific app.
         let path = dir.join(format!("{app_id}.json"));
-        if let Some((port, token)) = read_discovery_file(&path) {
-            let port = cli.port.unwrap_or(port);
-            return Ok((port, Some(token)));
+        if let Some(connection) = read_discovery_file(&path) {
+            if let Some(reason) = stale_reason(&path, &connection) {
+                bail!(
+                    "app '{app_id}' is not running: {reason} (stale discovery file {})",
+                    path.display()
+                );
+            }
+            return Ok(connection);
         }
         bail!("no discovery file for app '{app_id}' at {}", path.display());
     }
 
     // No --app: scan directory for available apps.
     if let Ok(entries) = std::fs::read_dir(dir) {
-        let files: Vec<_> = entries
+        let mut files: Vec<PathBuf> = entries
             .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().map(|x| x == "json").unwrap_or(false))
+            .map(|e| e.path())
+            .filter(|p| p.extension().map(|x| x == "json").unwrap_or(false))
             .collect();
+        files.sort();
 
-        if files.len() == 1 {
-            if let Some((port, token)) = read_discovery_file(&files[0].path()) {
-                let port = cli.port.unwrap_or(port);
-                return Ok((port, Some(token)));
+        // Skip files left behind by apps that crashed or were killed.
+        let mut apps: Vec<(PathBuf, Connection)> = Vec::new();
+        for path in files {
+            let Some(connection) = read_discovery_file(&path) else {
+                continue;
+            };
+            match stale_reason(&path, &connection) {
+                Some(reason) => eprintln!("warning: skipping {}: {reason}", path.display()),
+                None => apps.push((path, connection)),
+            }
+        }
+
+        if apps.len() == 1 {
+            return Ok(apps.remove(0).1);
+        } else if apps.len() > 1 {
+            let names: Vec<String> = apps
+                .iter()
+                .filter_map(|(p, _)| p.file_stem())
+                .map(|n| n.to_string_lossy().into_owned())
+                .collect();
+            // Someone at a terminal can just choose; scripts get the error.
+            if std::io::stdin().is_terminal() && std::io::stderr().is_terminal() {
+                let choice =
+                    pick_app(&names, &mut std::io::stdin().lock(), &mut std::io::stderr())?;
+                return Ok(apps.swap_remove(choice).1);
             }
-        } else if files.len() > 1 {
             eprintln!("Multiple apps detected. Use --app to specify:");
-            for f in &files {
-                if let Some(name) = f.path().file_stem() {
-                    eprintln!("  --app {}", name.to_string_lossy());
-                }
+            for name in &names {
+                eprintln!("  --app {name}");
             }
             bail!("multiple apps running — specify --app <identifier>");
         }
     }
 
     // No discovery files found — fall back to defaults.
-    Ok((cli.port.unwrap_or(9229), None))
+    Ok(Connection::new(None))
+}
+
+/// How long to wait for a discovered bridge to accept a connection.
+const LIVENESS_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(300);
+
+/// Why a discovery file no longer points at a running bridge, if it doesn't:
+/// the process that wrote it is gone, or what listens where it said isn't a
+/// debug bridge (nothing, or another program that reused the port). Files
+/// without a `pid` (older plugins) only get the `/health` probe.
+fn stale_reason(path: &Path, connection: &Connection) -> Option<String> {
+    let pid = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|json| json["pid"].as_u64());
+    if let Some(pid) = pid
+        && !pid_alive(pid as u32)
+    {
+        return Some(format!("process {pid} is not running"));
+    }
+    #[cfg(unix)]
+    if let Some(socket) = &connection.socket {
+        let stream = match std::os::unix::net::UnixStream::connect(socket) {
+            Ok(s) => s,
+            Err(e) => return Some(format!("cannot connect to {}: {e}", socket.display())),
+        };
+        let _ = stream.set_read_timeout(Some(LIVENESS_TIMEOUT));
+        return probe_health(stream)
+            .err()
+            .map(|e| format!("no debug bridge at {}: {e}", socket.display()));
+    }
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], connection.port));
+    let stream = match std::net::TcpStream::connect_timeout(&addr, LIVENESS_TIMEOUT) {
+        Ok(s) => s,
+        Err(e) => return Some(format!("cannot connect to {addr}: {e}")),
+    };
+    let _ = stream.set_read_timeout(Some(LIVENESS_TIMEOUT));
+    probe_health(stream)
+        .err()
+        .map(|e| format!("no debug bridge at {addr}: {e}"))
+}
+
+/// `GET /health` over an open connection, expecting the bridge's reply.
+/// `/health` needs no token, so this works before the token is known good.
+fn probe_health(mut stream: impl Read + Write) -> std::result::Result<(), String> {
+    stream
+        .write_all(b"GET /health HTTP/1.0\r\nHost: 127.0.0.1\r\n\r\n")
+        .map_err(|e| e.to_string())?;
+    let mut response = Vec::new();
+    stream
+        .read_to_end(&mut response)
+        .map_err(|e| e.to_string())?;
+    let response = String::from_utf8_lossy(&response);
+    let status = response.lines().next().unwrap_or_default();
+    if status.split(' ').nth(1) != Some("200") {
+        return Err(format!("/health answered {status:?}"));
+    }
+    if !response.contains("tauri-plugin-debug-bridge") {
+        return Err("/health answered, but not from the debug bridge".to_string());
+    }
+    Ok(())
+}
+
+/// Whether a process with this PID exists.
+#[cfg(unix)]
+fn pid_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 checks for the process without signalling it. EPERM means it
+    // exists but belongs to another user.
+    let found = unsafe { libc::kill(pid, 0) } == 0;
+    found || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn pid_alive(_pid: u32) -> bool {
+    true
+}
+
+/// Ask which of several running apps to connect to. Re-prompts until it gets
+/// a number from the menu; fails on end of input.
+fn pick_app(names: &[String], input: &mut impl BufRead, out: &mut impl Write) -> Result<usize> {
+    writeln!(out, "Multiple apps detected:")?;
+    for (i, name) in names.iter().enumerate() {
+        writeln!(out, "  {}) {name}", i + 1)?;
+    }
+    loop {
+        write!(out, "Connect to [1-{}]: ", names.len())?;
+        out.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            bail!("no app selected — specify --app <identifier>");
+        }
+        match line.trim().parse::<usize>() {
+            Ok(n) if (1..=names.len()).contains(&n) => return Ok(n - 1),
+            _ => writeln!(out, "Enter a number from 1 to {}.", names.len())?,
+        }
+    }
+}
+
+/// Read a command's input from `path`, or from stdin when it's omitted or "-".
+fn read_source(path: Option<&str>, what: &str) -> Result<String> {
+    match path {
+        None | Some("-") => std::io::read_to_string(std::io::stdin()).context("reading stdin"),
+        Some(path) => {
+            std::fs::read_to_string(path).with_context(|| format!("reading {what} {path}"))
+        }
+    }
+}
+
+/// Run a wait, failing if it takes longer than `timeout` seconds.
+async fn within<T>(timeout: Option<u64>, wait: impl Future<Output = Result<T>>) -> Result<T> {
+    match timeout {
+        Some(secs) => tokio::time::timeout(std::time::Duration::from_secs(secs), wait)
+            .await
+            .map_err(|_| anyhow::anyhow!("no message within {secs}s"))?,
+        None => wait.await,
+    }
+}
+
+/// The matcher for `wait-console`: PATTERN as a regex, or as literal text.
+fn console_pattern(pattern: &str, regex: bool) -> Result<regex::Regex> {
+    let source = if regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    regex::Regex::new(&source).with_context(|| format!("invalid --regex pattern '{pattern}'"))
+}
+
+/// Parse `--file-arg key=path` pairs.
+fn file_args_from_pairs(pairs: &[String]) -> Result<Vec<client::FileArg>> {
+    pairs
+        .iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((key, path)) if !key.is_empty() && !path.is_empty() => Ok(client::FileArg {
+                key: key.to_string(),
+                path: PathBuf::from(path),
+            }),
+            _ => bail!("invalid --file-arg '{pair}': expected key=path"),
+        })
+        .collect()
+}
+
+/// A window label as a file name: characters labels allow but paths don't
+/// (`/`, `:`) become `_`.
+fn file_stem(label: &str) -> String {
+    label.replace(['/', ':', '\\'], "_")
+}
+
+/// Parse `replay --speed`: a positive, finite factor.
+fn parse_speed(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(speed),
+        _ => Err(format!("'{s}' is not a positive number")),
+    }
+}
+
+/// Parse `--header "Name: Value"`. The token keeps its own flag so it is
+/// never sent twice or printed.
+fn parse_header(s: &str) -> Result<(client::HeaderName, client::HeaderValue), String> {
+    let (name, value) = client::parse_header(s).map_err(|e| format!("{e:#}"))?;
+    if name == "x-debug-bridge-token" {
+        return Err("pass the auth token with --token, not --header".to_string());
+    }
+    Ok((name, value))
+}
+
+/// Assemble `--arg key=value` pairs into a JSON args object.
+fn invoke_args_from_pairs(pairs: &[String]) -> Result<String> {
+    let args = json_object_from_pairs(pairs, "--arg")?;
+    Ok(serde_json::Value::Object(args).to_string())
+}
+
+/// Collect `key=value` pairs from a repeatable `flag` into a JSON object.
+/// Values parse as JSON, falling back to a plain string.
+fn json_object_from_pairs(
+    pairs: &[String],
+    flag: &str,
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let mut object = serde_json::Map::new();
+    for pair in pairs {
+        let Some((key, value)) = pair.split_once('=') else {
+            bail!("invalid {flag} '{pair}': expected key=value");
+        };
+        if key.is_empty() {
+            bail!("invalid {flag} '{pair}': key must not be empty");
+        }
+        let value = serde_json::from_str(value)
+            .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+        object.insert(key.to_string(), value);
+    }
+    Ok(object)
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> std::process::ExitCode {
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("tauri_browser=info".parse()?),
+                .add_directive("tauri_browser=info".parse().unwrap()),
         )
         .init();
 
     let cli = Cli::parse();
-    let (port, token) = resolve_connection(&cli)?;
-    let client = client::BridgeClient::new(port, token.as_deref());
+    let json_errors = cli.json_errors;
+    match run(cli).await {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => error::report(&e, json_errors),
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    if let Some(path) = &cli.tee {
+        output::tee_to(path).with_context(|| format!("creating {}", path.display()))?;
+    }
+    #[cfg(feature = "mdns")]
+    if let Command::Discover { timeout } = cli.command {
+        // Browsing needs no bridge connection.
+        let bridges = discover::browse(std::time::Duration::from_secs(timeout)).await?;
+        if bridges.is_empty() {
+            eprintln!("no bridges found (is `advertise` set in the plugin config?)");
+        }
+        output::print(&bridges, &cli.format);
+        return Ok(());
+    }
+    let connection = resolve_connection(&cli)?;
+    let mut builder = client::BridgeClient::builder(connection.port)
+        .host(connection.host.as_deref())
+        .token(connection.token.as_deref())
+        .verbose(cli.verbose);
+    if let Some(path) = connection.discovery_file {
+        // The app may restart mid-run (e.g. `tauri dev` rebuilding) with a
+        // new token; pick it up instead of failing with a 401.
+        builder = builder.token_refresh(move || read_discovery_file(&path)?.token);
+    }
+    for (name, value) in &cli.headers {
+        builder = builder.header(name.clone(), value.clone());
+    }
+    #[cfg(unix)]
+    {
+        builder = builder.unix_socket(connection.socket);
+    }
+    #[cfg(not(unix))]
+    if connection.socket.is_some() {
+        bail!("Unix sockets are not supported on this platform; use --port");
+    }
+    if let Command::Script { parallel, .. } = &cli.command {
+        // Keep a warm connection for every step a parallel group can run.
+        builder = builder.pool_max_idle_per_host((*parallel).max(1));
+    }
+    let client = builder.build();
+    let recorder = session::Recorder::new(cli.record.clone());
 
     match cli.command {
-        Command::Connect => {
+        Command::Connect { watch } => {
             let health = client.health().await?;
             output::print(&health, &cli.format);
+            if health["is_dev"].as_bool() == Some(false) {
+                eprintln!(
+                    "warning: this is a bundled (production) build; the debug bridge should only run in development"
+                );
+            }
+            if watch {
+                client.stream_diagnostics().await?;
+            }
+        }
+        Command::Screenshot {
+            wait_ready,
+            full_page,
+            dry_run: true,
+            ..
+        } => {
+            let scripts = client
+                .dry_run(client::DryRunRequest::Screenshot {
+                    wait_ready,
+                    full_page,
+                })
+                .await?;
+            output::print_dry_run(&scripts, &cli.format);
+        }
+        Command::Screenshot { all: true, dir, .. } => {
+            let captures = client.screenshot_all().await?;
+            std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+            let mut saved = serde_json::Map::new();
+            for (label, png) in &captures.screenshots {
+                let path = dir.join(format!("{}.png", file_stem(label)));
+                std::fs::write(&path, png)
+                    .with_context(|| format!("writing {}", path.display()))?;
+                saved.insert(label.clone(), path.display().to_string().into());
+            }
+            for (label, error) in &captures.errors {
+                eprintln!("warning: window '{label}' not captured: {error}");
+            }
+            if saved.is_empty() && !captures.errors.is_empty() {
+                bail!("no window could be captured");
+            }
+            output::print(&saved, &cli.format);
         }
-        Command::Screenshot { path } => {
-            let data = client.screenshot().await?;
+        Command::Screenshot {
+            path,
+            wait_ready,
+            full_page,
+            dry_run: false,
+            ..
+        } => {
+            let warning = match &path {
+                Some(path) => {
+                    let step = script::Step::Screenshot {
+                        path: path.clone(),
+                        wait_ready,
+                        full_page,
+                    };
+                    let saved = recorder.run(&client, step).await?;
+                    saved["warning"].as_str().map(str::to_string)
+                }
+                // Write raw PNG to stdout for piping
+                None => {
+                    client
+                        .screenshot_to(wait_ready, full_page, &mut tokio::io::stdout())
+                        .await?
+                }
+            };
+            if let Some(warning) = warning {
+                eprintln!("warning: {warning}");
+            }
             if let Some(path) = path {
-                std::fs::write(&path, &data)
-                    .with_context(|| format!("writing screenshot to {path}"))?;
                 println!("Screenshot saved to {path}");
+            }
+        }
+        Command::Snapshot {
+            interactive,
+            locators,
+            attrs,
+            include_hidden,
+            wait_ready,
+            reset_refs,
+            fields,
+            dry_run,
+            html,
+            with_screenshot,
+        } => {
+            let options = client::SnapshotOptions {
+                interactive,
+                locators,
+                attrs,
+                include_hidden,
+                wait_ready,
+                reset_refs,
+                fields: fields.clone(),
+            };
+            if dry_run {
+                let scripts = client
+                    .dry_run(client::DryRunRequest::Snapshot(options))
+                    .await?;
+                output::print_dry_run(&scripts, &cli.format);
             } else {
-                // Write raw PNG to stdout for piping
-                use std::io::Write;
-                std::io::stdout().write_all(&data)?;
+                let step = script::Step::Snapshot {
+                    interactive,
+                    locators,
+                    attrs,
+                    include_hidden,
+                    wait_ready,
+                    reset_refs,
+                    fields,
+                };
+                let snapshot = recorder.run(&client, step).await?;
+                if let Some(path) = html {
+                    let screenshot = if with_screenshot {
+                        Some(client.screenshot(false, false).await?.0)
+                    } else {
+                        None
+                    };
+                    let report = snapshot_html::render(&snapshot, screenshot.as_deref());
+                    std::fs::write(&path, report)
+                        .with_context(|| format!("writing {}", path.display()))?;
+                    eprintln!("wrote {}", path.display());
+                } else {
+                    output::print_snapshot(&snapshot, &cli.format);
+                }
+                if snapshot["truncated"].as_bool() == Some(true) {
+                    eprintln!(
+                        "warning: snapshot ran out of time; only part of the page is included (raise eval_timeout_secs for huge DOMs)"
+                    );
+                }
             }
         }
-        Command::Snapshot { interactive } => {
-            let snapshot = client.snapshot(interactive).await?;
-            output::print(&snapshot, &cli.format);
+        Command::Pagestate { window } => {
+            let page = client.page_state(window.as_deref()).await?;
+            output::print(&page, &cli.format);
         }
-        Command::Click { selector } => {
-            let result = client.click(&selector).await?;
-            output::print(&result, &cli.format);
+        Command::Refs {
+            window,
+            action: None,
+        } => {
+            let refs = client.live_refs(window.as_deref()).await?;
+            output::print(&refs, &cli.format);
+        }
+        Command::Refs {
+            window,
+            action: Some(RefsAction::Clear { window: label }),
+        } => {
+            let cleared = client.clear_refs(label.or(window).as_deref()).await?;
+            output::print(&cleared, &cli.format);
+        }
+        Command::Click {
+            selector,
+            dry_run: true,
+        } => {
+            let scripts = client
+                .dry_run(client::DryRunRequest::Click(&selector))
+                .await?;
+            output::print_dry_run(&scripts, &cli.format);
         }
-        Command::Fill { selector, text } => {
-            let result = client.fill(&selector, &text).await?;
+        Command::Click {
+            selector,
+            dry_run: false,
+        } => {
+            let step = script::Step::Click {
+                selector: selector.clone(),
+            };
+            let result = recorder.run(&client, step).await?;
             output::print(&result, &cli.format);
         }
-        Command::RunJs { code } => {
-            let result = client.run_js(&code).await?;
+        Command::Fill {
+            selector,
+            text,
+            dry_run: true,
+        } => {
+            let scripts = client
+                .dry_run(client::DryRunRequest::Fill {
+                    selector: &selector,
+                    text: &text,
+                })
+                .await?;
+            output::print_dry_run(&scripts, &cli.format);
+        }
+        Command::Fill {
+            selector,
+            text,
+            dry_run: false,
+        } => {
+            let step = script::Step::Fill {
+                selector: selector.clone(),
+                text,
+            };
+            let result = recorder.run(&client, step).await?;
             output::print(&result, &cli.format);
         }
-        Command::Console => {
-            client.stream_console().await?;
+        Command::SelectText {
+            selector,
+            start,
+            end,
+        } => {
+            let selection = client.select_text(&selector, start, end).await?;
+            output::print(&selection, &cli.format);
+        }
+        Command::Wait {
+            target:
+                WaitTarget::Navigation {
+                    url_contains,
+                    timeout,
+                    since,
+                },
+        } => {
+            let step = script::Step::WaitNavigation {
+                url_contains,
+                timeout_ms: Some(timeout * 1000),
+                since,
+            };
+            let page = recorder.run(&client, step).await?;
+            output::print(&page, &cli.format);
+        }
+        Command::Styles { selector, props } => {
+            let styles = recorder
+                .run(&client, script::Step::Styles { selector, props })
+                .await?;
+            output::print(&styles, &cli.format);
+        }
+        Command::Clickable { selector } => {
+            let check = client.is_clickable(&selector).await?;
+            output::print(&check, &cli.format);
+        }
+        Command::Html { selector, inner } => {
+            let html = client.html(selector.as_deref(), inner).await?;
+            if html["truncated"].as_bool() == Some(true) {
+                eprintln!(
+                    "warning: markup truncated ({} of {} characters)",
+                    html["html"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .encode_utf16()
+                        .count(),
+                    html["length"]
+                );
+            }
+            match cli.format {
+                // Raw markup, ready to diff or paste into a bug report.
+                output::Format::Text => {
+                    output::tee(&html);
+                    println!("{}", html["html"].as_str().unwrap_or_default());
+                }
+                output::Format::Json => output::print(&html, &cli.format),
+            }
+        }
+        Command::Clipboard { action } => match action {
+            ClipboardAction::Get => {
+                let clip = client.clipboard_read().await?;
+                output::print(&clip, &cli.format);
+            }
+            ClipboardAction::Set { text } => {
+                let result = client.clipboard_write(&text).await?;
+                output::print(&result, &cli.format);
+            }
+        },
+        Command::Zoom { factor, window } => {
+            let zoom = match factor {
+                Some(factor) => {
+                    let zoom = client.set_zoom(factor, window.as_deref()).await?;
+                    if zoom["clamped"].as_bool() == Some(true) {
+                        eprintln!(
+                            "warning: zoom {factor} is out of range; applied {}",
+                            zoom["factor"]
+                        );
+                    }
+                    zoom
+                }
+                None => client.zoom(window.as_deref()).await?,
+            };
+            output::print(&zoom, &cli.format);
+        }
+        Command::RunJs {
+            code,
+            file,
+            bind,
+            wait_ready,
+            dry_run,
+            raw,
+        } => {
+            let code = match code {
+                Some(code) => code,
+                None => read_source(file.as_deref(), "JS file")?,
+            };
+            let bindings = json_object_from_pairs(&bind, "--bind")?;
+            if dry_run {
+                let scripts = client
+                    .dry_run(client::DryRunRequest::Eval {
+                        code: &code,
+                        bindings: &bindings,
+                        wait_ready,
+                    })
+                    .await?;
+                output::print_dry_run(&scripts, &cli.format);
+            } else if raw {
+                let text = client.run_js_raw(&code, &bindings, wait_ready).await?;
+                std::io::stdout().write_all(text.as_bytes())?;
+            } else {
+                let step = script::Step::Eval {
+                    js: code,
+                    bindings,
+                    wait_ready,
+                };
+                let result = recorder.run(&client, step).await?;
+                output::print(&result, &cli.format);
+            }
+        }
+        Command::Repl => {
+            // Say so before the session rather than after it.
+            recorder.warn_if_unrecorded();
+            repl::run(&client, &cli.format).await?;
+        }
+        Command::Console {
+            tail: Some(n),
+            window,
+            ..
+        } => {
+            let messages = client.console_recent(n, window.as_deref()).await?;
+            output::tee(&messages);
+            // One JSON line per message, same as the stream.
+            for message in messages.as_array().into_iter().flatten() {
+                println!("{message}");
+            }
+        }
+        Command::Console {
+            tail: None,
+            window,
+            once,
+            timeout,
+            duration,
+        } => {
+            let duration = duration.map(std::time::Duration::from_secs);
+            within(
+                timeout,
+                client.stream_console(window.as_deref(), once, duration),
+            )
+            .await?;
+        }
+        Command::WaitConsole {
+            pattern,
+            regex,
+            window,
+            timeout,
+        } => {
+            let pattern = console_pattern(&pattern, regex)?;
+            let wait = client.wait_console(window.as_deref(), |message| {
+                message["message"]
+                    .as_str()
+                    .is_some_and(|text| pattern.is_match(text))
+            });
+            let message = within(Some(timeout), wait).await?;
+            output::print(&message, &cli.format);
         }
         Command::Errors => {
             client.stream_errors().await?;
         }
-        Command::Invoke { command, args } => {
-            let args = args.as_deref().unwrap_or("{}");
-            let result = client.invoke(&command, args).await?;
+        Command::Invoke {
+            command,
+            args,
+            arg,
+            file_arg,
+            bytes,
+        } => {
+            let args = match args {
+                Some(args) => args,
+                None => invoke_args_from_pairs(&arg)?,
+            };
+            let files = file_args_from_pairs(&file_arg)?;
+            let result = if files.is_empty() {
+                let args = serde_json::from_str(&args).context("invalid JSON args")?;
+                recorder
+                    .run(&client, script::Step::Invoke { command, args })
+                    .await?
+            } else {
+                client
+                    .invoke_with_files(&command, &args, &files, bytes)
+                    .await?
+            };
             output::print(&result, &cli.format);
         }
+        Command::InvokeBatch {
+            file,
+            stop_on_error,
+        } => {
+            let calls = read_source(file.as_deref(), "batch file")?;
+            let results = client.invoke_batch(&calls, stop_on_error).await?;
+            output::print(&results, &cli.format);
+        }
+        Command::Script { file, parallel } => {
+            let source = read_source(file.as_deref(), "script file")?;
+            let entries = script::parse(&source)?;
+            let outcomes = script::run(&client, &entries, parallel).await;
+            output::print(&outcomes, &cli.format);
+            if let Some(failed) = outcomes.iter().find(|o| o.error.is_some()) {
+                bail!("script failed at step {} ({})", failed.step, failed.action);
+            }
+        }
+        Command::Replay { file, speed } => {
+            let source = read_source(file.as_deref(), "session file")?;
+            let records = session::parse(&source)?;
+            let outcomes = session::replay(&client, &records, speed).await;
+            output::print(&outcomes, &cli.format);
+            let changed = outcomes.iter().filter(|o| o.changed).count();
+            if changed > 0 {
+                bail!(
+                    "{changed} of {} steps differed from the recording",
+                    outcomes.len()
+                );
+            }
+        }
         Command::State => {
-            let state = client.state().await?;
+            let state = recorder.run(&client, script::Step::State).await?;
             output::print(&state, &cli.format);
         }
         Command::Commands => {
-            let cmds = client.commands().await?;
+            let cmds = recorder.run(&client, script::Step::Commands).await?;
             output::print(&cmds, &cli.format);
         }
         Command::Events { action } => match action {
-            EventAction::Emit { name, payload } => {
+            EventAction::Emit {
+                name,
+                payload,
+                wait,
+                timeout,
+                file_arg,
+                bytes,
+            } => {
                 let payload = payload.as_deref().unwrap_or("{}");
-                let result = client.event_emit(&name, payload).await?;
+                let files = file_args_from_pairs(&file_arg)?;
+                let result = if !files.is_empty() {
+                    client
+                        .event_emit_with_files(&name, payload, &files, bytes)
+                        .await?
+                } else if wait {
+                    let timeout = std::time::Duration::from_secs(timeout);
+                    client
+                        .event_emit_await(&name, payload, Some(timeout))
+                        .await?
+                } else {
+                    client.event_emit(&name, payload).await?
+                };
                 output::print(&result, &cli.format);
             }
-            EventAction::Listen { name } => {
-                client.event_listen(&name).await?;
+            EventAction::Listen {
+                name,
+                once,
+                timeout,
+                duration,
+            } => {
+                let duration = duration.map(std::time::Duration::from_secs);
+                within(timeout, client.event_listen(&name, once, duration)).await?;
             }
             EventAction::List => {
                 let events = client.event_list().await?;
                 output::print(&events, &cli.format);
             }
         },
-        Command::Logs { level } => {
-            client.stream_logs(&level).await?;
+        Command::Dialog { action } => match action {
+            DialogAction::Arm { r#type, response } => {
+                let result = client.dialog_arm(&r#type, &response).await?;
+                output::print(&result, &cli.format);
+            }
+            DialogAction::List => {
+                let armed = client.dialog_armed().await?;
+                output::print(&armed, &cli.format);
+            }
+        },
+        Command::Menu { action } => match action {
+            MenuAction::List { window } => {
+                let menu = client.menu(window.as_deref()).await?;
+                output::print(&menu, &cli.format);
+            }
+            MenuAction::Click { id, window } => {
+                let result = client.menu_click(&id, window.as_deref()).await?;
+                output::print(&result, &cli.format);
+            }
+        },
+        Command::Logs { level, duration } => {
+            let duration = duration.map(std::time::Duration::from_secs);
+            client.stream_logs(&level, duration).await?;
+        }
+        Command::Windows { action: None } => {
+            let windows = recorder.run(&client, script::Step::Windows).await?;
+            output::print_windows(&windows, &cli.format);
+        }
+        Command::Windows {
+            action:
+                Some(WindowsAction::State {
+                    window,
+                    on_top,
+                    fullscreen,
+                    maximize,
+                }),
+        } => {
+            let change = client::WindowStateChange {
+                always_on_top: on_top,
+                fullscreen,
+                maximized: maximize,
+            };
+            let state = if on_top.is_none() && fullscreen.is_none() && maximize.is_none() {
+                client.window_state(window.as_deref()).await?
+            } else {
+                client.set_window_state(change, window.as_deref()).await?
+            };
+            output::print(&state, &cli.format);
+        }
+        Command::Paths => {
+            let paths = client.paths().await?;
+            output::print(&paths, &cli.format);
+        }
+        Command::Capabilities => {
+            let capabilities = client.capabilities().await?;
+            output::print(&capabilities, &cli.format);
+        }
+        Command::Report { out } => {
+            let dir = out.unwrap_or_else(report::default_dir);
+            let manifest = report::collect(&client, &dir).await?;
+            for (file, error) in &manifest.failed {
+                eprintln!("warning: couldn't gather {file}: {error}");
+            }
+            output::print(&manifest, &cli.format);
+        }
+        Command::Serve { listen } => {
+            serve::run(client, listen).await?;
+        }
+        Command::Bench {
+            operation,
+            iterations,
+            warmup,
+            js,
+        } => {
+            let (name, op) = match operation {
+                BenchTarget::Eval => ("eval", client::BenchOp::Eval(js)),
+                BenchTarget::Snapshot => (
+                    "snapshot",
+                    client::BenchOp::Snapshot(client::SnapshotOptions {
+                        interactive: true,
+                        ..Default::default()
+                    }),
+                ),
+                BenchTarget::Screenshot => ("screenshot", client::BenchOp::Screenshot),
+            };
+            let report =
+                bench::run(&client, name, &op, iterations as usize, warmup as usize).await?;
+            match cli.format {
+                output::Format::Text => {
+                    output::tee(&report);
+                    print!("{}", bench::render(&report));
+                }
+                output::Format::Json => output::print(&report, &cli.format),
+            }
+        }
+        Command::App => {
+            let app = client.app_info().await?;
+            output::print(&app, &cli.format);
         }
-        Command::Windows => {
-            let windows = client.windows().await?;
-            output::print(&windows, &cli.format);
+        Command::Env => {
+            let env = client.env().await?;
+            output::print(&env, &cli.format);
+        }
+        #[cfg(feature = "mdns")]
+        Command::Discover { .. } => unreachable!("handled before connecting"),
+        Command::Store { path } => {
+            let store = client.store(&path).await?;
+            output::print(&store, &cli.format);
+        }
+        Command::Shutdown => {
+            let result = client.shutdown().await?;
+            output::print(&result, &cli.format);
         }
     }
+    recorder.warn_if_unrecorded();
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invoke_args_infer_value_types() {
+        let pairs = [
+            "id=42",
+            "force=true",
+            "name=AAPL",
+            "filter={\"open\":true}",
+            "note=a=b",
+        ]
+        .map(String::from);
+        let args: serde_json::Value =
+            serde_json::from_str(&invoke_args_from_pairs(&pairs).unwrap()).unwrap();
+        assert_eq!(
+            args,
+            serde_json::json!({
+                "id": 42,
+                "force": true,
+                "name": "AAPL",
+                "filter": { "open": true },
+                "note": "a=b",
+            })
+        );
+    }
+
+    #[test]
+    fn invoke_args_reject_missing_separator() {
+        assert!(invoke_args_from_pairs(&["verbose".to_string()]).is_err());
+        assert_eq!(invoke_args_from_pairs(&[]).unwrap(), "{}");
+    }
+
+    #[test]
+    fn run_js_takes_code_or_file_but_not_both() {
+        use clap::Parser;
+        let parse = |args: &[&str]| {
+            Cli::try_parse_from(["tauri-browser", "run-js"].iter().chain(args)).map(|_| ())
+        };
+        assert!(parse(&["document.title"]).is_ok());
+        assert!(parse(&["--file", "check.js"]).is_ok());
+        assert!(parse(&["--file", "-"]).is_ok());
+        assert!(parse(&["document.title", "--file", "check.js"]).is_err());
+        assert!(parse(&[]).is_err());
+    }
+
+    #[test]
+    fn file_args_need_key_and_path() {
+        let files = file_args_from_pairs(&["logo=./logo.png".to_string()]).unwrap();
+        assert_eq!(files[0].key, "logo");
+        assert_eq!(files[0].path, PathBuf::from("./logo.png"));
+        assert!(file_args_from_pairs(&["logo".to_string()]).is_err());
+        assert!(file_args_from_pairs(&["=logo.png".to_string()]).is_err());
+
+        // --file-arg works alongside positional JSON args.
+        let cli = Cli::try_parse_from([
+            "tauri-browser",
+            "invoke",
+            "upload",
+            r#"{"name":"logo"}"#,
+            "--file-arg",
+            "data=logo.png",
+            "--bytes",
+        ]);
+        assert!(cli.is_ok());
+    }
+
+    #[test]
+    fn invoke_arg_conflicts_with_positional_json() {
+        use clap::Parser;
+        let result =
+            Cli::try_parse_from(["tauri-browser", "invoke", "greet", "{}", "--arg", "a=1"]);
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn once_waits_fail_after_the_timeout() {
+        let err = within(Some(0), std::future::pending::<Result<()>>())
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "no message within 0s");
+        assert!(within(None, async { Ok(()) }).await.is_ok());
+
+        // --timeout only bounds a --once wait, and --once can't tail.
+        let parse = |args: &[&str]| Cli::try_parse_from(["tauri-browser"].iter().chain(args));
+        assert!(parse(&["events", "listen", "saved", "--once", "--timeout", "5"]).is_ok());
+        assert!(parse(&["events", "listen", "saved", "--timeout", "5"]).is_err());
+        assert!(parse(&["console", "--once", "--tail", "5"]).is_err());
+        assert!(parse(&["console", "--duration", "5", "--tail", "5"]).is_err());
+        assert!(parse(&["events", "listen", "saved", "--duration", "5"]).is_ok());
+        assert!(parse(&["logs", "--duration", "5"]).is_ok());
+    }
+
+    #[test]
+    fn window_state_flags_take_explicit_values() {
+        let parse = |args: &[&str]| Cli::try_parse_from(["tauri-browser"].iter().chain(args));
+        let cli = parse(&[
+            "windows",
+            "state",
+            "--on-top",
+            "true",
+            "--maximize",
+            "false",
+        ])
+        .unwrap();
+        let Command::Windows {
+            action:
+                Some(WindowsAction::State {
+                    on_top,
+                    fullscreen,
+                    maximize,
+                    ..
+                }),
+        } = cli.command
+        else {
+            panic!("expected windows state");
+        };
+        assert_eq!(
+            (on_top, fullscreen, maximize),
+            (Some(true), None, Some(false))
+        );
+        assert!(parse(&["windows", "state", "--on-top"]).is_err());
+        assert!(matches!(
+            parse(&["windows"]).unwrap().command,
+            Command::Windows { action: None }
+        ));
+    }
+
+    #[test]
+    fn screenshot_all_saves_one_file_per_window() {
+        let parse = |args: &[&str]| Cli::try_parse_from(["tauri-browser"].iter().chain(args));
+        assert!(parse(&["screenshot", "--all", "--dir", "shots"]).is_ok());
+        assert!(parse(&["screenshot", "--all", "out.png"]).is_err());
+        assert!(parse(&["screenshot", "--dir", "shots"]).is_err());
+        assert_eq!(file_stem("main"), "main");
+        assert_eq!(file_stem("panel/settings:2"), "panel_settings_2");
+    }
+
+    #[test]
+    fn discovered_socket_is_preferred_unless_port_given() {
+        let path = std::env::temp_dir().join(format!("tauri-browser-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"port": 9300, "socket": "/tmp/app.sock", "token": "abc"}"#,
+        )
+        .unwrap();
+        let discovered = || read_discovery_file(&path).unwrap();
+
+        let cli = Cli::try_parse_from(["tauri-browser", "windows"]).unwrap();
+        let connection = discovered().with_overrides(&cli);
+        assert_eq!(connection.socket, Some(PathBuf::from("/tmp/app.sock")));
+        assert_eq!(connection.token.as_deref(), Some("abc"));
+        assert_eq!(connection.discovery_file.as_deref(), Some(path.as_path()));
+
+        let cli = Cli::try_parse_from(["tauri-browser", "--port", "9400", "windows"]).unwrap();
+        let connection = discovered().with_overrides(&cli);
+        assert_eq!(connection.socket, None);
+        assert_eq!(connection.port, 9400);
+
+        std::fs::write(&path, r#"{"token": "abc"}"#).unwrap();
+        assert_eq!(read_discovery_file(&path), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// A listener answering every request with `response`, as the bridge's
+    /// `/health` would (or as some other program on a reused port would).
+    fn fake_health(response: &'static str) -> u16 {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        port
+    }
+
+    const BRIDGE_HEALTH: &str = "HTTP/1.1 200 OK\r\nconnection: close\r\n\r\n\
+        {\"status\":\"ok\",\"plugin\":\"tauri-plugin-debug-bridge\"}";
+
+    // Spawns `true` for a PID that is known to have exited.
+    #[cfg(unix)]
+    #[test]
+    fn stale_discovery_files_are_detected() {
+        let path =
+            std::env::temp_dir().join(format!("tauri-browser-live-{}.json", std::process::id()));
+        let mut port = fake_health(BRIDGE_HEALTH);
+        let write = |port: u16, pid: u32| {
+            std::fs::write(
+                &path,
+                format!(r#"{{"port": {port}, "token": "abc", "pid": {pid}}}"#),
+            )
+            .unwrap();
+            read_discovery_file(&path).unwrap()
+        };
+
+        let connection = write(port, std::process::id());
+        assert_eq!(stale_reason(&path, &connection), None);
+
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead = child.id();
+        child.wait().unwrap();
+        let connection = write(port, dead);
+        assert_eq!(
+            stale_reason(&path, &connection),
+            Some(format!("process {dead} is not running"))
+        );
+
+        // The port was reused by something that isn't a bridge.
+        port = fake_health("HTTP/1.1 404 Not Found\r\nconnection: close\r\n\r\n");
+        let connection = write(port, std::process::id());
+        assert!(
+            stale_reason(&path, &connection)
+                .unwrap()
+                .contains("/health answered \"HTTP/1.1 404 Not Found\"")
+        );
+
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        port = closed.local_addr().unwrap().port();
+        drop(closed);
+        let connection = write(port, std::process::id());
+        assert!(
+            stale_reason(&path, &connection)
+                .unwrap()
+                .starts_with("cannot connect to 127.0.0.1:")
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn app_picker_reprompts_until_valid() {
+        let names = vec!["com.example.a".to_string(), "com.example.b".to_string()];
+        let mut out = Vec::new();
+        let choice = pick_app(&names, &mut "x\n3\n2\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(choice, 1);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("  1) com.example.a\n  2) com.example.b\n"));
+        assert_eq!(out.matches("Enter a number from 1 to 2.").count(), 2);
+
+        assert!(pick_app(&names, &mut "".as_bytes(), &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn headers_are_repeatable_but_not_the_token() {
+        let cli = Cli::try_parse_from([
+            "tauri-browser",
+            "--header",
+            "X-Proxy-Auth: abc",
+            "--header",
+            "X-Team: qa",
+            "windows",
+        ])
+        .unwrap();
+        assert_eq!(cli.headers.len(), 2);
+        assert_eq!(cli.headers[1].0, "x-team");
+
+        for bad in ["X-Proxy-Auth abc", "X-Debug-Bridge-Token: t"] {
+            assert!(Cli::try_parse_from(["tauri-browser", "--header", bad, "windows"]).is_err());
+        }
+    }
+
+    #[test]
+    fn console_patterns_are_literal_unless_regex() {
+        let literal = console_pattern("saved (1)", false).unwrap();
+        assert!(literal.is_match("draft saved (1) ok"));
+        assert!(!literal.is_match("saved 1"));
+
+        let regex = console_pattern(r"saved \(\d+\)", true).unwrap();
+        assert!(regex.is_match("saved (42)"));
+        assert!(console_pattern("(", true).is_err());
+    }
+
+    #[test]
+    fn flags_beat_profile_beats_discovery() {
+        let discovered = || Connection {
+            host: None,
+            port: 9300,
+            socket: Some(PathBuf::from("/tmp/app.sock")),
+            token: Some("discovered".to_string()),
+            discovery_file: Some(PathBuf::from("/tmp/app.json")),
+        };
+        let profile = profile::Profile {
+            port: Some(9400),
+            token: Some("profile".to_string()),
+            ..Default::default()
+        };
+
+        let cli = Cli::try_parse_from(["tauri-browser", "windows"]).unwrap();
+        let connection = discovered().with_profile(&profile).with_overrides(&cli);
+        assert_eq!(connection.port, 9400);
+        assert_eq!(connection.socket, None);
+        assert_eq!(connection.token.as_deref(), Some("profile"));
+        assert_eq!(connection.discovery_file, None);
+
+        let cli = Cli::try_parse_from([
+            "tauri-browser",
+            "--port",
+            "9500",
+            "--token",
+            "flag",
+            "windows",
+        ])
+        .unwrap();
+        let connection = discovered().with_profile(&profile).with_overrides(&cli);
+        assert_eq!(connection.port, 9500);
+        assert_eq!(connection.token.as_deref(), Some("flag"));
+
+        // An empty profile leaves discovery alone.
+        let cli = Cli::try_parse_from(["tauri-browser", "windows"]).unwrap();
+        let connection = discovered()
+            .with_profile(&profile::Profile::default())
+            .with_overrides(&cli);
+        assert_eq!(connection, discovered());
+    }
+
+    #[test]
+    fn app_flag_sets_aside_a_profile_token() {
+        let port = fake_health(BRIDGE_HEALTH);
+        let dir = std::env::temp_dir().join(format!("tauri-browser-app-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("com.example.other.json"),
+            format!(
+                r#"{{"port": {port}, "token": "discovered", "pid": {}}}"#,
+                std::process::id()
+            ),
+        )
+        .unwrap();
+        let profile = || profile::Profile {
+            port: Some(9400),
+            token: Some("profile".to_string()),
+            discovery_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+
+        let cli = Cli::try_parse_from(["tauri-browser", "--app", "com.example.other", "windows"])
+            .unwrap();
+        let connection = connect_with_profile(&cli, profile()).unwrap();
+        assert_eq!(connection.port, port);
+        assert_eq!(connection.token.as_deref(), Some("discovered"));
+        assert!(connection.discovery_file.is_some());
+
+        // Without --app the profile still decides.
+        let cli = Cli::try_parse_from(["tauri-browser", "windows"]).unwrap();
+        let connection = connect_with_profile(&cli, profile()).unwrap();
+        assert_eq!(connection.port, 9400);
+        assert_eq!(connection.token.as_deref(), Some("profile"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
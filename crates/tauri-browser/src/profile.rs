@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+/// Named connection settings from the CLI config file, selected with
+/// `--profile <name>`:
+///
+/// ```toml
+/// [profiles.editor]
+/// app = "com.example.editor"
+///
+/// [profiles.staging]
+/// port = 9300
+/// token = "a1b2c3..."
+/// ```
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub port: Option<u16>,
+    pub token: Option<String>,
+    /// Host the bridge is reachable on, e.g. through an SSH tunnel.
+    pub host: Option<String>,
+    /// App identifier to pick from the discovery directory.
+    pub app: Option<String>,
+    /// Where to look for discovery files instead of `/tmp/tauri-debug-bridge`.
+    pub discovery_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// `$XDG_CONFIG_HOME/tauri-browser/config.toml`, falling back to
+/// `~/.config/tauri-browser/config.toml`.
+pub fn config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("tauri-browser").join("config.toml"))
+}
+
+/// Load the profile called `name` from the config file.
+pub fn load(name: &str) -> Result<Profile> {
+    let path = config_path().context("no home directory to find config.toml in")?;
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("reading profiles from {}", path.display()))?;
+    find(&content, name).with_context(|| format!("in {}", path.display()))
+}
+
+fn find(content: &str, name: &str) -> Result<Profile> {
+    let mut config: ConfigFile = toml::from_str(content)?;
+    match config.profiles.remove(name) {
+        Some(profile) => Ok(profile),
+        None => {
+            let known: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+            bail!(
+                "no profile named '{name}' (defined: {})",
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_are_found_by_name() {
+        let content = r#"
+            [profiles.editor]
+            app = "com.example.editor"
+
+            [profiles.staging]
+            port = 9300
+            token = "abc"
+            host = "10.0.0.5"
+        "#;
+        let staging = find(content, "staging").unwrap();
+        assert_eq!(staging.port, Some(9300));
+        assert_eq!(staging.host.as_deref(), Some("10.0.0.5"));
+        assert_eq!(staging.app, None);
+
+        let err = find(content, "prod").unwrap_err().to_string();
+        assert_eq!(err, "no profile named 'prod' (defined: editor, staging)");
+
+        assert!(find("[profiles.x]\nprot = 1", "x").is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use anyhow::Result;
+use rustyline::error::ReadlineError;
+use rustyline::history::MemHistory;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Editor, Helper, completion::Completer, highlight::Highlighter, hint::Hinter};
+use serde_json::Value;
+
+use tauri_browser_client::client::{BridgeClient, SnapshotOptions};
+use tauri_browser_client::output::{self, Format};
+
+const HELP: &str = "\
+Enter JavaScript to evaluate it in the webview. Unbalanced (), [] or {}
+continue onto the next line. Each input runs in its own scope: use `return`
+in multi-line input, and assign to `window.x` to keep values between inputs.
+
+  .snapshot [-i|-l|-a|-H]
+                        DOM snapshot (-i: interactive only, -l: locators,
+                        -a: with id/class, -H: include hidden elements)
+  .refs                 refs still live in the DOM
+  .click <selector>     click an element by @ref or CSS selector
+  .fill <selector> <t>  fill an input
+  .state                dump managed state
+  .windows              list windows
+  .history              show entered inputs
+  .help                 show this help
+  .exit                 quit (or Ctrl-D)";
+
+/// Interactive read-eval-print loop over one connection, with line editing
+/// and recall of earlier inputs. Input with open brackets keeps the editor
+/// open for another line (see [`JsInput`]), so a whole block can be edited
+/// before it runs.
+pub async fn run(client: &BridgeClient, format: &Format) -> Result<()> {
+    let mut editor: Editor<JsInput, MemHistory> = Editor::with_history(
+        rustyline::Config::builder().auto_add_history(false).build(),
+        MemHistory::new(),
+    )?;
+    editor.set_helper(Some(JsInput));
+
+    eprintln!("tauri-browser repl — .help for commands, .exit to quit");
+    loop {
+        let input = match tokio::task::block_in_place(|| editor.readline("> ")) {
+            Ok(input) => input,
+            Err(ReadlineError::Eof) => break,
+            // Ctrl-C drops the input being edited, like a shell.
+            Err(ReadlineError::Interrupted) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let trimmed = input.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(trimmed)?;
+
+        if let Some(meta) = trimmed.strip_prefix('.') {
+            let history: Vec<&str> = editor.history().into_iter().map(String::as_str).collect();
+            match meta_command(client, meta, &history, format).await {
+                Ok(Flow::Exit) => break,
+                Ok(Flow::Continue) => {}
+                Err(e) => eprintln!("error: {e:#}"),
+            }
+            continue;
+        }
+        match client.run_js(&input).await {
+            Ok(result) => print_eval(&result, format),
+            Err(e) => eprintln!("error: {e:#}"),
+        }
+    }
+    Ok(())
+}
+
+enum Flow {
+    Continue,
+    Exit,
+}
+
+/// Editor helper that holds JavaScript with unclosed brackets open for
+/// another line, at a terminal or reading piped input.
+struct JsInput;
+
+impl Validator for JsInput {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        Ok(if wants_more(ctx.input()) {
+            ValidationResult::Incomplete
+        } else {
+            ValidationResult::Valid(None)
+        })
+    }
+}
+
+impl Completer for JsInput {
+    type Candidate = String;
+}
+
+impl Hinter for JsInput {
+    type Hint = String;
+}
+
+impl Highlighter for JsInput {}
+
+impl Helper for JsInput {}
+
+async fn meta_command(
+    client: &BridgeClient,
+    command: &str,
+    history: &[&str],
+    format: &Format,
+) -> Result<Flow> {
+    let (name, rest) = command
+        .split_once(char::is_whitespace)
+        .map(|(n, r)| (n, r.trim()))
+        .unwrap_or((command, ""));
+    let value = match name {
+        "exit" | "quit" => return Ok(Flow::Exit),
+        "help" => {
+            println!("{HELP}");
+            return Ok(Flow::Continue);
+        }
+        "history" => {
+            for (i, entry) in history.iter().enumerate() {
+                println!("{:>4}  {}", i + 1, entry.replace('\n', "\n      "));
+            }
+            return Ok(Flow::Continue);
+        }
+        "snapshot" => {
+            let flags: Vec<&str> = rest.split_whitespace().collect();
+            client
+                .snapshot(SnapshotOptions {
+                    interactive: flags.contains(&"-i"),
+                    locators: flags.contains(&"-l"),
+                    attrs: flags.contains(&"-a"),
+                    include_hidden: flags.contains(&"-H"),
+                    ..Default::default()
+                })
+                .await?
+        }
+        "refs" => client.live_refs(None).await?,
+        "click" if !rest.is_empty() => client.click(rest).await?,
+        "fill" => match rest.split_once(char::is_whitespace) {
+            Some((selector, text)) => client.fill(selector, text.trim_start()).await?,
+            None => anyhow::bail!("usage: .fill <selector> <text>"),
+        },
+        "state" => client.state().await?,
+        "windows" => {
+            output::print_windows(&client.windows().await?, format);
+            return Ok(Flow::Continue);
+        }
+        "click" => anyhow::bail!("usage: .click <selector>"),
+        _ => anyhow::bail!("unknown command .{name} (try .help)"),
+    };
+    output::print(&value, format);
+    Ok(Flow::Continue)
+}
+
+/// Print an eval result inline: the value on success (`undefined` when the
+/// code returned nothing), the error and stack otherwise.
+fn print_eval(result: &Value, format: &Format) {
+    if result["success"].as_bool() == Some(false) {
+        eprintln!("{}", result["error"].as_str().unwrap_or("eval failed"));
+        if let Some(stack) = result["stack"].as_str() {
+            eprintln!("{stack}");
+        }
+        return;
+    }
+    if result["undefined"] == true && matches!(format, Format::Text) {
+        println!("undefined");
+        return;
+    }
+    output::print(&result["value"], format);
+}
+
+/// Whether the editor should read another line: JavaScript that
+/// [`is_incomplete`]. Meta-commands are always complete.
+fn wants_more(input: &str) -> bool {
+    !input.trim_start().starts_with('.') && is_incomplete(input)
+}
+
+/// Whether `code` has unclosed brackets, ignoring those inside strings,
+/// template literals, and comments, so input should continue on the next
+/// line.
+fn is_incomplete(code: &str) -> bool {
+    let mut depth: i32 = 0;
+    let mut chars = code.chars().peekable();
+    let mut quote: Option<char> = None;
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                c if c == q => quote = None,
+                // Only template literals may span lines.
+                '\n' if q != '`' => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' | '`' => quote = Some(c),
+            '/' if chars.peek() == Some(&'/') => {
+                // Line comment: skip to end of line.
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        closed = true;
+                        break;
+                    }
+                    prev = c;
+                }
+                if !closed {
+                    return true;
+                }
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    // An open template literal spans lines; other open quotes are errors the
+    // webview should report, not a reason to wait for more input.
+    depth > 0 || quote == Some('`')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balanced_input_is_complete() {
+        assert!(!is_incomplete("document.title"));
+        assert!(!is_incomplete("[1, 2].map(x => { return x * 2 })"));
+        assert!(!is_incomplete("'('"));
+        assert!(!is_incomplete("1 // {"));
+        assert!(!is_incomplete("/* [ */ 2"));
+        assert!(!is_incomplete("'unterminated\n1"));
+    }
+
+    #[test]
+    fn open_brackets_continue_input() {
+        assert!(is_incomplete("function f() {"));
+        assert!(is_incomplete("const xs = ["));
+        assert!(is_incomplete("foo(\n  1,"));
+        assert!(is_incomplete("`line one"));
+        assert!(is_incomplete("/* still a comment"));
+        assert!(!is_incomplete("function f() {\n  return 1;\n}"));
+    }
+
+    #[test]
+    fn meta_commands_never_continue() {
+        assert!(wants_more("window.list = ["));
+        assert!(!wants_more(".fill #q (unbalanced"));
+        assert!(!wants_more("  .help"));
+    }
+}
@@ -0,0 +1,297 @@
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri_browser_client::client::{BridgeClient, SnapshotOptions};
+
+/// Console messages included in a report.
+const CONSOLE_MESSAGES: usize = 200;
+
+/// Config keys whose values are replaced before writing, matched anywhere
+/// in the key and case-insensitively.
+const SECRET_KEY_PARTS: &[&str] = &["token", "secret", "password", "credential", "key"];
+
+/// What replaces a redacted value.
+const REDACTED: &str = "[redacted]";
+
+/// `manifest.json`: what the bundle holds and what couldn't be gathered.
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    pub dir: PathBuf,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+    pub cli_version: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_version: Option<String>,
+    pub files: Vec<String>,
+    /// Why each missing item is missing, by the file it would have been.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub failed: BTreeMap<String, String>,
+    /// JSON pointers to the values replaced with `[redacted]`, by file.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub redacted: BTreeMap<String, Vec<String>>,
+}
+
+enum Artifact {
+    Json(Value),
+    Png(Vec<u8>),
+}
+
+impl Manifest {
+    /// Redact `value` with [`redact`] where `secret` matches a key, noting
+    /// what was replaced under `name`.
+    fn redact_secrets(
+        &mut self,
+        name: &str,
+        mut value: Value,
+        secret: fn(&str) -> bool,
+    ) -> Artifact {
+        let mut pointers = Vec::new();
+        redact(&mut value, secret, "", &mut pointers);
+        if !pointers.is_empty() {
+            self.redacted.insert(name.to_string(), pointers);
+        }
+        Artifact::Json(value)
+    }
+
+    /// Write `artifact` as `name` in the bundle, or note why it's missing.
+    fn record(&mut self, name: &str, artifact: Result<Artifact>) {
+        let written = artifact.and_then(|artifact| {
+            let bytes = match artifact {
+                Artifact::Json(value) => serde_json::to_vec_pretty(&value)?,
+                Artifact::Png(png) => png,
+            };
+            let path = self.dir.join(name);
+            std::fs::write(&path, bytes).with_context(|| format!("writing {}", path.display()))
+        });
+        match written {
+            Ok(()) => self.files.push(name.to_string()),
+            Err(e) => {
+                self.failed.insert(name.to_string(), format!("{e:#}"));
+            }
+        }
+    }
+}
+
+/// The default bundle directory, named for when it was made.
+pub fn default_dir() -> PathBuf {
+    PathBuf::from(format!("tauri-browser-report-{}", now()))
+}
+
+/// Gather everything useful for a bug report into `dir`, which must not
+/// exist yet or be empty. One failing call doesn't stop the rest; the
+/// manifest lists what's missing and why.
+pub async fn collect(client: &BridgeClient, dir: &Path) -> Result<Manifest> {
+    if dir
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some())
+    {
+        bail!("{} already exists and isn't empty", dir.display());
+    }
+    std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+
+    let mut manifest = Manifest {
+        dir: dir.to_path_buf(),
+        created_at: now(),
+        cli_version: env!("CARGO_PKG_VERSION"),
+        plugin_version: None,
+        files: Vec::new(),
+        failed: BTreeMap::new(),
+        redacted: BTreeMap::new(),
+    };
+
+    let health = client.health().await;
+    if let Ok(health) = &health {
+        manifest.plugin_version = health["version"].as_str().map(str::to_string);
+    }
+    manifest.record("health.json", health.map(Artifact::Json));
+    manifest.record("app.json", client.app_info().await.map(Artifact::Json));
+    manifest.record("env.json", client.env().await.map(Artifact::Json));
+    let config = client.config().await;
+    let config = config.map(|config| manifest.redact_secrets("config.json", config, is_secret_key));
+    manifest.record("config.json", config);
+    manifest.record(
+        "capabilities.json",
+        client.capabilities().await.map(Artifact::Json),
+    );
+    manifest.record("windows.json", client.windows().await.map(Artifact::Json));
+    let snapshot = client
+        .snapshot(SnapshotOptions {
+            attrs: true,
+            ..Default::default()
+        })
+        .await;
+    // Form fields can hold passwords and personal data; the bundle is meant
+    // to be attached to public issues.
+    let snapshot = snapshot
+        .map(|snapshot| manifest.redact_secrets("snapshot.json", snapshot, |key| key == "value"));
+    manifest.record("snapshot.json", snapshot);
+    manifest.record(
+        "screenshot.png",
+        client
+            .screenshot(false, false)
+            .await
+            .map(|(png, _)| Artifact::Png(png)),
+    );
+    manifest.record(
+        "console.json",
+        client
+            .console_recent(CONSOLE_MESSAGES, Some("*"))
+            .await
+            .map(Artifact::Json),
+    );
+
+    let path = dir.join("manifest.json");
+    std::fs::write(&path, serde_json::to_vec_pretty(&manifest)?)
+        .with_context(|| format!("writing {}", path.display()))?;
+    Ok(manifest)
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_KEY_PARTS.iter().any(|part| key.contains(part))
+}
+
+/// Replace the value of every key `secret` matches, at any depth, pushing
+/// the JSON pointer (under `at`) of each one replaced.
+fn redact(value: &mut Value, secret: fn(&str) -> bool, at: &str, redacted: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let pointer = format!("{at}/{}", key.replace('~', "~0").replace('/', "~1"));
+                if secret(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                    redacted.push(pointer);
+                } else {
+                    redact(value, secret, &pointer, redacted);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                redact(item, secret, &format!("{at}/{i}"), redacted);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_secrets_are_redacted() {
+        let mut config = serde_json::json!({
+            "identifier": "com.example.app",
+            "plugins": {
+                "debug-bridge": { "port": 9229, "token": "ci-token" },
+                "updater": { "pubkey": "abc", "endpoints": ["https://example.com"] },
+                "custom": [{ "apiSecret": "s3cret", "name": "x" }],
+            },
+        });
+        let mut redacted = Vec::new();
+        redact(&mut config, is_secret_key, "", &mut redacted);
+        assert_eq!(config["identifier"], "com.example.app");
+        assert_eq!(config["plugins"]["debug-bridge"]["port"], 9229);
+        assert_eq!(config["plugins"]["debug-bridge"]["token"], "[redacted]");
+        assert_eq!(config["plugins"]["updater"]["pubkey"], "[redacted]");
+        assert_eq!(config["plugins"]["custom"][0]["apiSecret"], "[redacted]");
+        assert_eq!(config["plugins"]["custom"][0]["name"], "x");
+        assert_eq!(
+            redacted,
+            [
+                "/plugins/custom/0/apiSecret",
+                "/plugins/debug-bridge/token",
+                "/plugins/updater/pubkey",
+            ]
+        );
+    }
+
+    #[test]
+    fn snapshot_field_values_are_redacted() {
+        let dir = std::env::temp_dir().join(format!(
+            "tauri-browser-report-values-{}",
+            std::process::id()
+        ));
+        let mut manifest = Manifest {
+            dir,
+            created_at: 0,
+            cli_version: "0.0.0",
+            plugin_version: None,
+            files: Vec::new(),
+            failed: BTreeMap::new(),
+            redacted: BTreeMap::new(),
+        };
+        let snapshot = serde_json::json!({
+            "title": "Sign in",
+            "elements": [{
+                "tag": "form",
+                "children": [
+                    { "tag": "input", "ref": "@e1", "name": "email", "value": "ada@example.com" },
+                    { "tag": "input", "ref": "@e2", "name": "password", "value": "hunter2" },
+                    { "tag": "button", "ref": "@e3", "text": "Sign in" },
+                ],
+            }],
+        });
+        let Artifact::Json(snapshot) =
+            manifest.redact_secrets("snapshot.json", snapshot, |key| key == "value")
+        else {
+            panic!("expected JSON");
+        };
+        let fields = &snapshot["elements"][0]["children"];
+        assert_eq!(fields[0]["value"], "[redacted]");
+        assert_eq!(fields[1]["value"], "[redacted]");
+        assert_eq!(fields[1]["name"], "password");
+        assert_eq!(fields[2]["text"], "Sign in");
+        assert_eq!(
+            manifest.redacted["snapshot.json"],
+            [
+                "/elements/0/children/0/value",
+                "/elements/0/children/1/value"
+            ]
+        );
+    }
+
+    #[test]
+    fn failures_are_noted_not_fatal() {
+        let dir = std::env::temp_dir().join(format!("tauri-browser-report-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut manifest = Manifest {
+            dir: dir.clone(),
+            created_at: 0,
+            cli_version: "0.0.0",
+            plugin_version: None,
+            files: Vec::new(),
+            failed: BTreeMap::new(),
+            redacted: BTreeMap::new(),
+        };
+        manifest.record(
+            "windows.json",
+            Ok(Artifact::Json(serde_json::json!(["main"]))),
+        );
+        manifest.record(
+            "screenshot.png",
+            Err(anyhow::anyhow!(
+                "screenshot not yet implemented on this platform"
+            )),
+        );
+        assert_eq!(manifest.files, ["windows.json"]);
+        assert_eq!(
+            manifest.failed["screenshot.png"],
+            "screenshot not yet implemented on this platform"
+        );
+        assert!(dir.join("windows.json").exists());
+        assert!(!dir.join("screenshot.png").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,376 @@
+use anyhow::{Context, Result, bail};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use tauri_browser_client::client::{BridgeClient, SnapshotOptions};
+
+/// One action in a script file, e.g. `{"action": "click", "selector": "@e3"}`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    Snapshot {
+        #[serde(default)]
+        interactive: bool,
+        #[serde(default)]
+        locators: bool,
+        #[serde(default)]
+        attrs: bool,
+        #[serde(default)]
+        include_hidden: bool,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        wait_ready: bool,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        reset_refs: bool,
+        /// Element fields to keep, e.g. `["ref", "text"]`; all when empty.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        fields: Vec<String>,
+    },
+    Screenshot {
+        path: String,
+        #[serde(default)]
+        wait_ready: bool,
+        #[serde(default)]
+        full_page: bool,
+    },
+    Styles {
+        selector: String,
+        #[serde(default)]
+        props: Vec<String>,
+    },
+    State,
+    Commands,
+    Windows,
+    Eval {
+        js: String,
+        /// Values the code reads as `__env.<key>`.
+        #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+        bindings: serde_json::Map<String, Value>,
+        /// Wait for `document.readyState` to be `complete` first.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        wait_ready: bool,
+    },
+    Invoke {
+        command: String,
+        #[serde(default = "empty_args")]
+        args: Value,
+    },
+    Click {
+        selector: String,
+    },
+    Fill {
+        selector: String,
+        text: String,
+    },
+    /// Wait for the page a previous step navigated to.
+    WaitNavigation {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url_contains: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_ms: Option<u64>,
+        /// Page loads to count from; filled in from the last `click` when
+        /// not given.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        since: Option<u64>,
+    },
+}
+
+fn empty_args() -> Value {
+    Value::Object(Default::default())
+}
+
+impl Step {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Step::Snapshot { .. } => "snapshot",
+            Step::Screenshot { .. } => "screenshot",
+            Step::Styles { .. } => "styles",
+            Step::State => "state",
+            Step::Commands => "commands",
+            Step::Windows => "windows",
+            Step::Eval { .. } => "eval",
+            Step::Invoke { .. } => "invoke",
+            Step::Click { .. } => "click",
+            Step::Fill { .. } => "fill",
+            Step::WaitNavigation { .. } => "wait_navigation",
+        }
+    }
+
+    /// Interactions depend on the page state left by the previous step, so
+    /// they must never be reordered. Reads are safe to run concurrently.
+    /// `eval` and `invoke` are allowed too; the script author is responsible
+    /// for only grouping calls that don't depend on each other.
+    fn parallel_safe(&self) -> bool {
+        !matches!(
+            self,
+            Step::Click { .. } | Step::Fill { .. } | Step::WaitNavigation { .. }
+        )
+    }
+}
+
+/// A top-level script entry: a single step, or a `{"parallel": [...]}` group
+/// whose steps run concurrently.
+#[derive(Debug)]
+pub enum Entry {
+    Step(Step),
+    Parallel(Vec<Step>),
+}
+
+/// Parse a script: a JSON array of steps and `{"parallel": [...]}` groups.
+pub fn parse(source: &str) -> Result<Vec<Entry>> {
+    let raw: Vec<Value> = serde_json::from_str(source).context("script must be a JSON array")?;
+    raw.into_iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let n = i + 1;
+            match value.get("parallel") {
+                Some(group) => {
+                    let steps: Vec<Step> = serde_json::from_value(group.clone())
+                        .with_context(|| format!("entry {n}: invalid parallel group"))?;
+                    if let Some(step) = steps.iter().find(|s| !s.parallel_safe()) {
+                        bail!(
+                            "entry {n}: `{}` can't run in a parallel group; interactions must stay ordered",
+                            step.name()
+                        );
+                    }
+                    Ok(Entry::Parallel(steps))
+                }
+                None => serde_json::from_value(value)
+                    .map(Entry::Step)
+                    .with_context(|| format!("entry {n}: invalid step")),
+            }
+        })
+        .collect()
+}
+
+/// Result of one executed step, reported in script order.
+#[derive(Debug, Serialize)]
+pub struct StepOutcome {
+    /// 1-based position of the step, counting steps inside groups.
+    pub step: usize,
+    pub action: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Run a script, executing at most `parallel` steps of a group at once.
+/// Stops after the first entry with a failing step. Outcomes are always in
+/// script order, regardless of completion order. A `wait_navigation`
+/// counts page loads from just before the last `click`, so it also catches
+/// a navigation that click started before the wait began.
+pub async fn run(client: &BridgeClient, entries: &[Entry], parallel: usize) -> Vec<StepOutcome> {
+    let mut outcomes = Vec::new();
+    let mut clicked_at = None;
+    for entry in entries {
+        let chained;
+        let steps = match entry {
+            Entry::Step(Step::WaitNavigation {
+                url_contains,
+                timeout_ms,
+                since: None,
+            }) if clicked_at.is_some() => {
+                chained = Step::WaitNavigation {
+                    url_contains: url_contains.clone(),
+                    timeout_ms: *timeout_ms,
+                    since: clicked_at,
+                };
+                std::slice::from_ref(&chained)
+            }
+            Entry::Step(step) => std::slice::from_ref(step),
+            Entry::Parallel(steps) => steps.as_slice(),
+        };
+        let first = outcomes.len() + 1;
+        let results: Vec<StepOutcome> = futures_util::stream::iter(steps.iter().enumerate())
+            .map(|(i, step)| async move {
+                let (value, error) = match run_step(client, step).await {
+                    Ok(value) => (Some(value), None),
+                    Err(e) => (None, Some(format!("{e:#}"))),
+                };
+                StepOutcome {
+                    step: first + i,
+                    action: step.name(),
+                    value,
+                    error,
+                }
+            })
+            .buffered(parallel.max(1))
+            .collect()
+            .await;
+
+        for outcome in &results {
+            match outcome.action {
+                "click" => {
+                    clicked_at = outcome
+                        .value
+                        .as_ref()
+                        .and_then(|v| v["loads_started"].as_u64());
+                }
+                "wait_navigation" => clicked_at = None,
+                _ => {}
+            }
+        }
+        let failed = results.iter().any(|o| o.error.is_some());
+        outcomes.extend(results);
+        if failed {
+            break;
+        }
+    }
+    outcomes
+}
+
+pub async fn run_step(client: &BridgeClient, step: &Step) -> Result<Value> {
+    match step {
+        Step::Snapshot {
+            interactive,
+            locators,
+            attrs,
+            include_hidden,
+            wait_ready,
+            reset_refs,
+            fields,
+        } => {
+            client
+                .snapshot(SnapshotOptions {
+                    interactive: *interactive,
+                    locators: *locators,
+                    attrs: *attrs,
+                    include_hidden: *include_hidden,
+                    wait_ready: *wait_ready,
+                    reset_refs: *reset_refs,
+                    fields: fields.clone(),
+                })
+                .await
+        }
+        Step::Screenshot {
+            path,
+            wait_ready,
+            full_page,
+        } => {
+            let warning = client
+                .screenshot_to_file(path, *wait_ready, *full_page)
+                .await?;
+            Ok(serde_json::json!({ "path": path, "warning": warning }))
+        }
+        Step::Styles { selector, props } => client.computed_styles(selector, props).await,
+        Step::State => client.state().await,
+        Step::Commands => client.commands().await,
+        Step::Windows => client.windows().await,
+        Step::Eval {
+            js,
+            bindings,
+            wait_ready,
+        } => {
+            if *wait_ready {
+                client.run_js_when_ready(js, bindings).await
+            } else {
+                client.run_js_with_bindings(js, bindings).await
+            }
+        }
+        Step::Invoke { command, args } => client.invoke(command, &args.to_string()).await,
+        Step::Click { selector } => client.click(selector).await,
+        Step::Fill { selector, text } => client.fill(selector, text).await,
+        Step::WaitNavigation {
+            url_contains,
+            timeout_ms,
+            since,
+        } => {
+            client
+                .wait_navigation(
+                    url_contains.as_deref(),
+                    timeout_ms.map(std::time::Duration::from_millis),
+                    *since,
+                )
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_steps_and_parallel_groups() {
+        let entries = parse(
+            r#"[
+                {"action": "click", "selector": "@e1"},
+                {"parallel": [
+                    {"action": "snapshot"},
+                    {"action": "screenshot", "path": "a.png", "full_page": true}
+                ]},
+                {"action": "invoke", "command": "greet"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert!(matches!(entries[0], Entry::Step(Step::Click { .. })));
+        assert!(matches!(&entries[1], Entry::Parallel(steps) if steps.len() == 2));
+        match &entries[2] {
+            Entry::Step(Step::Invoke { args, .. }) => assert_eq!(args, &empty_args()),
+            other => panic!("unexpected entry {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_interactions_in_parallel_groups() {
+        let err = parse(r#"[{"parallel": [{"action": "snapshot"}, {"action": "fill", "selector": ".name", "text": "x"}]}]"#)
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("`fill` can't run in a parallel group")
+        );
+    }
+
+    #[tokio::test]
+    async fn waits_count_loads_from_the_last_click() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0; 4096];
+                let n = socket.read(&mut request).await.unwrap();
+                let head = String::from_utf8_lossy(&request[..n]).to_string();
+                let (status, body) = if head.starts_with("POST /click ") {
+                    (
+                        "200 OK",
+                        r#"{"success":true,"value":true,"error":null,"loads_started":3}"#,
+                    )
+                } else if head.starts_with("POST /wait/navigation?since=3 ") {
+                    (
+                        "200 OK",
+                        r#"{"url":"tauri://localhost/next","elapsed_ms":0}"#,
+                    )
+                } else {
+                    (
+                        "404 Not Found",
+                        r#"{"error":"unexpected request","code":"not_found"}"#,
+                    )
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let entries =
+            parse(r#"[{"action": "click", "selector": "@e1"}, {"action": "wait_navigation"}]"#)
+                .unwrap();
+        let client = BridgeClient::builder(port).build();
+        let outcomes = run(&client, &entries, 1).await;
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes.iter().all(|o| o.error.is_none()), "{outcomes:?}");
+    }
+
+    #[test]
+    fn reports_entry_of_invalid_step() {
+        let err = parse(r#"[{"action": "state"}, {"action": "teleport"}]"#).unwrap_err();
+        assert!(err.to_string().starts_with("entry 2"));
+    }
+}
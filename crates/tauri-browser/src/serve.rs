@@ -0,0 +1,233 @@
+use anyhow::{Context, Result};
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, State},
+    http::{HeaderMap, Method, StatusCode, Uri, header},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use std::sync::Arc;
+
+use tauri_browser_client::client::BridgeClient;
+
+/// Request headers passed through to the bridge. Anything carrying
+/// credentials is left behind; the proxy adds the token itself.
+const FORWARDED_REQUEST_HEADERS: &[header::HeaderName] = &[
+    header::ACCEPT,
+    header::CONTENT_TYPE,
+    header::CONTENT_ENCODING,
+];
+
+/// Response headers not copied back: the proxy re-frames the body.
+const HOP_BY_HOP: &[header::HeaderName] = &[
+    header::CONNECTION,
+    header::CONTENT_LENGTH,
+    header::TRANSFER_ENCODING,
+];
+
+/// Serve the inspector page on `127.0.0.1:port` and relay every other
+/// request to the bridge with the token added, until interrupted.
+pub async fn run(client: BridgeClient, port: u16) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .with_context(|| format!("binding 127.0.0.1:{port}"))?;
+    let port = listener.local_addr()?.port();
+    let app = Router::new()
+        .route("/", get(|| async { Html(INSPECTOR_HTML) }))
+        .fallback(proxy)
+        .layer(DefaultBodyLimit::disable())
+        .with_state(Arc::new(Proxy { client, port }));
+    eprintln!("inspector at http://127.0.0.1:{port}/ (Ctrl-C to stop)");
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+struct Proxy {
+    client: BridgeClient,
+    port: u16,
+}
+
+async fn proxy(
+    State(proxy): State<Arc<Proxy>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if !from_this_origin(&headers, proxy.port) {
+        return (
+            StatusCode::FORBIDDEN,
+            "requests must come from the inspector's own origin",
+        )
+            .into_response();
+    }
+    if headers.contains_key(header::UPGRADE) {
+        return (
+            StatusCode::NOT_IMPLEMENTED,
+            "WebSocket streams aren't proxied; use `tauri-browser console` or `events listen`",
+        )
+            .into_response();
+    }
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+    let resp = match proxy
+        .client
+        .forward(method, path, forwarded_headers(&headers), body.to_vec())
+        .await
+    {
+        Ok(resp) => resp,
+        Err(e) => return (StatusCode::BAD_GATEWAY, format!("{e:#}")).into_response(),
+    };
+    let status = resp.status();
+    let mut headers = resp.headers().clone();
+    for name in HOP_BY_HOP {
+        headers.remove(name);
+    }
+    match resp.bytes().await {
+        Ok(bytes) => (status, headers, Body::from(bytes)).into_response(),
+        Err(e) => (StatusCode::BAD_GATEWAY, e.to_string()).into_response(),
+    }
+}
+
+/// Whether a request was addressed to this proxy by a loopback name and,
+/// if it names an origin, came from the inspector page. This keeps other
+/// sites open in the browser (and DNS-rebound hostnames) from using the
+/// token the proxy holds.
+fn from_this_origin(headers: &HeaderMap, port: u16) -> bool {
+    let Some(host) = headers.get(header::HOST).and_then(|h| h.to_str().ok()) else {
+        return false;
+    };
+    let loopback = [format!("127.0.0.1:{port}"), format!("localhost:{port}")];
+    if !loopback.iter().any(|name| name == host) {
+        return false;
+    }
+    match headers.get(header::ORIGIN) {
+        None => true,
+        Some(origin) => origin.to_str().ok() == Some(format!("http://{host}").as_str()),
+    }
+}
+
+fn forwarded_headers(headers: &HeaderMap) -> HeaderMap {
+    let mut forwarded = HeaderMap::new();
+    for name in FORWARDED_REQUEST_HEADERS {
+        if let Some(value) = headers.get(name) {
+            forwarded.insert(name, value.clone());
+        }
+    }
+    forwarded
+}
+
+/// The page served at `/`: buttons for the common reads, an eval box, and
+/// a pane showing whatever came back.
+const INSPECTOR_HTML: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>tauri-browser inspector</title>
+<style>
+  body { font: 14px system-ui, sans-serif; margin: 1.5rem; color: #1f2328; }
+  nav button { margin-right: .4rem; }
+  textarea { width: 100%; height: 5rem; font: 13px ui-monospace, monospace; margin-top: 1rem; }
+  pre { background: #f6f8fa; padding: 1rem; overflow: auto; max-height: 70vh; }
+  img { max-width: 100%; border: 1px solid #d0d7de; }
+  .error { color: #cf222e; }
+</style>
+</head>
+<body>
+<h1>tauri-browser inspector</h1>
+<nav>
+  <button data-get="/health">Health</button>
+  <button data-get="/snapshot?interactive=true">Snapshot</button>
+  <button data-get="/windows">Windows</button>
+  <button data-get="/console/recent?n=50">Console</button>
+  <button data-get="/capabilities">Capabilities</button>
+  <button id="screenshot">Screenshot</button>
+</nav>
+<textarea id="js" placeholder="document.title"></textarea>
+<button id="eval">Run JS</button>
+<div id="out"></div>
+<script>
+const out = document.getElementById('out');
+function show(text, ok) {
+  const pre = document.createElement('pre');
+  pre.textContent = text;
+  if (!ok) pre.className = 'error';
+  out.replaceChildren(pre);
+}
+async function call(path, init) {
+  try {
+    const resp = await fetch(path, init);
+    const text = await resp.text();
+    let body = text;
+    try { body = JSON.stringify(JSON.parse(text), null, 2); } catch (_) {}
+    show(body, resp.ok);
+  } catch (e) {
+    show(String(e), false);
+  }
+}
+for (const button of document.querySelectorAll('[data-get]')) {
+  button.onclick = () => call(button.dataset.get);
+}
+document.getElementById('eval').onclick = () => call('/eval', {
+  method: 'POST',
+  headers: { 'Content-Type': 'application/json' },
+  body: JSON.stringify({ js: document.getElementById('js').value || 'document.title' }),
+});
+document.getElementById('screenshot').onclick = async () => {
+  const resp = await fetch('/screenshot');
+  if (!resp.ok) return show(await resp.text(), false);
+  const img = document.createElement('img');
+  img.src = URL.createObjectURL(await resp.blob());
+  out.replaceChildren(img);
+};
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.clone(), value.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn only_the_inspector_origin_gets_through() {
+        let ok = |pairs: &[(header::HeaderName, &str)]| from_this_origin(&headers(pairs), 9230);
+        assert!(ok(&[(header::HOST, "127.0.0.1:9230")]));
+        assert!(ok(&[
+            (header::HOST, "localhost:9230"),
+            (header::ORIGIN, "http://localhost:9230"),
+        ]));
+        assert!(!ok(&[]), "no Host header");
+        assert!(!ok(&[(header::HOST, "evil.example:9230")]), "rebound name");
+        assert!(!ok(&[
+            (header::HOST, "127.0.0.1:9230"),
+            (header::ORIGIN, "https://evil.example"),
+        ]));
+    }
+
+    #[test]
+    fn credentials_are_not_forwarded() {
+        let forwarded = forwarded_headers(&headers(&[
+            (header::CONTENT_TYPE, "application/json"),
+            (header::AUTHORIZATION, "Bearer guess"),
+            (header::COOKIE, "session=1"),
+            (
+                header::HeaderName::from_static("x-debug-bridge-token"),
+                "guess",
+            ),
+        ]));
+        assert_eq!(forwarded.len(), 1);
+        assert_eq!(forwarded[header::CONTENT_TYPE], "application/json");
+    }
+}
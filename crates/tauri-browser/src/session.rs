@@ -0,0 +1,240 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::script::{self, Step};
+use tauri_browser_client::client::BridgeClient;
+
+/// One recorded command: when it ran, what it did, and what came back.
+/// A session file holds one record per line.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Record {
+    /// Unix time in milliseconds when the command started.
+    pub at: u64,
+    pub step: Step,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Runs commands as script steps, appending each one and its result to a
+/// session file when recording.
+pub struct Recorder {
+    path: Option<PathBuf>,
+    /// Whether anything was recorded, or the gap already reported.
+    recorded: std::cell::Cell<bool>,
+}
+
+impl Recorder {
+    /// Records to `path`, or just runs steps when it's `None`.
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            recorded: Default::default(),
+        }
+    }
+
+    /// Whether recording is on but the command recorded nothing: it has no
+    /// script step, so a replay would skip it.
+    fn unrecorded(&self) -> bool {
+        self.path.is_some() && !self.recorded.get()
+    }
+
+    /// Warn, once, that the command isn't in the session file.
+    pub fn warn_if_unrecorded(&self) {
+        if self.unrecorded() {
+            self.recorded.set(true);
+            eprintln!(
+                "warning: not recorded: this command has no script step, so a replay of the session will skip it"
+            );
+        }
+    }
+
+    pub async fn run(&self, client: &BridgeClient, step: Step) -> Result<Value> {
+        let at = now_ms();
+        let result = script::run_step(client, &step).await;
+        if let Some(path) = &self.path {
+            let record = Record {
+                at,
+                step,
+                value: result.as_ref().ok().cloned(),
+                error: result.as_ref().err().map(|e| format!("{e:#}")),
+            };
+            append(path, &record).with_context(|| format!("recording to {}", path.display()))?;
+            self.recorded.set(true);
+        }
+        result
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Append a record, creating the file readable only by its owner: `fill`
+/// steps keep their text, passwords included.
+fn append(path: &Path, record: &Record) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Parse a session file: one JSON record per line, blank lines ignored.
+pub fn parse(source: &str) -> Result<Vec<Record>> {
+    let records = source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| format!("line {}: invalid record", i + 1))
+        })
+        .collect::<Result<Vec<Record>>>()?;
+    if records.is_empty() {
+        bail!("session file has no records");
+    }
+    Ok(records)
+}
+
+/// Result of one replayed step, next to what was recorded when it differs.
+#[derive(Debug, Serialize)]
+pub struct ReplayOutcome {
+    /// 1-based position of the step in the session.
+    pub step: usize,
+    pub action: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Whether the result differs from the recorded one.
+    pub changed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recorded_value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recorded_error: Option<String>,
+}
+
+/// Re-run every recorded step in order. With `speed`, steps keep their
+/// recorded spacing divided by it (1 is real time, 2 twice as fast);
+/// without, they run back to back. Failing steps don't stop the replay,
+/// since a recorded failure may be the bug being reproduced.
+pub async fn replay(
+    client: &BridgeClient,
+    records: &[Record],
+    speed: Option<f64>,
+) -> Vec<ReplayOutcome> {
+    let start = tokio::time::Instant::now();
+    let mut outcomes = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        if let Some(speed) = speed {
+            tokio::time::sleep_until(start + offset(records, i, speed)).await;
+        }
+        let (value, error) = match script::run_step(client, &record.step).await {
+            Ok(value) => (Some(value), None),
+            Err(e) => (None, Some(format!("{e:#}"))),
+        };
+        let changed = value != record.value || error != record.error;
+        outcomes.push(ReplayOutcome {
+            step: i + 1,
+            action: record.step.name(),
+            value,
+            error,
+            changed,
+            recorded_value: record.value.clone().filter(|_| changed),
+            recorded_error: record.error.clone().filter(|_| changed),
+        });
+    }
+    outcomes
+}
+
+/// When step `i` should start, relative to the first, at `speed`.
+fn offset(records: &[Record], i: usize, speed: f64) -> Duration {
+    let elapsed = records[i].at.saturating_sub(records[0].at);
+    Duration::from_secs_f64(elapsed as f64 / 1000.0 / speed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_round_trip_through_session_lines() {
+        let record = Record {
+            at: 1_700_000_000_000,
+            step: Step::Click {
+                selector: "@e3".to_string(),
+            },
+            value: Some(serde_json::json!({ "success": true, "value": true })),
+            error: None,
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert_eq!(
+            line,
+            r#"{"at":1700000000000,"step":{"action":"click","selector":"@e3"},"value":{"success":true,"value":true}}"#
+        );
+
+        let records = parse(&format!("{line}\n\n{line}\n")).unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(matches!(&records[1].step, Step::Click { selector } if selector == "@e3"));
+
+        let err = parse(&format!("{line}\nnot json")).unwrap_err();
+        assert!(err.to_string().starts_with("line 2"));
+        assert!(parse("\n").is_err());
+    }
+
+    #[test]
+    fn commands_without_steps_are_flagged() {
+        assert!(!Recorder::new(None).unrecorded());
+
+        let path =
+            std::env::temp_dir().join(format!("tauri-browser-rec-{}.jsonl", std::process::id()));
+        let recorder = Recorder::new(Some(path.clone()));
+        assert!(recorder.unrecorded());
+        recorder.warn_if_unrecorded();
+        assert!(!recorder.unrecorded(), "warned once");
+
+        let record = Record {
+            at: 0,
+            step: Step::Fill {
+                selector: "#password".to_string(),
+                text: "hunter2".to_string(),
+            },
+            value: None,
+            error: None,
+        };
+        append(&path, &record).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn replay_spacing_scales_with_speed() {
+        let records: Vec<Record> = [1_000, 1_500, 4_000]
+            .into_iter()
+            .map(|at| Record {
+                at,
+                step: Step::State,
+                value: None,
+                error: None,
+            })
+            .collect();
+        assert_eq!(offset(&records, 0, 1.0), Duration::ZERO);
+        assert_eq!(offset(&records, 2, 1.0), Duration::from_secs(3));
+        assert_eq!(offset(&records, 1, 2.0), Duration::from_millis(250));
+    }
+}
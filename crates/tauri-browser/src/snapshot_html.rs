@@ -0,0 +1,169 @@
+use base64::Engine;
+use serde_json::Value;
+
+/// Inline styles, so the report is a single file with no external assets.
+const STYLE: &str = r#"
+body { font: 14px/1.5 system-ui, sans-serif; margin: 2em; color: #1f2328; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 1em; }
+header h1 { font-size: 1.3em; margin: 0; }
+header p { margin: 0.2em 0; color: #59636e; word-break: break-all; }
+.warning { color: #9a6700; }
+figure { margin: 1em 0; }
+figure img { max-width: 100%; border: 1px solid #d0d7de; }
+.tree { font-family: ui-monospace, monospace; font-size: 13px; }
+.tree details, .tree .leaf { margin-left: 1.2em; }
+.tree summary { cursor: pointer; }
+.tree .leaf { padding-left: 1em; }
+.tag { color: #0550ae; }
+.ref { background: #fff8c5; border: 1px solid #d4a72c; border-radius: 4px; padding: 0 4px; font-weight: bold; }
+.name { color: #116329; }
+.meta { color: #59636e; }
+.hidden { opacity: 0.5; }
+"#;
+
+/// Expand/collapse-all buttons.
+const SCRIPT: &str = r#"
+function setAll(open) {
+  document.querySelectorAll('.tree details').forEach((d) => { d.open = open; });
+}
+"#;
+
+/// A standalone HTML page showing a `/snapshot` tree, each node collapsible
+/// and every @ref highlighted, under the page's title and URL. `screenshot`
+/// (PNG bytes) is embedded as a data URL.
+pub fn render(snapshot: &Value, screenshot: Option<&[u8]>) -> String {
+    let title = snapshot["title"].as_str().unwrap_or_default();
+    let url = snapshot["url"].as_str().unwrap_or_default();
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Snapshot: {title}</title>\n<style>{STYLE}</style>\n<script>{SCRIPT}</script>\n</head>\n<body>\n<header>\n<h1>{title}</h1>\n<p>{url}</p>\n",
+        title = escape(title),
+        url = escape(url),
+    );
+    if snapshot["truncated"].as_bool() == Some(true) {
+        html.push_str(
+            "<p class=\"warning\">The snapshot ran out of time; only part of the page is shown.</p>\n",
+        );
+    }
+    html.push_str("</header>\n");
+    if let Some(png) = screenshot {
+        html.push_str(&format!(
+            "<figure><img alt=\"Screenshot\" src=\"data:image/png;base64,{}\"></figure>\n",
+            base64::engine::general_purpose::STANDARD.encode(png)
+        ));
+    }
+    html.push_str(
+        "<p><button onclick=\"setAll(true)\">Expand all</button> <button onclick=\"setAll(false)\">Collapse all</button></p>\n<div class=\"tree\">\n",
+    );
+    for element in snapshot["elements"].as_array().into_iter().flatten() {
+        render_element(element, &mut html);
+    }
+    html.push_str("</div>\n</body>\n</html>\n");
+    html
+}
+
+/// One node: a `<details>` when it has children, open by default.
+fn render_element(el: &Value, html: &mut String) {
+    let label = label(el);
+    let hidden = el["visible"].as_bool() == Some(false);
+    match el["children"].as_array().filter(|c| !c.is_empty()) {
+        Some(children) => {
+            let class = if hidden { " class=\"hidden\"" } else { "" };
+            html.push_str(&format!(
+                "<details open{class}><summary>{label}</summary>\n"
+            ));
+            for child in children {
+                render_element(child, html);
+            }
+            html.push_str("</details>\n");
+        }
+        None => {
+            let class = if hidden { "leaf hidden" } else { "leaf" };
+            html.push_str(&format!("<div class=\"{class}\">{label}</div>\n"));
+        }
+    }
+}
+
+/// `button @e3 testid=... [role] "name" text value=... {aria}`, escaped.
+fn label(el: &Value) -> String {
+    let str_field = |key: &str| el[key].as_str().filter(|s| !s.is_empty());
+    let mut parts = vec![format!(
+        "<span class=\"tag\">{}</span>",
+        escape(str_field("tag").unwrap_or("?"))
+    )];
+    if let Some(r) = str_field("ref") {
+        parts.push(format!("<span class=\"ref\">@{}</span>", escape(r)));
+    }
+    if let Some(testid) = str_field("testid") {
+        parts.push(format!(
+            "<span class=\"meta\">testid={}</span>",
+            escape(testid)
+        ));
+    }
+    if let Some(role) = str_field("role") {
+        parts.push(format!("<span class=\"meta\">[{}]</span>", escape(role)));
+    }
+    if let Some(name) = str_field("name") {
+        parts.push(format!("<span class=\"name\">\"{}\"</span>", escape(name)));
+    }
+    if let Some(text) = str_field("text") {
+        parts.push(escape(text));
+    }
+    if let Some(value) = str_field("value") {
+        parts.push(format!(
+            "<span class=\"meta\">value={}</span>",
+            escape(value)
+        ));
+    }
+    if let Some(aria) = el["aria"].as_object() {
+        let states: Vec<String> = aria
+            .iter()
+            .map(|(k, v)| format!("{k}={}", v.as_str().unwrap_or_default()))
+            .collect();
+        parts.push(format!(
+            "<span class=\"meta\">{{{}}}</span>",
+            escape(&states.join(", "))
+        ));
+    }
+    parts.join(" ")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_nests_nodes_and_highlights_refs() {
+        let snapshot = serde_json::json!({
+            "title": "Tom & Jerry",
+            "url": "http://localhost:1420/?a=<b>",
+            "elements": [{
+                "tag": "form",
+                "interactive": false,
+                "children": [
+                    {"tag": "input", "ref": "e1", "name": "Email", "interactive": true},
+                    {"tag": "button", "ref": "e2", "text": "<Save>", "interactive": true,
+                     "aria": {"pressed": "true"}},
+                ],
+            }],
+        });
+        let html = render(&snapshot, Some(b"png"));
+        assert!(html.contains("<h1>Tom &amp; Jerry</h1>"));
+        assert!(html.contains("<p>http://localhost:1420/?a=&lt;b&gt;</p>"));
+        assert!(html.contains(
+            "<details open><summary><span class=\"tag\">form</span></summary>\n<div class=\"leaf\"><span class=\"tag\">input</span> <span class=\"ref\">@e1</span> <span class=\"name\">\"Email\"</span></div>"
+        ));
+        assert!(html.contains(
+            "<span class=\"ref\">@e2</span> &lt;Save&gt; <span class=\"meta\">{pressed=true}</span>"
+        ));
+        assert!(html.contains("src=\"data:image/png;base64,cG5n\""));
+        // Nothing is loaded from elsewhere.
+        assert!(!html.contains("src=\"http") && !html.contains("href="));
+    }
+}
@@ -1,5 +1,35 @@
 const COMMANDS: &[&str] = &["eval_callback", "console_callback"];
 
 fn main() {
+    build_metadata();
     tauri_plugin::Builder::new(COMMANDS).build();
 }
+
+/// Record the git commit and build profile for `/health`. Both are optional:
+/// builds outside a git checkout (e.g. from crates.io) simply omit the sha.
+fn build_metadata() {
+    if let Ok(profile) = std::env::var("PROFILE") {
+        println!("cargo:rustc-env=DEBUG_BRIDGE_BUILD_PROFILE={profile}");
+    }
+
+    let sha = std::process::Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok());
+    if let Some(sha) = sha {
+        println!("cargo:rustc-env=DEBUG_BRIDGE_GIT_SHA={}", sha.trim());
+    }
+
+    // Refresh the sha when the checked-out commit changes.
+    if let Ok(output) = std::process::Command::new("git")
+        .args(["rev-parse", "--git-dir"])
+        .output()
+        && output.status.success()
+    {
+        let git_dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        println!("cargo:rerun-if-changed={git_dir}/HEAD");
+        println!("cargo:rerun-if-changed={git_dir}/refs/heads");
+    }
+}
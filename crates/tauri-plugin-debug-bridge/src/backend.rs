@@ -1,10 +1,18 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
-use axum::{extract::State, http::StatusCode, response::Json};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
 use serde::{Deserialize, Serialize};
-use tauri::{Manager, Runtime};
+use tauri::{Manager, Runtime, WebviewWindow};
 
-use crate::{BridgeState, EvalResult};
+use crate::{
+    ApiError, BridgeState, EvalResult,
+    upload::{FileTarget, JsonOrMultipart},
+    webview::get_window,
+};
 
 #[derive(Deserialize)]
 pub struct InvokeRequest {
@@ -13,6 +21,26 @@ pub struct InvokeRequest {
     pub args: serde_json::Value,
 }
 
+impl FileTarget for InvokeRequest {
+    const FIELD: &'static str = "args";
+}
+
+#[derive(Deserialize)]
+pub struct BatchInvokeRequest {
+    pub calls: Vec<InvokeRequest>,
+    /// Stop at the first failing call instead of running the rest.
+    #[serde(default)]
+    pub stop_on_error: bool,
+}
+
+#[derive(Serialize)]
+pub struct BatchInvokeResult {
+    pub command: String,
+    pub success: bool,
+    pub value: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct CommandInfo {
     pub name: String,
@@ -27,20 +55,152 @@ pub struct WindowInfo {
     pub is_focused: bool,
 }
 
+#[derive(Deserialize)]
+pub struct WindowStateQuery {
+    pub window: Option<String>,
+}
+
+/// `POST /windows/state` body. A setting that is null or missing is left
+/// as it is.
+#[cfg(desktop)]
+#[derive(Deserialize)]
+pub struct WindowStateRequest {
+    pub window: Option<String>,
+    pub always_on_top: Option<bool>,
+    pub fullscreen: Option<bool>,
+    pub maximized: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct WindowState {
+    pub window: String,
+    pub always_on_top: bool,
+    pub fullscreen: bool,
+    pub maximized: bool,
+}
+
+/// Directories the app reads and writes, resolved for the current platform.
+/// A directory that isn't available on the platform is omitted.
+#[derive(Serialize)]
+pub struct AppPaths {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_data_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_config_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_cache_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_dir: Option<PathBuf>,
+}
+
+/// Which app the bridge is running in. `/health` describes the bridge;
+/// this describes its host.
+#[derive(Serialize)]
+pub struct AppInfo {
+    /// `productName` from the config, or the crate name without one.
+    pub name: String,
+    pub version: String,
+    pub identifier: String,
+    pub tauri_version: &'static str,
+}
+
+/// How the app was built and where it's running.
+#[derive(Serialize)]
+pub struct AppEnv {
+    /// Built by `tauri dev`: the frontend comes from the dev server.
+    pub is_dev: bool,
+    /// Built by `tauri build`, with the frontend embedded in the binary.
+    pub is_bundled: bool,
+    /// Package format of a bundled app (`app`, `deb`, `msi`, ...), when the
+    /// bundler recorded it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle_type: Option<String>,
+    /// Dev server the frontend loads from, in dev builds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dev_url: Option<String>,
+    /// The same fields as `/app`.
+    #[serde(flatten)]
+    pub app: AppInfo,
+    /// Cargo build profile ("debug" or "release").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_profile: Option<&'static str>,
+    pub os: &'static str,
+    pub arch: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub executable: Option<PathBuf>,
+}
+
 /// POST /invoke — call a registered Tauri command by routing through the webview.
 /// Since Tauri doesn't expose a Rust-side command invocation API, we inject JS
 /// that calls `window.__TAURI_INTERNALS__.invoke()` and captures the result.
+/// Files sent as multipart parts are added to `args` (see [`JsonOrMultipart`]).
 pub async fn invoke<R: Runtime>(
     State(state): State<Arc<BridgeState<R>>>,
-    Json(req): Json<InvokeRequest>,
-) -> Result<Json<EvalResult>, (StatusCode, String)> {
-    let window = state
-        .app
-        .get_webview_window("main")
-        .ok_or_else(|| (StatusCode::NOT_FOUND, "window 'main' not found".to_string()))?;
+    JsonOrMultipart(req): JsonOrMultipart<InvokeRequest>,
+) -> Result<Json<EvalResult>, ApiError> {
+    let window = get_window(&state, None)?;
+    let result = invoke_command(&state, &window, &req.command, &req.args).await?;
+    Ok(Json(result))
+}
 
-    let args_json = serde_json::to_string(&req.args)
-        .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid args: {e}")))?;
+/// POST /invoke/batch — call several Tauri commands in order.
+/// Each call gets its own result; with `stop_on_error` the batch ends at the
+/// first failure and the remaining calls are not run.
+pub async fn invoke_batch<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Json(req): Json<BatchInvokeRequest>,
+) -> Result<Json<Vec<BatchInvokeResult>>, ApiError> {
+    let window = get_window(&state, None)?;
+    let (state, window) = (&state, &window);
+    let results = run_batch(req, |call| async move {
+        invoke_command(state, window, &call.command, &call.args).await
+    })
+    .await;
+    Ok(Json(results))
+}
+
+/// Run each call of a batch through `invoke` in order, recording every
+/// outcome and stopping after the first failure if the batch asks to.
+async fn run_batch<F, Fut>(req: BatchInvokeRequest, mut invoke: F) -> Vec<BatchInvokeResult>
+where
+    F: FnMut(InvokeRequest) -> Fut,
+    Fut: std::future::Future<Output = Result<EvalResult, ApiError>>,
+{
+    let mut results = Vec::with_capacity(req.calls.len());
+    for call in req.calls {
+        let command = call.command.clone();
+        let result = match invoke(call).await {
+            Ok(r) => BatchInvokeResult {
+                command,
+                success: r.success,
+                value: r.value,
+                error: r.error,
+            },
+            Err(e) => BatchInvokeResult {
+                command,
+                success: false,
+                value: None,
+                error: Some(e.message),
+            },
+        };
+        let failed = !result.success;
+        results.push(result);
+        if failed && req.stop_on_error {
+            break;
+        }
+    }
+    results
+}
+
+/// Invoke a single command in the given window and wait for its result.
+async fn invoke_command<R: Runtime>(
+    state: &BridgeState<R>,
+    window: &WebviewWindow<R>,
+    command: &str,
+    args: &serde_json::Value,
+) -> Result<EvalResult, ApiError> {
+    let args_json = serde_json::to_string(args)
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, format!("invalid args: {e}")))?;
 
     let invoke_js = format!(
         r#"
@@ -51,10 +211,12 @@ pub async fn invoke<R: Runtime>(
             throw new Error('invoke failed: ' + e);
         }}
         "#,
-        cmd = serde_json::to_string(&req.command).unwrap(),
+        cmd = serde_json::to_string(command).unwrap(),
         args = args_json,
     );
 
+    crate::webview::wait_until_ready(state, window).await?;
+
     let id = crate::webview::uuid_v4();
     let (tx, rx) = tokio::sync::oneshot::channel();
 
@@ -71,7 +233,7 @@ pub async fn invoke<R: Runtime>(
                 const __result = await (async () => {{ {code} }})();
                 await window.__TAURI_INTERNALS__.invoke(
                     'plugin:debug-bridge|eval_callback',
-                    {{ id: '{id}', success: true, value: __result, error: null }}
+                    {{ id: '{id}', success: true, value: __result, undefined: __result === undefined, error: null }}
                 );
             }} catch(__e) {{
                 await window.__TAURI_INTERNALS__.invoke(
@@ -84,23 +246,38 @@ pub async fn invoke<R: Runtime>(
         id = id,
     );
 
-    window
-        .eval(&wrapped)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if let Err(e) = window.eval(&wrapped) {
+        state.pending.lock().await.remove(&id);
+        state.diagnostics.record(
+            "invoke",
+            format!("injection into window '{}' failed: {e}", window.label()),
+        );
+        return Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+        ));
+    }
 
-    match tokio::time::timeout(std::time::Duration::from_secs(30), rx).await {
-        Ok(Ok(result)) => Ok(Json(result)),
-        Ok(Err(_)) => Err((
+    let timeout = state.timeouts.invoke;
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(_)) => Err(ApiError::new(
             StatusCode::INTERNAL_SERVER_ERROR,
             "invoke callback channel dropped".to_string(),
         )),
         Err(_) => {
             let mut pending = state.pending.lock().await;
             pending.remove(&id);
-            Err((
-                StatusCode::GATEWAY_TIMEOUT,
-                "invoke timed out after 30s".to_string(),
-            ))
+            state.diagnostics.record(
+                "invoke",
+                format!("'{command}' got no callback within {}s", timeout.as_secs()),
+            );
+            let message = format!("invoke timed out after {}s", timeout.as_secs());
+            let message = match state.csp.timeout_hint(window.label()) {
+                Some(hint) => format!("{message}: {hint}"),
+                None => message,
+            };
+            Err(ApiError::new(StatusCode::GATEWAY_TIMEOUT, message))
         }
     }
 }
@@ -110,7 +287,7 @@ pub async fn invoke<R: Runtime>(
 /// is a placeholder that apps can populate via the plugin API.
 pub async fn commands<R: Runtime>(
     State(_state): State<Arc<BridgeState<R>>>,
-) -> Result<Json<Vec<CommandInfo>>, (StatusCode, String)> {
+) -> Result<Json<Vec<CommandInfo>>, ApiError> {
     // TODO: Allow apps to register command metadata with the plugin.
     Ok(Json(vec![]))
 }
@@ -119,7 +296,7 @@ pub async fn commands<R: Runtime>(
 /// Placeholder — apps need to register serializable state with the plugin.
 pub async fn state<R: Runtime>(
     State(_state): State<Arc<BridgeState<R>>>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     Ok(Json(serde_json::json!({
         "note": "state inspection requires app integration — register state types with the plugin"
     })))
@@ -128,7 +305,7 @@ pub async fn state<R: Runtime>(
 /// GET /windows — list all open windows/webviews.
 pub async fn windows<R: Runtime>(
     State(state): State<Arc<BridgeState<R>>>,
-) -> Result<Json<Vec<WindowInfo>>, (StatusCode, String)> {
+) -> Result<Json<Vec<WindowInfo>>, ApiError> {
     let webview_windows = state.app.webview_windows();
 
     let windows: Vec<WindowInfo> = webview_windows
@@ -145,12 +322,195 @@ pub async fn windows<R: Runtime>(
     Ok(Json(windows))
 }
 
+/// GET /windows/state?window=... — whether the window is always on top,
+/// fullscreen, and maximized.
+pub async fn window_state<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<WindowStateQuery>,
+) -> Result<Json<WindowState>, ApiError> {
+    let window = get_window(&state, query.window.as_deref())?;
+    read_window_state(&window).map(Json)
+}
+
+/// POST /windows/state — apply the settings given, for reproducing bugs
+/// that only show in one window mode, and answer with the state afterwards.
+/// Fullscreen is applied before maximizing, since leaving fullscreen can
+/// restore the old size. Desktop only: mobile windows can't change mode.
+#[cfg(desktop)]
+pub async fn set_window_state<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Json(req): Json<WindowStateRequest>,
+) -> Result<Json<WindowState>, ApiError> {
+    let window = get_window(&state, req.window.as_deref())?;
+    let failed = |what: &str, e: tauri::Error| {
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("{what} failed: {e}"),
+        )
+    };
+    if let Some(on_top) = req.always_on_top {
+        window
+            .set_always_on_top(on_top)
+            .map_err(|e| failed("set_always_on_top", e))?;
+    }
+    if let Some(fullscreen) = req.fullscreen {
+        window
+            .set_fullscreen(fullscreen)
+            .map_err(|e| failed("set_fullscreen", e))?;
+    }
+    match req.maximized {
+        Some(true) => window.maximize().map_err(|e| failed("maximize", e))?,
+        Some(false) => window.unmaximize().map_err(|e| failed("unmaximize", e))?,
+        None => {}
+    }
+    read_window_state(&window).map(Json)
+}
+
+fn read_window_state<R: Runtime>(window: &WebviewWindow<R>) -> Result<WindowState, ApiError> {
+    let read = |what: &str, value: tauri::Result<bool>| {
+        value.map_err(|e| {
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("reading {what} failed: {e}"),
+            )
+        })
+    };
+    Ok(WindowState {
+        window: window.label().to_string(),
+        always_on_top: read("always-on-top", window.is_always_on_top())?,
+        fullscreen: read("fullscreen", window.is_fullscreen())?,
+        maximized: read("maximized", window.is_maximized())?,
+    })
+}
+
+/// GET /paths — the app's data, config, cache, and resource directories.
+pub async fn paths<R: Runtime>(State(state): State<Arc<BridgeState<R>>>) -> Json<AppPaths> {
+    let path = state.app.path();
+    Json(AppPaths {
+        app_data_dir: path.app_data_dir().ok(),
+        app_config_dir: path.app_config_dir().ok(),
+        app_cache_dir: path.app_cache_dir().ok(),
+        resource_dir: path.resource_dir().ok(),
+    })
+}
+
+/// GET /app — the host app's name, version, and identifier, and the Tauri
+/// version it was built against.
+pub async fn app_info<R: Runtime>(State(state): State<Arc<BridgeState<R>>>) -> Json<AppInfo> {
+    Json(AppInfo::of(&state.app))
+}
+
+impl AppInfo {
+    fn of<R: Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let info = app.package_info();
+        Self {
+            name: info.name.clone(),
+            version: info.version.to_string(),
+            identifier: app.config().identifier.clone(),
+            tauri_version: tauri::VERSION,
+        }
+    }
+}
+
+/// GET /env — whether the app is a dev or bundled build, plus the app and
+/// platform it's running as.
+pub async fn env<R: Runtime>(State(state): State<Arc<BridgeState<R>>>) -> Json<AppEnv> {
+    let is_dev = tauri::is_dev();
+    let config = state.app.config();
+    Json(AppEnv {
+        is_dev,
+        is_bundled: !is_dev,
+        // macOS reports `app` for any binary, so only trust it once bundled.
+        bundle_type: (!is_dev)
+            .then(tauri::utils::platform::bundle_type)
+            .flatten()
+            .map(|t| t.to_string()),
+        dev_url: is_dev
+            .then(|| config.build.dev_url.as_ref().map(|u| u.to_string()))
+            .flatten(),
+        app: AppInfo::of(&state.app),
+        build_profile: option_env!("DEBUG_BRIDGE_BUILD_PROFILE"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        executable: std::env::current_exe().ok(),
+    })
+}
+
 /// GET /config — return the app's Tauri config.
 pub async fn config<R: Runtime>(
     State(state): State<Arc<BridgeState<R>>>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let config = state.app.config();
     let json = serde_json::to_value(config)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     Ok(Json(json))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(commands: &[&str], stop_on_error: bool) -> BatchInvokeRequest {
+        BatchInvokeRequest {
+            calls: commands
+                .iter()
+                .map(|c| InvokeRequest {
+                    command: c.to_string(),
+                    args: serde_json::Value::Null,
+                })
+                .collect(),
+            stop_on_error,
+        }
+    }
+
+    /// `fail` rejects in the command, `missing` fails to run at all.
+    async fn fake_invoke(call: InvokeRequest) -> Result<EvalResult, ApiError> {
+        match call.command.as_str() {
+            "missing" => Err(ApiError::new(StatusCode::NOT_FOUND, "no such window")),
+            command => Ok(EvalResult {
+                success: command != "fail",
+                value: (command != "fail").then(|| serde_json::json!(command)),
+                undefined: false,
+                error: (command == "fail").then(|| "invoke failed: boom".to_string()),
+                stack: None,
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn batches_run_past_failures_by_default() {
+        let results = run_batch(batch(&["a", "fail", "missing", "b"], false), fake_invoke).await;
+        let summary: Vec<(&str, bool)> = results
+            .iter()
+            .map(|r| (r.command.as_str(), r.success))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("a", true),
+                ("fail", false),
+                ("missing", false),
+                ("b", true)
+            ]
+        );
+        assert_eq!(results[0].value, Some(serde_json::json!("a")));
+        assert_eq!(results[1].error.as_deref(), Some("invoke failed: boom"));
+        assert_eq!(results[2].error.as_deref(), Some("no such window"));
+    }
+
+    #[tokio::test]
+    async fn stop_on_error_ends_the_batch_at_the_first_failure() {
+        let mut ran = Vec::new();
+        let results = run_batch(batch(&["a", "fail", "b"], true), |call| {
+            ran.push(call.command.clone());
+            fake_invoke(call)
+        })
+        .await;
+        assert_eq!(ran, ["a", "fail"], "calls after the failure never run");
+        assert_eq!(results.len(), 2);
+        assert!(!results[1].success);
+
+        let results = run_batch(batch(&["a", "missing", "b"], true), fake_invoke).await;
+        assert_eq!(results.len(), 2);
+    }
+}
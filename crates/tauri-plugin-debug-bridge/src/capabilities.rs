@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use axum::response::{IntoResponse, Json, Response};
+use serde::Serialize;
+
+use crate::Config;
+
+/// How an endpoint is reached.
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Http,
+    /// A WebSocket upgrade (always `GET`).
+    WebSocket,
+}
+
+/// Whether an endpoint works in this build and configuration.
+#[derive(Clone, Copy)]
+enum Needs {
+    Nothing,
+    /// The `store` cargo feature.
+    StoreFeature,
+    /// `allow_shutdown` in the plugin config.
+    AllowShutdown,
+    /// A desktop platform.
+    Desktop,
+}
+
+/// Every built-in route as `build_router` registers it. Keep the two in
+/// sync; a test checks that the paths match.
+const ENDPOINTS: &[(&str, &str, Kind, Needs)] = &[
+    ("GET", "/health", Kind::Http, Needs::Nothing),
+    ("GET", "/capabilities", Kind::Http, Needs::Nothing),
+    // Webview
+    ("POST", "/eval", Kind::Http, Needs::Nothing),
+    ("GET", "/screenshot", Kind::Http, Needs::Nothing),
+    ("GET", "/screenshot/all", Kind::Http, Needs::Nothing),
+    ("GET", "/snapshot", Kind::Http, Needs::Nothing),
+    ("GET", "/refs", Kind::Http, Needs::Nothing),
+    ("POST", "/refs/clear", Kind::Http, Needs::Nothing),
+    ("GET", "/pagestate", Kind::Http, Needs::Nothing),
+    ("POST", "/click", Kind::Http, Needs::Nothing),
+    ("POST", "/fill", Kind::Http, Needs::Nothing),
+    ("POST", "/select-text", Kind::Http, Needs::Nothing),
+    ("POST", "/wait/navigation", Kind::Http, Needs::Nothing),
+    ("GET", "/styles", Kind::Http, Needs::Nothing),
+    ("GET", "/clickable", Kind::Http, Needs::Nothing),
+    ("GET", "/html", Kind::Http, Needs::Nothing),
+    ("GET", "/zoom", Kind::Http, Needs::Nothing),
+    ("POST", "/zoom", Kind::Http, Needs::Nothing),
+    ("GET", "/clipboard", Kind::Http, Needs::Nothing),
+    ("POST", "/clipboard", Kind::Http, Needs::Nothing),
+    // Backend
+    ("POST", "/invoke", Kind::Http, Needs::Nothing),
+    ("POST", "/invoke/batch", Kind::Http, Needs::Nothing),
+    ("GET", "/commands", Kind::Http, Needs::Nothing),
+    ("GET", "/state", Kind::Http, Needs::Nothing),
+    ("GET", "/windows", Kind::Http, Needs::Nothing),
+    ("GET", "/windows/state", Kind::Http, Needs::Nothing),
+    ("POST", "/windows/state", Kind::Http, Needs::Desktop),
+    ("GET", "/app", Kind::Http, Needs::Nothing),
+    ("GET", "/config", Kind::Http, Needs::Nothing),
+    ("GET", "/paths", Kind::Http, Needs::Nothing),
+    ("GET", "/env", Kind::Http, Needs::Nothing),
+    ("GET", "/store", Kind::Http, Needs::StoreFeature),
+    // Bridge lifecycle
+    ("POST", "/shutdown", Kind::Http, Needs::AllowShutdown),
+    // Dialogs
+    ("GET", "/dialog", Kind::Http, Needs::Nothing),
+    ("POST", "/dialog/arm", Kind::Http, Needs::Nothing),
+    // Events
+    ("POST", "/events/emit", Kind::Http, Needs::Nothing),
+    ("POST", "/events/emit-await", Kind::Http, Needs::Nothing),
+    ("GET", "/events/list", Kind::Http, Needs::Nothing),
+    ("GET", "/events/listen", Kind::WebSocket, Needs::Nothing),
+    // Logs
+    ("GET", "/diagnostics", Kind::WebSocket, Needs::Nothing),
+    ("GET", "/logs", Kind::WebSocket, Needs::Nothing),
+    ("GET", "/console", Kind::WebSocket, Needs::Nothing),
+    ("GET", "/console/recent", Kind::Http, Needs::Nothing),
+    ("GET", "/console/poll", Kind::Http, Needs::Nothing),
+    // Native menu
+    ("GET", "/menu", Kind::Http, Needs::Desktop),
+    ("POST", "/menu/click", Kind::Http, Needs::Desktop),
+];
+
+#[derive(Serialize)]
+pub struct Capabilities {
+    pub plugin: &'static str,
+    pub version: &'static str,
+    /// Optional cargo features, and whether this build has each.
+    pub features: Features,
+    pub endpoints: Vec<Endpoint>,
+}
+
+#[derive(Serialize)]
+pub struct Features {
+    pub store: bool,
+    pub mdns: bool,
+}
+
+#[derive(Serialize)]
+pub struct Endpoint {
+    pub method: &'static str,
+    pub path: &'static str,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub websocket: bool,
+    /// False when the route exists but refuses requests in this build or
+    /// configuration (e.g. `/shutdown` without `allow_shutdown`).
+    pub available: bool,
+    /// What to change to make an unavailable endpoint work.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requires: Option<&'static str>,
+}
+
+/// Build the manifest for `config`. Host app routes aren't listed; axum
+/// can't enumerate a router's routes.
+pub fn manifest(config: &Config) -> Capabilities {
+    let endpoints = ENDPOINTS
+        .iter()
+        .map(|&(method, path, kind, needs)| {
+            let (available, requires) = match needs {
+                Needs::Nothing => (true, None),
+                Needs::StoreFeature => (cfg!(feature = "store"), Some("the `store` feature")),
+                Needs::AllowShutdown => (
+                    config.allow_shutdown.unwrap_or(false),
+                    Some("`allow_shutdown` in the plugin config"),
+                ),
+                Needs::Desktop => (cfg!(desktop), Some("a desktop platform")),
+            };
+            Endpoint {
+                method,
+                path,
+                websocket: kind == Kind::WebSocket,
+                available,
+                requires: requires.filter(|_| !available),
+            }
+        })
+        .collect();
+    Capabilities {
+        plugin: "tauri-plugin-debug-bridge",
+        version: env!("CARGO_PKG_VERSION"),
+        features: Features {
+            store: cfg!(feature = "store"),
+            mdns: cfg!(feature = "mdns"),
+        },
+        endpoints,
+    }
+}
+
+/// GET /capabilities — the built-in endpoints, whether each is usable here,
+/// and which optional features this build has, so tools can adapt to the
+/// plugin they're talking to.
+pub async fn capabilities(manifest: Arc<Capabilities>) -> Response {
+    Json(&*manifest).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// Paths `build_router` registers with `.route("...")`.
+    fn routed_paths() -> BTreeSet<String> {
+        let source = include_str!("lib.rs");
+        let start = source.find("fn build_router").unwrap();
+        let end = start + source[start..].find("fn apply_layers").unwrap();
+        source[start..end]
+            .split(".route(")
+            .skip(1)
+            .filter_map(|rest| {
+                let rest = rest.trim_start();
+                let rest = rest.strip_prefix('"')?;
+                Some(rest[..rest.find('"')?].to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn manifest_matches_the_router() {
+        let listed: BTreeSet<String> = ENDPOINTS.iter().map(|e| e.1.to_string()).collect();
+        assert_eq!(listed, routed_paths());
+    }
+
+    #[test]
+    fn unavailable_endpoints_say_what_they_need() {
+        let defaults = manifest(&Config::default());
+        let shutdown = defaults
+            .endpoints
+            .iter()
+            .find(|e| e.path == "/shutdown")
+            .unwrap();
+        assert!(!shutdown.available);
+        assert_eq!(
+            shutdown.requires,
+            Some("`allow_shutdown` in the plugin config")
+        );
+
+        let config = Config {
+            allow_shutdown: Some(true),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(manifest(&config)).unwrap();
+        let endpoints = json["endpoints"].as_array().unwrap();
+        let shutdown = endpoints.iter().find(|e| e["path"] == "/shutdown").unwrap();
+        assert_eq!(shutdown["available"], true);
+        assert!(shutdown.get("requires").is_none());
+        let console = endpoints.iter().find(|e| e["path"] == "/console").unwrap();
+        assert_eq!(console["websocket"], true);
+        assert_eq!(json["features"]["store"], cfg!(feature = "store"));
+    }
+}
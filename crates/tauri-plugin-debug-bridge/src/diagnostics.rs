@@ -0,0 +1,157 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use axum::{
+    extract::{
+        State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::Response,
+};
+use serde::Serialize;
+use tauri::Runtime;
+use tokio::sync::broadcast;
+
+use crate::BridgeState;
+
+/// How many past diagnostics are replayed to a newly connected client.
+const RECENT_CAPACITY: usize = 64;
+
+/// A bridge-internal error (bind failure, eval injection failure, timeout).
+/// Messages describe what failed, never request payloads or the auth token.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub source: &'static str,
+    pub message: String,
+}
+
+/// Bounded record of bridge-internal errors with a live broadcast.
+#[derive(Clone)]
+pub struct Diagnostics {
+    tx: broadcast::Sender<Diagnostic>,
+    recent: Arc<std::sync::Mutex<VecDeque<Diagnostic>>>,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(RECENT_CAPACITY);
+        Self {
+            tx,
+            recent: Arc::new(std::sync::Mutex::new(VecDeque::with_capacity(
+                RECENT_CAPACITY,
+            ))),
+        }
+    }
+}
+
+impl Diagnostics {
+    /// Record an error and log it through `tracing`.
+    pub fn record(&self, source: &'static str, message: impl Into<String>) {
+        let message = message.into();
+        tracing::warn!("debug-bridge {source}: {message}");
+
+        let diagnostic = Diagnostic {
+            timestamp_ms: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            source,
+            message,
+        };
+
+        {
+            let mut recent = self.recent.lock().unwrap();
+            if recent.len() == RECENT_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(diagnostic.clone());
+        }
+        let _ = self.tx.send(diagnostic);
+    }
+
+    /// Diagnostics recorded so far, oldest first.
+    pub fn recent(&self) -> Vec<Diagnostic> {
+        self.recent.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Diagnostic> {
+        self.tx.subscribe()
+    }
+}
+
+/// GET /diagnostics — WebSocket stream of bridge-internal errors.
+/// Replays recent diagnostics on connect, then streams new ones.
+pub async fn diagnostics_ws<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let diagnostics = state.diagnostics.clone();
+    let stream = state.activity.stream();
+    ws.protocols([crate::WS_PROTOCOL])
+        .on_upgrade(move |socket| async move {
+            let _stream = stream;
+            handle_diagnostics(socket, diagnostics).await
+        })
+}
+
+async fn handle_diagnostics(mut socket: WebSocket, diagnostics: Diagnostics) {
+    // Subscribe before replaying so nothing recorded in between is missed.
+    let mut rx = diagnostics.subscribe();
+
+    for diagnostic in diagnostics.recent() {
+        let msg = serde_json::to_string(&diagnostic).unwrap_or_default();
+        if socket.send(Message::Text(msg.into())).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            Ok(diagnostic) = rx.recv() => {
+                let msg = serde_json::to_string(&diagnostic).unwrap_or_default();
+                if socket.send(Message::Text(msg.into())).await.is_err() {
+                    break;
+                }
+            }
+            Some(Ok(msg)) = socket.recv() => {
+                if matches!(msg, Message::Close(_)) {
+                    break;
+                }
+            }
+            else => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_is_bounded_and_ordered() {
+        let diagnostics = Diagnostics::default();
+        for i in 0..RECENT_CAPACITY + 5 {
+            diagnostics.record("test", format!("error {i}"));
+        }
+
+        let recent = diagnostics.recent();
+        assert_eq!(recent.len(), RECENT_CAPACITY);
+        assert_eq!(recent[0].message, "error 5");
+        assert_eq!(
+            recent.last().unwrap().message,
+            format!("error {}", RECENT_CAPACITY + 4)
+        );
+    }
+
+    #[test]
+    fn subscribers_receive_new_diagnostics() {
+        let diagnostics = Diagnostics::default();
+        let mut rx = diagnostics.subscribe();
+        diagnostics.record("eval", "injection failed");
+
+        let received = rx.try_recv().unwrap();
+        assert_eq!(received.source, "eval");
+        assert_eq!(received.message, "injection failed");
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::{ApiError, BridgeState};
+
+/// Responses armed for upcoming dialogs, queued per dialog type.
+pub type ArmedDialogs = Arc<std::sync::Mutex<HashMap<String, VecDeque<serde_json::Value>>>>;
+
+#[derive(Deserialize)]
+pub struct ArmRequest {
+    /// Dialog type the app asks for, e.g. "confirm", "message", "open", "save".
+    pub r#type: String,
+    /// Value handed to the app instead of showing the dialog
+    /// (a bool for confirm, a path or list of paths for file dialogs).
+    pub response: serde_json::Value,
+}
+
+#[derive(Serialize)]
+pub struct ArmResponse {
+    pub success: bool,
+    /// Responses now queued for this dialog type.
+    pub queued: usize,
+}
+
+fn arm(dialogs: &ArmedDialogs, kind: &str, response: serde_json::Value) -> usize {
+    let mut map = dialogs.lock().unwrap();
+    let queue = map.entry(kind.to_string()).or_default();
+    queue.push_back(response);
+    queue.len()
+}
+
+fn take(dialogs: &ArmedDialogs, kind: &str) -> Option<serde_json::Value> {
+    let mut map = dialogs.lock().unwrap();
+    let queue = map.get_mut(kind)?;
+    let response = queue.pop_front();
+    if queue.is_empty() {
+        map.remove(kind);
+    }
+    response
+}
+
+/// Consume the response armed for the next dialog of `kind`, if any.
+///
+/// Dialogs are native and can't be intercepted from outside, so this is
+/// cooperative: the app calls it wherever it is about to show a dialog and
+/// skips the dialog when a response is returned.
+///
+/// ```rust,no_run
+/// # fn ask_user<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> bool { true }
+/// fn confirm_delete<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> bool {
+///     #[cfg(feature = "debug-bridge")]
+///     if let Some(armed) = tauri_plugin_debug_bridge::take_dialog_response(app, "confirm") {
+///         return armed.as_bool().unwrap_or(false);
+///     }
+///     ask_user(app)
+/// }
+/// ```
+pub fn take_dialog_response<R: Runtime>(
+    app: &AppHandle<R>,
+    kind: &str,
+) -> Option<serde_json::Value> {
+    let dialogs = app.try_state::<ArmedDialogs>()?;
+    take(&dialogs, kind)
+}
+
+/// POST /dialog/arm — queue a response for the next dialog of a given type.
+pub async fn arm_dialog<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Json(req): Json<ArmRequest>,
+) -> Result<Json<ArmResponse>, ApiError> {
+    if req.r#type.trim().is_empty() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "dialog type must not be empty".to_string(),
+        ));
+    }
+    let queued = arm(&state.dialogs, &req.r#type, req.response);
+    Ok(Json(ArmResponse {
+        success: true,
+        queued,
+    }))
+}
+
+/// GET /dialog — list responses that are armed but not yet consumed.
+pub async fn armed<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+) -> Json<HashMap<String, VecDeque<serde_json::Value>>> {
+    Json(state.dialogs.lock().unwrap().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn armed_responses_are_consumed_in_order() {
+        let dialogs = ArmedDialogs::default();
+        assert_eq!(arm(&dialogs, "confirm", serde_json::json!(true)), 1);
+        assert_eq!(arm(&dialogs, "confirm", serde_json::json!(false)), 2);
+        arm(&dialogs, "open", serde_json::json!("/tmp/a.txt"));
+
+        assert_eq!(take(&dialogs, "confirm"), Some(serde_json::json!(true)));
+        assert_eq!(take(&dialogs, "confirm"), Some(serde_json::json!(false)));
+        assert_eq!(take(&dialogs, "confirm"), None);
+        assert_eq!(
+            take(&dialogs, "open"),
+            Some(serde_json::json!("/tmp/a.txt"))
+        );
+        assert!(dialogs.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+
+/// An error response. Rendered as `{"error": "...", "code": "..."}` with its
+/// status, so clients get the same parseable shape from every endpoint.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+    /// Extra fields added to the body, e.g. the live refs when a ref is gone.
+    pub details: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+    code: &'static str,
+    #[serde(flatten)]
+    details: &'a serde_json::Map<String, serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+            details: serde_json::Map::new(),
+        }
+    }
+
+    /// Add `key` to the error body.
+    pub fn with_detail(mut self, key: &str, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).unwrap_or_default();
+        self.details.insert(key.to_string(), value);
+        self
+    }
+
+    /// Machine-readable name for the status, e.g. `not_found`.
+    pub fn code(&self) -> &'static str {
+        match self.status.as_u16() {
+            400 => "bad_request",
+            401 => "unauthorized",
+            403 => "forbidden",
+            404 => "not_found",
+            405 => "method_not_allowed",
+            409 => "conflict",
+            413 => "payload_too_large",
+            415 => "unsupported_media_type",
+            422 => "unprocessable",
+            501 => "not_implemented",
+            503 => "unavailable",
+            504 => "timeout",
+            status if status < 500 => "client_error",
+            _ => "server_error",
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: &self.message,
+            code: self.code(),
+            details: &self.details,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Largest plain-text error body [`json_error_bodies`] rewrites.
+const MAX_TEXT_ERROR_BYTES: usize = 64 * 1024;
+
+/// Middleware giving errors that don't come from a handler (extractor
+/// rejections, body limits, unknown routes, custom routes returning text)
+/// the same JSON shape as [`ApiError`].
+pub(crate) async fn json_error_bodies(req: Request<Body>, next: Next) -> Response {
+    let response = next.run(req).await;
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !(status.is_client_error() || status.is_server_error()) || is_json {
+        return response;
+    }
+    let body = axum::body::to_bytes(response.into_body(), MAX_TEXT_ERROR_BYTES)
+        .await
+        .unwrap_or_default();
+    let message = match String::from_utf8_lossy(&body).trim() {
+        "" => status.canonical_reason().unwrap_or("error").to_string(),
+        text => text.to_string(),
+    };
+    ApiError::new(status, message).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn errors_render_as_json() {
+        let resp = ApiError::new(StatusCode::NOT_FOUND, "window 'prefs' not found").into_response();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/json");
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "error": "window 'prefs' not found", "code": "not_found" })
+        );
+
+        let resp = ApiError::new(StatusCode::NOT_FOUND, "ref @e9 not found")
+            .with_detail("live_refs", ["@e1"])
+            .into_response();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "not_found");
+        assert_eq!(body["live_refs"], serde_json::json!(["@e1"]));
+
+        assert_eq!(
+            ApiError::new(StatusCode::GATEWAY_TIMEOUT, "").code(),
+            "timeout"
+        );
+        assert_eq!(
+            ApiError::new(StatusCode::IM_A_TEAPOT, "").code(),
+            "client_error"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use axum::{
     extract::{
@@ -10,9 +11,12 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use tauri::{Emitter, Listener, Runtime};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 
-use crate::BridgeState;
+use crate::{
+    ApiError, BridgeState,
+    upload::{FileTarget, JsonOrMultipart},
+};
 
 #[derive(Deserialize)]
 pub struct EmitRequest {
@@ -21,11 +25,35 @@ pub struct EmitRequest {
     pub payload: serde_json::Value,
 }
 
+impl FileTarget for EmitRequest {
+    const FIELD: &'static str = "payload";
+}
+
 #[derive(Serialize)]
 pub struct EmitResponse {
     pub success: bool,
 }
 
+#[derive(Deserialize)]
+pub struct EmitAwaitRequest {
+    pub event: String,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+    /// How long to wait for the ack. Defaults to 10 seconds.
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct EmitAwaitResponse {
+    /// The event that acknowledged, `<event>:ack`.
+    pub ack_event: String,
+    /// The ack's payload (null if it had none).
+    pub payload: serde_json::Value,
+    pub elapsed_ms: u64,
+}
+
+const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Serialize)]
 pub struct EventInfo {
     pub name: String,
@@ -36,25 +64,81 @@ pub struct ListenQuery {
     pub name: String,
 }
 
-/// POST /events/emit — emit a Tauri event.
+/// POST /events/emit — emit a Tauri event. Files sent as multipart parts are
+/// added to `payload` (see [`JsonOrMultipart`]).
 pub async fn emit<R: Runtime>(
     State(state): State<Arc<BridgeState<R>>>,
-    Json(req): Json<EmitRequest>,
-) -> Result<Json<EmitResponse>, (StatusCode, String)> {
+    JsonOrMultipart(req): JsonOrMultipart<EmitRequest>,
+) -> Result<Json<EmitResponse>, ApiError> {
     state
         .app
         .emit(&req.event, req.payload)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     Ok(Json(EmitResponse { success: true }))
 }
 
+/// The event a frontend emits to acknowledge `event`.
+fn ack_event_name(event: &str) -> String {
+    format!("{event}:ack")
+}
+
+/// POST /events/emit-await — emit an event, then wait for the frontend to
+/// emit `<event>:ack` and return that event's payload.
+pub async fn emit_await<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Json(req): Json<EmitAwaitRequest>,
+) -> Result<Json<EmitAwaitResponse>, ApiError> {
+    let ack_event = ack_event_name(&req.event);
+    let timeout = req
+        .timeout_ms
+        .map_or(DEFAULT_ACK_TIMEOUT, Duration::from_millis);
+
+    // Listen before emitting so a fast ack isn't missed.
+    let (tx, rx) = oneshot::channel::<String>();
+    let listener = state.app.once(&ack_event, move |event| {
+        let _ = tx.send(event.payload().to_string());
+    });
+
+    let start = Instant::now();
+    if let Err(e) = state.app.emit(&req.event, req.payload) {
+        state.app.unlisten(listener);
+        return Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+        ));
+    }
+
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(payload)) => Ok(Json(EmitAwaitResponse {
+            payload: serde_json::from_str(&payload).unwrap_or(serde_json::Value::String(payload)),
+            ack_event,
+            elapsed_ms: start.elapsed().as_millis() as u64,
+        })),
+        Ok(Err(_)) => Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("listener for '{ack_event}' dropped"),
+        )),
+        Err(_) => {
+            state.app.unlisten(listener);
+            Err(ApiError::new(
+                StatusCode::GATEWAY_TIMEOUT,
+                format!(
+                    "no '{ack_event}' event within {}ms (does the frontend emit it after handling '{}'?)",
+                    timeout.as_millis(),
+                    req.event
+                ),
+            ))
+        }
+    }
+}
+
 /// GET /events/list — list known event names.
 pub async fn list<R: Runtime>(
     State(_state): State<Arc<BridgeState<R>>>,
-) -> Result<Json<Vec<EventInfo>>, (StatusCode, String)> {
+) -> Result<Json<Vec<EventInfo>>, ApiError> {
     // Tauri doesn't expose a public event registry.
-    Err((
+    Err(ApiError::new(
         StatusCode::NOT_IMPLEMENTED,
         "event listing not yet implemented — Tauri has no public event registry".to_string(),
     ))
@@ -68,7 +152,12 @@ pub async fn listen<R: Runtime>(
 ) -> Response {
     let app = state.app.clone();
     let event_name = query.name;
-    ws.on_upgrade(move |socket| handle_listen(socket, app, event_name))
+    let stream = state.activity.stream();
+    ws.protocols([crate::WS_PROTOCOL])
+        .on_upgrade(move |socket| async move {
+            let _stream = stream;
+            handle_listen(socket, app, event_name).await
+        })
 }
 
 async fn handle_listen<R: Runtime>(
@@ -108,3 +197,14 @@ async fn handle_listen<R: Runtime>(
     // Clean up the Tauri event listener.
     app.unlisten(event_id);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ack_events_follow_the_naming_convention() {
+        assert_eq!(ack_event_name("refresh"), "refresh:ack");
+        assert_eq!(ack_event_name("doc/save"), "doc/save:ack");
+    }
+}
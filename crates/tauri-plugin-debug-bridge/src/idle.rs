@@ -0,0 +1,107 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use tauri::Runtime;
+
+use crate::BridgeState;
+
+/// How often the idle watcher checks for activity.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// When the bridge last served a request, and how many WebSocket streams are
+/// open. A connected stream keeps the bridge busy for as long as it lasts.
+#[derive(Clone)]
+pub struct Activity {
+    last: Arc<std::sync::Mutex<Instant>>,
+    streams: Arc<AtomicUsize>,
+}
+
+impl Default for Activity {
+    fn default() -> Self {
+        Self {
+            last: Arc::new(std::sync::Mutex::new(Instant::now())),
+            streams: Default::default(),
+        }
+    }
+}
+
+impl Activity {
+    pub fn touch(&self) {
+        *self.last.lock().unwrap() = Instant::now();
+    }
+
+    /// Count a WebSocket stream as activity until the guard is dropped.
+    pub fn stream(&self) -> StreamGuard {
+        self.streams.fetch_add(1, Ordering::SeqCst);
+        StreamGuard(self.clone())
+    }
+
+    /// No open streams and no request for at least `timeout`.
+    fn is_idle(&self, timeout: Duration) -> bool {
+        self.streams.load(Ordering::SeqCst) == 0 && self.last.lock().unwrap().elapsed() >= timeout
+    }
+}
+
+/// Keeps the bridge from idling out while a WebSocket stream is connected.
+pub struct StreamGuard(Activity);
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        self.0.touch();
+        self.0.streams.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Middleware recording each request as activity.
+pub async fn track(State(activity): State<Activity>, req: Request, next: Next) -> Response {
+    activity.touch();
+    next.run(req).await
+}
+
+/// Shut the server down once it has been idle for `timeout`. Returns early if
+/// something else (`POST /shutdown`) already took the shutdown signal.
+pub async fn shutdown_when_idle<R: Runtime>(state: Arc<BridgeState<R>>, timeout: Duration) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if !state.activity.is_idle(timeout) {
+            continue;
+        }
+        let Some(tx) = state.shutdown.lock().unwrap().take() else {
+            return;
+        };
+        tracing::info!(
+            "debug-bridge idle for {}s, shutting down",
+            timeout.as_secs()
+        );
+        let _ = tx.send(());
+        return;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_streams_keep_the_bridge_busy() {
+        let activity = Activity::default();
+        assert!(activity.is_idle(Duration::ZERO));
+        assert!(!activity.is_idle(Duration::from_secs(60)));
+
+        let guard = activity.stream();
+        assert!(!activity.is_idle(Duration::ZERO));
+        drop(guard);
+        assert!(activity.is_idle(Duration::ZERO));
+    }
+}
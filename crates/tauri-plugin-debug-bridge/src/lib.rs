@@ -1,11 +1,15 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use axum::{
     Router,
-    extract::DefaultBodyLimit,
-    http::{Request, StatusCode},
+    extract::{DefaultBodyLimit, State},
+    http::{HeaderName, HeaderValue, Method, Request, StatusCode, header},
     middleware::{self, Next},
-    response::{Json, Response},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
 };
 use rand::Rng;
@@ -14,18 +18,148 @@ use tauri::{
     AppHandle, Manager, Runtime,
     plugin::{Builder, TauriPlugin},
 };
-use tokio::sync::{Mutex, broadcast, oneshot};
+use tokio::sync::{Mutex, broadcast, oneshot, watch};
+use tower_http::cors::CorsLayer;
+use tower_http::decompression::RequestDecompressionLayer;
+use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
+use tracing::Instrument;
+
+pub use axum;
+pub use error::ApiError;
 
 mod backend;
+mod capabilities;
+mod diagnostics;
+mod dialog;
+mod error;
 mod events;
+mod idle;
 mod logs;
+#[cfg(feature = "mdns")]
+mod mdns;
+#[cfg(desktop)]
+mod menu;
+#[cfg(feature = "store")]
+mod store;
+mod timing;
+mod upload;
 mod webview;
 
+pub use dialog::take_dialog_response;
+#[cfg(desktop)]
+pub use menu::MENU_CLICK_EVENT;
+pub use webview::get_window;
+
 /// Plugin configuration, read from tauri.conf.json plugin section.
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default)]
 pub struct Config {
-    /// Port for the debug HTTP/WS server. Defaults to 9229.
+    /// Port for the debug HTTP/WS server. Defaults to 9229. `0` or `"auto"`
+    /// binds any free port; the log and discovery file carry the one chosen.
+    #[serde(default, deserialize_with = "deserialize_port")]
     pub port: Option<u16>,
+    /// Log every bridge request (method, path, status, latency) via `tracing`.
+    /// Defaults to false.
+    pub trace_requests: Option<bool>,
+    /// Origins allowed to call the bridge from a browser (CORS). Empty by
+    /// default, which sends no CORS headers at all.
+    #[serde(default)]
+    pub cors_origins: Vec<String>,
+    /// Enable `POST /shutdown`, which stops the bridge server (not the app).
+    /// Defaults to false.
+    pub allow_shutdown: Option<bool>,
+    /// Also listen on this Unix domain socket path (Unix only). The socket
+    /// is created with owner-only permissions and removed on shutdown.
+    pub socket: Option<String>,
+    /// Listen on TCP. Defaults to true; set to false alongside `socket` to
+    /// serve only over the Unix socket. Ignored where Unix sockets aren't
+    /// supported, so TCP stays on.
+    pub tcp: Option<bool>,
+    /// Stop the bridge server after this many seconds without a request or
+    /// an open WebSocket stream, freeing the port. Off by default.
+    pub idle_timeout_secs: Option<u64>,
+    /// Largest request body accepted once gzip-decoded, in bytes. Defaults to
+    /// 16 MiB. Bodies as sent are always capped at 1 MiB, so only compressed
+    /// requests can get past that.
+    pub max_body_bytes: Option<usize>,
+    /// How long to wait for an eval's result. Defaults to 10.
+    pub eval_timeout_secs: Option<u64>,
+    /// How long to wait for an invoked command to return. Defaults to 30.
+    pub invoke_timeout_secs: Option<u64>,
+    /// Label of the window requests target when they don't name one.
+    /// Defaults to "main".
+    pub default_window: Option<String>,
+    /// Announce the bridge over mDNS/DNS-SD (`_tauri-debug._tcp`) with its
+    /// port and app identifier, for `tauri-browser discover`. Needs the
+    /// `mdns` feature; defaults to false. The server still only listens on
+    /// 127.0.0.1 and still requires the token.
+    pub advertise: Option<bool>,
+    /// Log the auth token at startup (via `tracing`). Defaults to true; set
+    /// to false to log only that one was generated. The discovery file
+    /// still holds it either way.
+    pub log_token: Option<bool>,
+    /// Console messages a slow `/console` stream can fall behind by before
+    /// it drops them (and is told how many). Defaults to 256.
+    pub console_channel_capacity: Option<usize>,
+    /// Attribute that `testid=<id>` selectors match and snapshots report as
+    /// `testid`. Defaults to `data-testid`.
+    pub testid_attr: Option<String>,
+    /// A fixed auth token instead of a random one per startup, for test
+    /// harnesses that set it on both sides. [`TOKEN_ENV`] overrides it.
+    pub token: Option<String>,
+}
+
+/// Label of the window requests target when they don't name one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefaultWindow(pub String);
+
+impl Default for DefaultWindow {
+    fn default() -> Self {
+        Self("main".to_string())
+    }
+}
+
+impl DefaultWindow {
+    fn from_config(config: &Config) -> Self {
+        config
+            .default_window
+            .clone()
+            .map_or_else(Self::default, Self)
+    }
+
+    /// `label`, or the default window's when none is given.
+    pub fn resolve<'a>(&'a self, label: Option<&'a str>) -> &'a str {
+        label.unwrap_or(&self.0)
+    }
+}
+
+/// How long the bridge waits for results from the webview.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timeouts {
+    pub eval: std::time::Duration,
+    pub invoke: std::time::Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            eval: std::time::Duration::from_secs(10),
+            invoke: std::time::Duration::from_secs(30),
+        }
+    }
+}
+
+impl Timeouts {
+    fn from_config(config: &Config) -> Self {
+        let default = Self::default();
+        Self {
+            eval: config
+                .eval_timeout_secs
+                .map_or(default.eval, std::time::Duration::from_secs),
+            invoke: config
+                .invoke_timeout_secs
+                .map_or(default.invoke, std::time::Duration::from_secs),
+        }
+    }
 }
 
 /// Pending JS evaluation results, keyed by request ID.
@@ -35,8 +169,19 @@ pub type PendingResults = Arc<Mutex<HashMap<String, oneshot::Sender<EvalResult>>
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EvalResult {
     pub success: bool,
+    /// The returned value; `null` both for a `null` return and for code
+    /// that returned nothing, which [`undefined`](Self::undefined) tells
+    /// apart.
     pub value: Option<serde_json::Value>,
+    /// The code returned `undefined`, usually because a multi-line snippet
+    /// has no `return`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub undefined: bool,
     pub error: Option<String>,
+    /// Stack trace of a thrown error, with line numbers relative to the
+    /// evaluated code where they could be mapped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack: Option<String>,
 }
 
 /// Shared state accessible to all axum route handlers.
@@ -44,6 +189,22 @@ pub struct BridgeState<R: Runtime> {
     pub app: AppHandle<R>,
     pub pending: PendingResults,
     pub console_tx: broadcast::Sender<String>,
+    pub console_buffer: logs::ConsoleBuffer,
+    pub console_hooks: logs::ConsoleHooks,
+    pub dialogs: dialog::ArmedDialogs,
+    pub diagnostics: diagnostics::Diagnostics,
+    pub readiness: webview::WebviewReadiness,
+    pub csp: webview::CspStatus,
+    pub zoom: webview::ZoomLevels,
+    pub snapshot_walkers: webview::SnapshotWalkers,
+    pub activity: idle::Activity,
+    pub timeouts: Timeouts,
+    pub default_window: DefaultWindow,
+    /// Attribute behind `testid=` selectors (`Config.testid_attr`).
+    pub testid_attr: String,
+    /// Signals the server to shut down gracefully; taken by `POST /shutdown`
+    /// or the idle watcher.
+    pub shutdown: std::sync::Mutex<Option<oneshot::Sender<()>>>,
 }
 
 /// Health check response.
@@ -52,6 +213,69 @@ struct HealthResponse {
     status: &'static str,
     plugin: &'static str,
     version: &'static str,
+    /// Git commit the plugin was built from, when built from a checkout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git_sha: Option<&'static str>,
+    /// Cargo build profile ("debug" or "release").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build_profile: Option<&'static str>,
+    /// Whether the app was built by `tauri dev` rather than bundled by
+    /// `tauri build`.
+    is_dev: bool,
+    /// Page-load state per webview: "loading" or "ready".
+    webviews: std::collections::BTreeMap<String, &'static str>,
+    /// Whether injected scripts ran in every loaded webview. Omitted until a
+    /// page has finished loading and been probed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    csp_ok: Option<bool>,
+    /// Content-Security-Policy of the default window: its `<meta>` policy if
+    /// it has one, otherwise the app's configured policy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    csp: Option<String>,
+    /// Whether `app.withGlobalTauri` is set, defining `window.__TAURI__` for
+    /// `run-js` code. The bridge itself doesn't need it.
+    global_tauri: bool,
+}
+
+#[derive(Serialize)]
+struct ShutdownResponse {
+    success: bool,
+}
+
+/// Environment variable that sets the auth token, taking precedence over
+/// [`Config::token`].
+pub const TOKEN_ENV: &str = "TAURI_DEBUG_BRIDGE_TOKEN";
+
+/// A port number, or `"auto"` for any free port (stored as 0).
+fn deserialize_port<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u16>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Port {
+        Number(u16),
+        Named(String),
+    }
+    match Option::<Port>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Port::Number(port)) => Ok(Some(port)),
+        Some(Port::Named(name)) if name == "auto" => Ok(Some(0)),
+        Some(Port::Named(name)) => Err(serde::de::Error::custom(format!(
+            "invalid port \"{name}\": expected a number or \"auto\""
+        ))),
+    }
+}
+
+/// Preset tokens shorter than this are used, but warned about.
+const MIN_TOKEN_LEN: usize = 16;
+
+/// The token set by `env` (the value of [`TOKEN_ENV`]) or the config, if
+/// either is non-empty.
+fn preset_token(config: &Config, env: Option<String>) -> Option<String> {
+    env.into_iter()
+        .chain(config.token.clone())
+        .map(|token| token.trim().to_string())
+        .find(|token| !token.is_empty())
 }
 
 /// Generate a random 32-character hex token for auth.
@@ -64,13 +288,26 @@ fn generate_auth_token() -> String {
 /// Well-known directory for discovery files.
 const DISCOVERY_DIR: &str = "/tmp/tauri-debug-bridge";
 
-/// Write a discovery file so the CLI can auto-find this app's port and token.
-fn write_discovery_file(identifier: &str, port: u16, token: &str) -> std::io::Result<()> {
-    let dir = std::path::Path::new(DISCOVERY_DIR);
+/// Write a discovery file so the CLI can auto-find this app's port (or
+/// socket) and token.
+fn write_discovery_file(
+    identifier: &str,
+    port: Option<u16>,
+    socket: Option<&Path>,
+    token: &str,
+) -> std::io::Result<()> {
+    let dir = Path::new(DISCOVERY_DIR);
     std::fs::create_dir_all(dir)?;
 
     let file_path = dir.join(format!("{identifier}.json"));
-    let content = serde_json::json!({ "port": port, "token": token });
+    // The PID lets the CLI tell a live app from a file left by a crash.
+    let mut content = serde_json::json!({ "token": token, "pid": std::process::id() });
+    if let Some(port) = port {
+        content["port"] = port.into();
+    }
+    if let Some(socket) = socket {
+        content["socket"] = socket.to_string_lossy().into();
+    }
     std::fs::write(&file_path, content.to_string())?;
 
     #[cfg(unix)]
@@ -82,12 +319,145 @@ fn write_discovery_file(identifier: &str, port: u16, token: &str) -> std::io::Re
     Ok(())
 }
 
-/// Middleware that checks the `X-Debug-Bridge-Token` header on every request
-/// except `/health`.
-async fn auth_middleware(
-    req: Request<axum::body::Body>,
-    next: Next,
-) -> Result<Response, StatusCode> {
+/// Remove this app's discovery file once the bridge is no longer serving.
+fn remove_discovery_file(identifier: &str) -> std::io::Result<()> {
+    std::fs::remove_file(Path::new(DISCOVERY_DIR).join(format!("{identifier}.json")))
+}
+
+/// Bind a Unix socket at `path`, replacing a stale socket left by a previous
+/// run but not one a running bridge still answers on. The socket is bound
+/// in a private (0700) directory next to `path`, restricted to the owner,
+/// and only then moved into place, so it is never reachable with the
+/// default permissions.
+#[cfg(unix)]
+fn bind_unix_socket(path: &Path) -> std::io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        match std::os::unix::net::UnixStream::connect(path) {
+            Ok(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AddrInUse,
+                    format!("{} is in use by another process", path.display()),
+                ));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "socket path has no file name",
+        )
+    })?;
+    let private = path.with_file_name(format!(
+        ".{}.{}",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    std::fs::DirBuilder::new().mode(0o700).create(&private)?;
+    let staged = private.join("bridge.sock");
+    let bound = tokio::net::UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+        // Replaces a stale socket at `path` in one step.
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_file(&staged);
+    let _ = std::fs::remove_dir(&private);
+    bound
+}
+
+/// Serve `router` on `listener` until `stop` fires.
+async fn serve_until<L>(
+    listener: L,
+    router: Router,
+    mut stop: watch::Receiver<()>,
+) -> std::io::Result<()>
+where
+    L: axum::serve::Listener,
+    L::Addr: std::fmt::Debug,
+{
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async move {
+            let _ = stop.changed().await;
+        })
+        .await
+}
+
+/// Header correlating a request with its log lines.
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied request id kept; longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Middleware giving every request an id: the client's `X-Request-Id`, or a
+/// fresh one. Everything logged while handling the request is recorded under
+/// a span carrying the id, and the response echoes it back.
+async fn request_id(mut req: Request<axum::body::Body>, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .filter(|v| v.len() <= MAX_REQUEST_ID_LEN && v.to_str().is_ok_and(|s| !s.is_empty()))
+        .cloned()
+        .unwrap_or_else(|| HeaderValue::from_str(&webview::uuid_v4()).unwrap());
+    req.headers_mut().insert(REQUEST_ID_HEADER, id.clone());
+
+    let span = tracing::info_span!("request", id = id.to_str().unwrap_or_default());
+    let mut response = next.run(req).instrument(span).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, id);
+    response
+}
+
+/// Subprotocol the WebSocket endpoints select. Clients that can't set headers
+/// on a handshake (browsers) offer it alongside `debug-bridge.token.<token>`;
+/// the server echoes this one, never the entry carrying the token.
+pub const WS_PROTOCOL: &str = "debug-bridge";
+
+/// `Sec-WebSocket-Protocol` entry prefix carrying the auth token.
+const WS_TOKEN_PROTOCOL_PREFIX: &str = "debug-bridge.token.";
+
+/// The token a request carries, from the first of: the
+/// `X-Debug-Bridge-Token` header, `Authorization: Bearer <token>`, or a
+/// `debug-bridge.token.<token>` subprotocol. Never the query string, which
+/// ends up in proxy and server logs.
+fn provided_token(headers: &axum::http::HeaderMap) -> Option<&str> {
+    let header_str = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    header_str("X-Debug-Bridge-Token")
+        .or_else(|| {
+            header_str("Authorization")?
+                .strip_prefix("Bearer ")
+                .map(str::trim)
+        })
+        .or_else(|| {
+            headers
+                .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(','))
+                .find_map(|p| p.trim().strip_prefix(WS_TOKEN_PROTOCOL_PREFIX))
+        })
+}
+
+/// Whether the query string has a `token` parameter.
+fn token_in_query(query: Option<&str>) -> bool {
+    query
+        .unwrap_or_default()
+        .split('&')
+        .any(|pair| pair.split('=').next() == Some("token"))
+}
+
+/// Middleware that checks the auth token (see [`provided_token`]) on every
+/// request except `/health`. WebSocket handshakes are checked the same way.
+async fn auth_middleware(req: Request<axum::body::Body>, next: Next) -> Result<Response, ApiError> {
     // Skip auth for health check endpoint.
     if req.uri().path() == "/health" {
         return Ok(next.run(req).await);
@@ -99,14 +469,21 @@ async fn auth_middleware(
         .map(|t| t.0.clone())
         .unwrap_or_default();
 
-    let provided = req
-        .headers()
-        .get("X-Debug-Bridge-Token")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
+    let provided = provided_token(req.headers()).unwrap_or("");
 
     if provided != expected {
-        return Err(StatusCode::UNAUTHORIZED);
+        if token_in_query(req.uri().query()) {
+            return Err(ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "tokens in the URL aren't accepted (they leak into logs); send the \
+                 X-Debug-Bridge-Token or Authorization: Bearer header, or the \
+                 debug-bridge.token.<token> WebSocket subprotocol",
+            ));
+        }
+        return Err(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "missing or invalid X-Debug-Bridge-Token",
+        ));
     }
 
     Ok(next.run(req).await)
@@ -117,21 +494,26 @@ async fn auth_middleware(
 struct AuthToken(String);
 
 /// Tauri command: receives JS eval results from the webview.
-/// Called by injected JS via `window.__TAURI__.invoke('plugin:debug-bridge|eval_callback', ...)`.
+/// Called by injected JS via `window.__TAURI_INTERNALS__.invoke('plugin:debug-bridge|eval_callback', ...)`.
 #[tauri::command]
 async fn eval_callback(
     pending: tauri::State<'_, PendingResults>,
     id: String,
     success: bool,
     value: Option<serde_json::Value>,
+    undefined: Option<bool>,
     error: Option<String>,
+    trace: Option<webview::ErrorTrace>,
 ) -> Result<(), String> {
     let mut map = pending.lock().await;
     if let Some(tx) = map.remove(&id) {
+        let undefined = undefined.unwrap_or(false);
         let _ = tx.send(EvalResult {
             success,
-            value,
+            value: value.filter(|_| !undefined),
+            undefined,
             error,
+            stack: trace.map(|t| webview::map_stack(&t.stack, t.probe.as_deref(), t.code_lines)),
         });
     }
     Ok(())
@@ -139,77 +521,298 @@ async fn eval_callback(
 
 /// Tauri command: receives JS console messages from the webview.
 /// Called by the injected console hook via `__TAURI_INTERNALS__.invoke`.
+/// Messages are tagged with the label of the webview that sent them.
 #[tauri::command]
-async fn console_callback(
+async fn console_callback<R: Runtime>(
+    webview: tauri::Webview<R>,
     console_tx: tauri::State<'_, broadcast::Sender<String>>,
+    console_buffer: tauri::State<'_, logs::ConsoleBuffer>,
     level: String,
-    message: String,
+    args: Vec<serde_json::Value>,
 ) -> Result<(), String> {
-    let msg = serde_json::json!({
-        "level": level,
-        "message": message,
-    });
-    let _ = console_tx.send(msg.to_string());
+    let msg = logs::console_message(webview.label(), &level, args).to_string();
+    console_buffer.push(msg.clone());
+    let _ = console_tx.send(msg);
     Ok(())
 }
 
 /// Build the axum router with all debug bridge routes.
-fn build_router<R: Runtime>(state: Arc<BridgeState<R>>, token: String) -> Router {
-    let auth_token = AuthToken(token);
+fn build_router<R: Runtime>(
+    state: Arc<BridgeState<R>>,
+    routes: CustomRoutes<R>,
+    token: String,
+    config: &Config,
+) -> Router {
+    let readiness = state.readiness.clone();
+    let csp = state.csp.clone();
+    let default_window = state.default_window.clone();
+    let global_tauri = state.app.config().app.with_global_tauri;
+    let activity = state.activity.clone();
+    let manifest = Arc::new(capabilities::manifest(config));
 
     // Stateful routes (require BridgeState via axum State extractor).
     let stateful = Router::new()
         // Webview
         .route("/eval", post(webview::webview_eval::<R>))
         .route("/screenshot", get(webview::screenshot::<R>))
+        .route("/screenshot/all", get(webview::screenshot_all::<R>))
         .route("/snapshot", get(webview::snapshot::<R>))
+        .route("/refs", get(webview::refs::<R>))
+        .route("/refs/clear", post(webview::clear_refs::<R>))
+        .route("/pagestate", get(webview::page_state::<R>))
         .route("/click", post(webview::click::<R>))
         .route("/fill", post(webview::fill::<R>))
+        .route("/select-text", post(webview::select_text::<R>))
+        .route("/wait/navigation", post(webview::wait_navigation::<R>))
+        .route("/styles", get(webview::styles::<R>))
+        .route("/clickable", get(webview::clickable::<R>))
+        .route("/html", get(webview::html::<R>))
+        .route(
+            "/zoom",
+            get(webview::zoom::<R>).post(webview::set_zoom::<R>),
+        )
+        .route(
+            "/clipboard",
+            get(webview::clipboard_read::<R>).post(webview::clipboard_write::<R>),
+        )
         // Backend
         .route("/invoke", post(backend::invoke::<R>))
+        .route("/invoke/batch", post(backend::invoke_batch::<R>))
         .route("/commands", get(backend::commands::<R>))
         .route("/state", get(backend::state::<R>))
         .route("/windows", get(backend::windows::<R>))
+        .route("/windows/state", get(backend::window_state::<R>))
+        .route("/app", get(backend::app_info::<R>))
         .route("/config", get(backend::config::<R>))
+        .route("/paths", get(backend::paths::<R>))
+        .route("/env", get(backend::env::<R>))
+        .route("/store", {
+            #[cfg(feature = "store")]
+            {
+                get(store::store::<R>)
+            }
+            #[cfg(not(feature = "store"))]
+            {
+                get(store_disabled)
+            }
+        })
+        // Bridge lifecycle
+        .route(
+            "/shutdown",
+            if config.allow_shutdown.unwrap_or(false) {
+                post(shutdown::<R>)
+            } else {
+                post(shutdown_disabled)
+            },
+        )
+        // Dialogs
+        .route("/dialog", get(dialog::armed::<R>))
+        .route("/dialog/arm", post(dialog::arm_dialog::<R>))
         // Events
         .route("/events/emit", post(events::emit::<R>))
+        .route("/events/emit-await", post(events::emit_await::<R>))
         .route("/events/list", get(events::list::<R>))
         .route("/events/listen", get(events::listen::<R>))
         // Logs (WebSocket)
+        .route("/diagnostics", get(diagnostics::diagnostics_ws::<R>))
         .route("/logs", get(logs::logs_ws::<R>))
         .route("/console", get(logs::console_ws::<R>))
+        .route("/console/recent", get(logs::console_recent::<R>))
+        .route("/console/poll", get(logs::console_poll::<R>));
+
+    // Native menu and window modes (desktop only)
+    #[cfg(desktop)]
+    let stateful = stateful
+        .route("/menu", get(menu::list::<R>))
+        .route("/menu/click", post(menu::click::<R>))
+        .route("/windows/state", post(backend::set_window_state::<R>));
+
+    let stateful = stateful
+        // Host app routes
+        .merge(routes)
         .with_state(state);
 
-    // Combine stateless health route with stateful routes, then apply security layers.
+    let router = Router::new()
+        .route(
+            "/health",
+            get(move || health(readiness, csp, default_window, global_tauri)),
+        )
+        .route(
+            "/capabilities",
+            get(move || capabilities::capabilities(manifest.clone())),
+        )
+        .merge(stateful)
+        // Inside auth, so only accepted requests count as activity.
+        .layer(middleware::from_fn_with_state(activity, idle::track));
+    apply_layers(router, token, config)
+}
+
+/// Apply the security and diagnostic layers shared by every route.
+fn apply_layers(router: Router, token: String, config: &Config) -> Router {
+    let auth_token = AuthToken(token);
+
     // Layer order: outermost layer is the LAST .layer() call.
     // Extension must be outer so auth_middleware can read it from request extensions.
-    Router::new()
-        .route("/health", get(health))
-        .merge(stateful)
-        // Security: 1 MB body size limit
-        .layer(DefaultBodyLimit::max(1_048_576))
+    let router = router
+        // `?timing=true`: Server-Timing header with eval round trips
+        .layer(middleware::from_fn(timing::server_timing))
+        // Security: cap on the decoded body, so a small gzip body can't
+        // inflate without bound
+        .layer(DefaultBodyLimit::max(
+            config.max_body_bytes.unwrap_or(DEFAULT_MAX_BODY_BYTES),
+        ))
+        // Accept `Content-Encoding: gzip` bodies for large evals and invokes
+        .layer(RequestDecompressionLayer::new())
+        // Security: 1 MB body size limit on the wire
+        .layer(middleware::from_fn(wire_body_limit))
         // Security: auth token check (reads AuthToken from extensions)
         .layer(middleware::from_fn(auth_middleware))
-        // Inject auth token into request extensions (must be outermost)
-        .layer(axum::Extension(auth_token))
+        // Inject auth token into request extensions (outermost security layer)
+        .layer(axum::Extension(auth_token));
+
+    // CORS wraps auth so preflight OPTIONS requests (which never carry the
+    // token) are answered by the CORS layer without reaching auth_middleware.
+    let router = match cors_layer(&config.cors_origins) {
+        Some(cors) => router.layer(cors),
+        None => router,
+    };
+
+    // Request tracing wraps everything so requests rejected by auth are logged
+    // too. Headers are never recorded since they carry the token.
+    let router = if config.trace_requests.unwrap_or(false) {
+        router.layer(
+            TraceLayer::new_for_http()
+                .make_span_with(
+                    DefaultMakeSpan::new()
+                        .level(tracing::Level::INFO)
+                        .include_headers(false),
+                )
+                .on_request(())
+                .on_response(DefaultOnResponse::new().level(tracing::Level::INFO)),
+        )
+    } else {
+        router
+    };
+
+    // Outermost, so every log line for the request (including the trace
+    // layer's) carries its id, and even CORS and auth rejections echo it.
+    router
+        .layer(middleware::from_fn(error::json_error_bodies))
+        .layer(middleware::from_fn(request_id))
+}
+
+/// Largest request body as sent, compressed or not. Multipart uploads are
+/// exempt and only held to `max_body_bytes`.
+const MAX_WIRE_BODY_BYTES: usize = 1_048_576;
+
+/// Enforce [`MAX_WIRE_BODY_BYTES`] on everything but multipart uploads.
+async fn wire_body_limit(req: Request<axum::body::Body>, next: Next) -> Response {
+    if upload::is_multipart(req.headers()) {
+        return next.run(req).await;
+    }
+    let declared = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared.is_some_and(|len| len > MAX_WIRE_BODY_BYTES) {
+        return ApiError::new(StatusCode::PAYLOAD_TOO_LARGE, "request body too large")
+            .into_response();
+    }
+    next.run(
+        req.map(|body| {
+            axum::body::Body::new(http_body_util::Limited::new(body, MAX_WIRE_BODY_BYTES))
+        }),
+    )
+    .await
 }
 
-async fn health() -> Json<HealthResponse> {
+/// Default cap on a request body after gzip decoding.
+const DEFAULT_MAX_BODY_BYTES: usize = 16 * 1_048_576;
+
+/// Build a CORS layer allowing the configured origins to send the token header.
+/// Returns `None` when no (valid) origins are configured.
+fn cors_layer(origins: &[String]) -> Option<CorsLayer> {
+    let origins: Vec<HeaderValue> = origins
+        .iter()
+        .filter_map(|o| match HeaderValue::from_str(o) {
+            Ok(v) => Some(v),
+            Err(_) => {
+                tracing::warn!("ignoring invalid CORS origin: {o}");
+                None
+            }
+        })
+        .collect();
+
+    if origins.is_empty() {
+        return None;
+    }
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([
+                header::CONTENT_TYPE,
+                header::AUTHORIZATION,
+                HeaderName::from_static("x-debug-bridge-token"),
+                REQUEST_ID_HEADER,
+            ])
+            .expose_headers([REQUEST_ID_HEADER]),
+    )
+}
+
+async fn health(
+    readiness: webview::WebviewReadiness,
+    csp: webview::CspStatus,
+    default_window: DefaultWindow,
+    global_tauri: bool,
+) -> Json<HealthResponse> {
     Json(HealthResponse {
         status: "ok",
         plugin: "tauri-plugin-debug-bridge",
         version: env!("CARGO_PKG_VERSION"),
+        git_sha: option_env!("DEBUG_BRIDGE_GIT_SHA"),
+        build_profile: option_env!("DEBUG_BRIDGE_BUILD_PROFILE"),
+        is_dev: tauri::is_dev(),
+        webviews: readiness.states(),
+        csp_ok: csp.ok(),
+        csp: csp.policy(default_window.resolve(None)),
+        global_tauri,
     })
 }
 
-/// Initialize the debug bridge plugin.
-///
-/// ```rust,no_run
-/// // In your Tauri app's lib.rs:
-/// #[cfg(feature = "debug")]
-/// app.plugin(tauri_plugin_debug_bridge::init());
-/// ```
+/// POST /shutdown — stop the bridge server gracefully. In-flight requests
+/// finish, the discovery file is removed, and the app keeps running.
+async fn shutdown<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+) -> Result<Json<ShutdownResponse>, ApiError> {
+    let tx = state.shutdown.lock().unwrap().take().ok_or(ApiError::new(
+        StatusCode::CONFLICT,
+        "debug bridge is already shutting down".to_string(),
+    ))?;
+    let _ = tx.send(());
+    tracing::info!("debug-bridge shutdown requested");
+    Ok(Json(ShutdownResponse { success: true }))
+}
+
+async fn shutdown_disabled() -> ApiError {
+    ApiError::new(
+        StatusCode::FORBIDDEN,
+        "shutdown is disabled; set allow_shutdown in the debug-bridge plugin config",
+    )
+}
+
+#[cfg(not(feature = "store"))]
+async fn store_disabled() -> ApiError {
+    ApiError::new(
+        StatusCode::NOT_IMPLEMENTED,
+        "store plugin not enabled; build tauri-plugin-debug-bridge with the `store` feature",
+    )
+}
+
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
@@ -223,67 +826,871 @@ mod tests {
         );
     }
 
+    #[test]
+    fn timeouts_come_from_config() {
+        assert_eq!(
+            Timeouts::from_config(&Config::default()),
+            Timeouts::default()
+        );
+
+        let config = Config {
+            invoke_timeout_secs: Some(120),
+            ..Default::default()
+        };
+        let timeouts = Timeouts::from_config(&config);
+        assert_eq!(timeouts.eval, std::time::Duration::from_secs(10));
+        assert_eq!(timeouts.invoke, std::time::Duration::from_secs(120));
+    }
+
+    #[test]
+    fn default_window_comes_from_config() {
+        let default = DefaultWindow::from_config(&Config::default());
+        assert_eq!(default.resolve(None), "main");
+
+        let config = Config {
+            default_window: Some("editor".to_string()),
+            ..Default::default()
+        };
+        let default = DefaultWindow::from_config(&config);
+        assert_eq!(default.resolve(None), "editor");
+        assert_eq!(default.resolve(Some("settings")), "settings");
+    }
+
+    #[test]
+    fn auto_port_means_any_free_port() {
+        let port = |json: serde_json::Value| {
+            serde_json::from_value::<Config>(json).map(|config| config.port)
+        };
+        assert_eq!(port(serde_json::json!({})).unwrap(), None);
+        assert_eq!(
+            port(serde_json::json!({ "port": 9300 })).unwrap(),
+            Some(9300)
+        );
+        assert_eq!(port(serde_json::json!({ "port": 0 })).unwrap(), Some(0));
+        assert_eq!(
+            port(serde_json::json!({ "port": "auto" })).unwrap(),
+            Some(0)
+        );
+        assert!(port(serde_json::json!({ "port": "any" })).is_err());
+        assert!(port(serde_json::json!({ "port": 70000 })).is_err());
+    }
+
+    #[test]
+    fn preset_tokens_come_from_env_then_config() {
+        let config = Config {
+            token: Some("from-config".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            preset_token(&config, Some("from-env".to_string())).as_deref(),
+            Some("from-env")
+        );
+        assert_eq!(
+            preset_token(&config, Some(" ".to_string())).as_deref(),
+            Some("from-config"),
+            "a blank variable doesn't count"
+        );
+        assert_eq!(preset_token(&Config::default(), None), None);
+    }
+
     #[test]
     fn auth_tokens_are_unique() {
         let t1 = generate_auth_token();
         let t2 = generate_auth_token();
         assert_ne!(t1, t2, "consecutive tokens should differ");
     }
+
+    /// Collects formatted tracing output so tests can assert on it.
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for CapturedLogs {
+        type Writer = Self;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    /// Send one authenticated `/health` request through the layered router
+    /// and return whatever was logged.
+    async fn traced_health_request(config: &Config) -> String {
+        use tower::ServiceExt;
+
+        let logs = CapturedLogs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(logs.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let router = apply_layers(
+            Router::new().route(
+                "/health",
+                get(|| {
+                    health(
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                        false,
+                    )
+                }),
+            ),
+            "secret-token".to_string(),
+            config,
+        );
+        let req = Request::get("/health")
+            .header("X-Debug-Bridge-Token", "secret-token")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let resp = router.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        String::from_utf8(logs.0.lock().unwrap().clone()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn trace_layer_logs_requests_when_enabled() {
+        let config = Config {
+            trace_requests: Some(true),
+            ..Default::default()
+        };
+        let logs = traced_health_request(&config).await;
+        assert!(logs.contains("/health"), "request path should be logged");
+        assert!(logs.contains("200"), "response status should be logged");
+        assert!(
+            !logs.contains("secret-token"),
+            "auth token must never be logged"
+        );
+    }
+
+    #[tokio::test]
+    async fn request_ids_are_logged_and_echoed() {
+        use tower::ServiceExt;
+
+        let logs = CapturedLogs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(logs.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let config = Config {
+            trace_requests: Some(true),
+            ..Default::default()
+        };
+        let router = apply_layers(
+            Router::new().route(
+                "/health",
+                get(|| {
+                    health(
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                        false,
+                    )
+                }),
+            ),
+            "secret-token".to_string(),
+            &config,
+        );
+        let req = Request::get("/health")
+            .header("X-Request-Id", "cli-run-42")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let resp = router.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.headers()["x-request-id"], "cli-run-42");
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            logs.contains("cli-run-42"),
+            "id should be on the span: {logs}"
+        );
+
+        // Without one (or with an unusable one), the bridge makes its own.
+        let req = Request::get("/health")
+            .header("X-Request-Id", "x".repeat(MAX_REQUEST_ID_LEN + 1))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let resp = router.oneshot(req).await.unwrap();
+        assert_eq!(resp.headers()["x-request-id"].len(), 32);
+    }
+
+    #[tokio::test]
+    async fn trace_layer_absent_by_default() {
+        let logs = traced_health_request(&Config::default()).await;
+        assert!(logs.is_empty(), "no request logs without trace_requests");
+    }
+
+    fn echo_router(config: &Config) -> Router {
+        apply_layers(
+            Router::new().route(
+                "/echo",
+                axum::routing::post(
+                    |body: axum::body::Bytes| async move { body.len().to_string() },
+                ),
+            ),
+            "secret-token".to_string(),
+            config,
+        )
+    }
+
+    fn gzipped_post(body: &[u8]) -> Request<axum::body::Body> {
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(body).unwrap();
+        Request::post("/echo")
+            .header("X-Debug-Bridge-Token", "secret-token")
+            .header(header::CONTENT_ENCODING, "gzip")
+            .body(axum::body::Body::from(encoder.finish().unwrap()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn gzip_bodies_are_decoded_up_to_the_cap() {
+        use tower::ServiceExt;
+
+        // 4 MiB of JS compresses far below the 1 MiB wire limit.
+        let big = vec![b'x'; 4 * 1_048_576];
+        let resp = echo_router(&Config::default())
+            .oneshot(gzipped_post(&big))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, big.len().to_string());
+
+        let config = Config {
+            max_body_bytes: Some(1_048_576),
+            ..Default::default()
+        };
+        let resp = echo_router(&config)
+            .oneshot(gzipped_post(&big))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Uncompressed bodies are still held to the wire limit.
+        let req = Request::post("/echo")
+            .header("X-Debug-Bridge-Token", "secret-token")
+            .header(header::CONTENT_LENGTH, big.len())
+            .body(axum::body::Body::from(big))
+            .unwrap();
+        let resp = echo_router(&Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn tokens_come_from_headers_only() {
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut map = axum::http::HeaderMap::new();
+            for (name, value) in pairs {
+                map.append(*name, HeaderValue::from_static(value));
+            }
+            map
+        };
+        let h = headers(&[("X-Debug-Bridge-Token", "abc")]);
+        assert_eq!(provided_token(&h), Some("abc"));
+        let h = headers(&[("Authorization", "Bearer abc")]);
+        assert_eq!(provided_token(&h), Some("abc"));
+        let h = headers(&[("Authorization", "Basic dXNlcjpwdw==")]);
+        assert_eq!(provided_token(&h), None);
+        let h = headers(&[(
+            "Sec-WebSocket-Protocol",
+            "debug-bridge, debug-bridge.token.abc",
+        )]);
+        assert_eq!(provided_token(&h), Some("abc"));
+        let h = headers(&[("Sec-WebSocket-Protocol", "debug-bridge")]);
+        assert_eq!(provided_token(&h), None);
+
+        assert!(token_in_query(Some("window=main&token=abc")));
+        assert!(!token_in_query(Some("tokens=1")));
+        assert!(!token_in_query(None));
+    }
+
+    #[tokio::test]
+    async fn query_string_tokens_are_refused() {
+        use tower::ServiceExt;
+
+        let req = Request::post("/echo?token=secret-token")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let resp = echo_router(&Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("leak into logs"));
+
+        let req = Request::post("/echo")
+            .header(header::AUTHORIZATION, "Bearer secret-token")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let resp = echo_router(&Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn websocket_upgrades_need_the_token() {
+        use axum::extract::ws::WebSocketUpgrade;
+        use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+
+        let router = apply_layers(
+            Router::new().route(
+                "/ws",
+                get(|ws: WebSocketUpgrade| async move {
+                    ws.protocols([WS_PROTOCOL]).on_upgrade(|_| async {})
+                }),
+            ),
+            "secret-token".to_string(),
+            &Config::default(),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        let (_stop_tx, stop_rx) = watch::channel(());
+        tokio::spawn(serve_until(listener, router, stop_rx));
+
+        let upgrade = |headers: &[(&'static str, &'static str)]| {
+            let mut req = url.as_str().into_client_request().unwrap();
+            for (name, value) in headers {
+                req.headers_mut()
+                    .insert(*name, HeaderValue::from_static(value));
+            }
+            tokio_tungstenite::connect_async(req)
+        };
+
+        match upgrade(&[]).await {
+            Err(tungstenite::Error::Http(resp)) => {
+                assert_eq!(resp.status(), StatusCode::UNAUTHORIZED)
+            }
+            other => panic!("expected a 401, got {other:?}"),
+        }
+        assert!(upgrade(&[("X-Debug-Bridge-Token", "wrong")]).await.is_err());
+
+        upgrade(&[("X-Debug-Bridge-Token", "secret-token")])
+            .await
+            .unwrap();
+        upgrade(&[("Authorization", "Bearer secret-token")])
+            .await
+            .unwrap();
+        // Browsers fail the handshake unless the server picks one of the
+        // offered subprotocols, and it mustn't echo the token back.
+        let (_, resp) = upgrade(&[(
+            "Sec-WebSocket-Protocol",
+            "debug-bridge, debug-bridge.token.secret-token",
+        )])
+        .await
+        .unwrap();
+        assert_eq!(resp.headers()["Sec-WebSocket-Protocol"], WS_PROTOCOL);
+    }
+
+    #[tokio::test]
+    async fn rejections_have_json_error_bodies() {
+        use tower::ServiceExt;
+
+        async fn error_body(resp: Response) -> serde_json::Value {
+            let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice(&body).unwrap()
+        }
+
+        let req = Request::post("/echo")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let resp = echo_router(&Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(error_body(resp).await["code"], "unauthorized");
+
+        // Plain-text rejections from extractors are rewritten too.
+        let router = apply_layers(
+            Router::new().route(
+                "/json",
+                axum::routing::post(|Json(v): Json<serde_json::Value>| async move { Json(v) }),
+            ),
+            "secret-token".to_string(),
+            &Config::default(),
+        );
+        let req = Request::post("/json")
+            .header("X-Debug-Bridge-Token", "secret-token")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from("{not json"))
+            .unwrap();
+        let resp = router.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = error_body(resp).await;
+        assert_eq!(body["code"], "bad_request");
+        assert!(body["error"].as_str().unwrap().contains("JSON"));
+    }
+
+    #[tokio::test]
+    async fn multipart_uploads_skip_the_wire_limit() {
+        use tower::ServiceExt;
+
+        let router = apply_layers(
+            Router::new().route(
+                "/invoke",
+                axum::routing::post(
+                    |upload::JsonOrMultipart(req): upload::JsonOrMultipart<
+                        backend::InvokeRequest,
+                    >| async move {
+                        format!(
+                            "{} {}",
+                            req.command,
+                            req.args["data"].as_str().unwrap().len()
+                        )
+                    },
+                ),
+            ),
+            "secret-token".to_string(),
+            &Config::default(),
+        );
+        let file = vec![7u8; 3 * 1_048_576];
+        let mut body = Vec::new();
+        body.extend_from_slice(
+            b"--XyZ\r\nContent-Disposition: form-data; name=\"json\"\r\n\r\n{\"command\":\"upload\"}\r\n",
+        );
+        body.extend_from_slice(
+            b"--XyZ\r\nContent-Disposition: form-data; name=\"file:data\"; filename=\"blob.bin\"\r\n\r\n",
+        );
+        body.extend_from_slice(&file);
+        body.extend_from_slice(b"\r\n--XyZ--\r\n");
+        let req = Request::post("/invoke")
+            .header("X-Debug-Bridge-Token", "secret-token")
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=XyZ")
+            .header(header::CONTENT_LENGTH, body.len())
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let resp = router.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let text = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        // 3 MiB of base64 is 4 MiB.
+        assert_eq!(text, "upload 4194304");
+    }
+
+    fn cors_router(origins: &[&str]) -> Router {
+        let config = Config {
+            cors_origins: origins.iter().map(|o| o.to_string()).collect(),
+            ..Default::default()
+        };
+        apply_layers(
+            Router::new().route(
+                "/health",
+                get(|| {
+                    health(
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                        false,
+                    )
+                }),
+            ),
+            "secret-token".to_string(),
+            &config,
+        )
+    }
+
+    fn preflight(origin: &str) -> Request<axum::body::Body> {
+        Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/windows")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .header(
+                header::ACCESS_CONTROL_REQUEST_HEADERS,
+                "x-debug-bridge-token",
+            )
+            .body(axum::body::Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn cors_preflight_bypasses_auth_for_allowed_origin() {
+        use tower::ServiceExt;
+
+        let resp = cors_router(&["http://localhost:5173"])
+            .oneshot(preflight("http://localhost:5173"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "http://localhost:5173"
+        );
+    }
+
+    #[tokio::test]
+    async fn cors_preflight_allows_bearer_auth() {
+        use tower::ServiceExt;
+
+        let mut request = preflight("http://localhost:5173");
+        request.headers_mut().insert(
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            HeaderValue::from_static("authorization"),
+        );
+        let resp = cors_router(&["http://localhost:5173"])
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let allowed = resp.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS]
+            .to_str()
+            .unwrap()
+            .to_ascii_lowercase();
+        assert!(
+            allowed.split(',').any(|h| h.trim() == "authorization"),
+            "{allowed}"
+        );
+    }
+
+    #[tokio::test]
+    async fn cors_disabled_by_default() {
+        use tower::ServiceExt;
+
+        let resp = cors_router(&[])
+            .oneshot(preflight("http://localhost:5173"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert!(
+            !resp
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+    }
+
+    #[tokio::test]
+    async fn cors_ignores_other_origins() {
+        use tower::ServiceExt;
+
+        let resp = cors_router(&["http://localhost:5173"])
+            .oneshot(preflight("http://evil.example"))
+            .await
+            .unwrap();
+        assert!(
+            !resp
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_replaces_stale_socket_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("debug-bridge-{}", generate_auth_token()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bridge.sock");
+
+        // A socket left behind by a previous run is replaced.
+        drop(bind_unix_socket(&path).unwrap());
+        let listener = bind_unix_socket(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // One a running bridge still listens on is not.
+        let err = bind_unix_socket(&path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+        assert!(std::os::unix::net::UnixStream::connect(&path).is_ok());
+        drop(listener);
+        let leftovers: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(leftovers.len(), 1, "staging directory cleaned up");
+
+        // Anything else at the path is left alone.
+        let file = dir.join("not-a-socket");
+        std::fs::write(&file, "keep me").unwrap();
+        assert!(bind_unix_socket(&file).is_err());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep me");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
 
+/// Initialize the debug bridge plugin.
+///
+/// ```rust,no_run
+/// // In your Tauri app's lib.rs:
+/// #[cfg(feature = "debug")]
+/// app.plugin(tauri_plugin_debug_bridge::init());
+/// ```
 pub fn init<R: Runtime>() -> TauriPlugin<R, Option<Config>> {
+    init_with_routes(Router::new())
+}
+
+/// Routes supplied by the host app, merged into the bridge router.
+pub type CustomRoutes<R> = Router<Arc<BridgeState<R>>>;
+
+/// Initialize the debug bridge plugin with extra app-specific routes.
+///
+/// The routes are merged into the bridge router before the security layers
+/// are applied, so they require the auth token like every built-in endpoint.
+/// Handlers can extract `State<Arc<BridgeState<R>>>` to reach the host
+/// `AppHandle` through `state.app`. Paths must not collide with built-in
+/// routes (axum panics on overlapping routes).
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+///
+/// use tauri::{Manager, Runtime, plugin::TauriPlugin};
+/// use tauri_plugin_debug_bridge::{
+///     BridgeState, Config, CustomRoutes,
+///     axum::{Json, extract::State, routing::get},
+/// };
+///
+/// /// GET /app/window-count — number of open webview windows.
+/// async fn window_count<R: Runtime>(State(state): State<Arc<BridgeState<R>>>) -> Json<usize> {
+///     Json(state.app.webview_windows().len())
+/// }
+///
+/// fn debug_bridge<R: Runtime>() -> TauriPlugin<R, Option<Config>> {
+///     let routes = CustomRoutes::new().route("/app/window-count", get(window_count::<R>));
+///     tauri_plugin_debug_bridge::init_with_routes(routes)
+/// }
+/// ```
+pub fn init_with_routes<R: Runtime>(routes: CustomRoutes<R>) -> TauriPlugin<R, Option<Config>> {
     let pending: PendingResults = Arc::new(Mutex::new(HashMap::new()));
+    let readiness = webview::WebviewReadiness::default();
+    let console_hooks = logs::ConsoleHooks::default();
+    let page_loads = readiness.clone();
+    let crashes = readiness.clone();
+    let rehook = console_hooks.clone();
 
     Builder::<R, Option<Config>>::new("debug-bridge")
         .invoke_handler(tauri::generate_handler![eval_callback, console_callback])
+        .on_webview_ready(move |webview| webview::watch_for_crashes(&webview, crashes.clone()))
+        .on_page_load(move |webview, payload| {
+            let finished = payload.event() == tauri::webview::PageLoadEvent::Finished;
+            page_loads.set(webview.label(), finished);
+            let state = webview.try_state::<Arc<BridgeState<R>>>();
+            if let Some(state) = &state {
+                // A new page starts without the cached snapshot walker.
+                state.snapshot_walkers.forget(webview.label());
+            }
+            if finished {
+                rehook.page_loaded(webview);
+                if let Some(state) = state {
+                    webview::probe_csp(state.inner().clone(), webview.label().to_string());
+                }
+            }
+        })
         .setup(move |app, api| {
-            let port = api.config().as_ref().and_then(|c| c.port).unwrap_or(9229);
+            let config = api.config().clone().unwrap_or_default();
+            let port = config.port.unwrap_or(9229);
+            let socket = config.socket.as_ref().map(PathBuf::from);
+            // Without a socket to fall back on, TCP stays on regardless. That
+            // includes every non-Unix platform, where `socket` can't be served.
+            let serve_tcp = config.tcp.unwrap_or(true) || socket.is_none() || cfg!(not(unix));
 
-            // Generate auth token for this session.
-            let token = generate_auth_token();
-            println!("debug-bridge auth token: {token}");
-            tracing::info!("debug-bridge auth token: {token}");
+            // Use a preset auth token, or generate one for this session.
+            let preset = preset_token(&config, std::env::var(TOKEN_ENV).ok());
+            if preset.as_ref().is_some_and(|t| t.len() < MIN_TOKEN_LEN) {
+                tracing::warn!(
+                    "debug-bridge: the preset auth token is under {MIN_TOKEN_LEN} characters; \
+                     anything on this machine could guess it"
+                );
+            }
+            let token = preset.unwrap_or_else(generate_auth_token);
+            if config.log_token.unwrap_or(true) {
+                tracing::info!("debug-bridge auth token: {token}");
+            } else {
+                tracing::info!("debug-bridge auth token set (see the discovery file)");
+            }
 
             // Broadcast channel for JS console messages.
-            let (console_tx, _) = broadcast::channel(256);
+            let capacity = config
+                .console_channel_capacity
+                .unwrap_or(logs::DEFAULT_CONSOLE_CHANNEL_CAPACITY);
+            let (console_tx, _) = broadcast::channel(capacity.max(1));
 
             // Share state with both Tauri commands and axum handlers.
             app.manage(pending.clone());
             app.manage(console_tx.clone());
 
+            // Recent console messages, replayed by /console/recent.
+            let console_buffer = logs::ConsoleBuffer::default();
+            app.manage(console_buffer.clone());
+
+            // Armed dialog responses, consumed by the app via take_dialog_response.
+            let dialogs = dialog::ArmedDialogs::default();
+            app.manage(dialogs.clone());
+
+            // Fired by POST /shutdown to stop the server gracefully.
+            let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
             let state = Arc::new(BridgeState {
                 app: app.clone(),
                 pending,
                 console_tx,
+                console_buffer,
+                console_hooks,
+                dialogs,
+                diagnostics: diagnostics::Diagnostics::default(),
+                readiness,
+                csp: webview::CspStatus::new(app),
+                zoom: webview::ZoomLevels::default(),
+                snapshot_walkers: webview::SnapshotWalkers::default(),
+                activity: idle::Activity::default(),
+                timeouts: Timeouts::from_config(&config),
+                default_window: DefaultWindow::from_config(&config),
+                testid_attr: config
+                    .testid_attr
+                    .clone()
+                    .unwrap_or_else(|| webview::DEFAULT_TESTID_ATTR.to_string()),
+                shutdown: std::sync::Mutex::new(Some(shutdown_tx)),
             });
+            let diagnostics = state.diagnostics.clone();
+            // Lets on_page_load reach the state to probe each loaded page.
+            app.manage(state.clone());
+            let idle_state = state.clone();
+            let idle_timeout = config.idle_timeout_secs.map(std::time::Duration::from_secs);
+            let advertise = config.advertise.unwrap_or(false);
 
-            let router = build_router(state, token.clone());
+            let router = build_router(state, routes, token.clone(), &config);
             let identifier = app.config().identifier.clone();
 
             tauri::async_runtime::spawn(async move {
-                let addr = format!("127.0.0.1:{port}");
-                let listener = match tokio::net::TcpListener::bind(&addr).await {
-                    Ok(l) => l,
-                    Err(e) => {
-                        tracing::error!("failed to bind debug-bridge on {addr}: {e}");
-                        return;
+                let mut tcp = None;
+                if serve_tcp {
+                    let addr = format!("127.0.0.1:{port}");
+                    match tokio::net::TcpListener::bind(&addr).await {
+                        Ok(l) => tcp = Some(l),
+                        Err(e) => {
+                            diagnostics.record("server", format!("failed to bind on {addr}: {e}"))
+                        }
                     }
-                };
+                }
+                let actual_port = tcp.as_ref().map(|l| l.local_addr().unwrap().port());
+                if let Some(actual_port) = actual_port {
+                    let chosen = if port == 0 { " (picked by the OS)" } else { "" };
+                    tracing::info!(
+                        "debug-bridge listening on http://127.0.0.1:{actual_port}{chosen}"
+                    );
+                }
+
+                #[cfg(unix)]
+                let unix = socket
+                    .as_deref()
+                    .and_then(|path| match bind_unix_socket(path) {
+                        Ok(l) => {
+                            tracing::info!("debug-bridge listening on unix:{}", path.display());
+                            Some(l)
+                        }
+                        Err(e) => {
+                            diagnostics.record(
+                                "server",
+                                format!("failed to bind socket {}: {e}", path.display()),
+                            );
+                            None
+                        }
+                    });
+                #[cfg(not(unix))]
+                let unix: Option<()> = socket.as_ref().and_then(|_| {
+                    diagnostics.record(
+                        "server",
+                        "Unix sockets are not supported on this platform; serving TCP only",
+                    );
+                    None
+                });
+                let socket = unix.as_ref().and(socket);
 
-                let actual_port = listener.local_addr().unwrap().port();
-                tracing::info!("debug-bridge listening on http://127.0.0.1:{actual_port}");
+                if tcp.is_none() && socket.is_none() {
+                    return;
+                }
 
                 // Write discovery file after binding so we have the real port
                 // (important when configured port is 0 = OS-assigned).
-                if let Err(e) = write_discovery_file(&identifier, actual_port, &token) {
-                    tracing::warn!("failed to write discovery file: {e}");
+                if let Err(e) =
+                    write_discovery_file(&identifier, actual_port, socket.as_deref(), &token)
+                {
+                    diagnostics.record("discovery", format!("failed to write discovery file: {e}"));
                 } else {
                     tracing::info!("debug-bridge discovery: {DISCOVERY_DIR}/{identifier}.json");
                 }
 
-                if let Err(e) = axum::serve(listener, router).await {
-                    tracing::error!("debug-bridge server error: {e}");
+                #[cfg(feature = "mdns")]
+                let advertisement = match actual_port.filter(|_| advertise) {
+                    Some(port) => match mdns::advertise(&identifier, port) {
+                        Ok(a) => {
+                            tracing::info!("debug-bridge advertised as {}", mdns::SERVICE_TYPE);
+                            Some(a)
+                        }
+                        Err(e) => {
+                            diagnostics.record("mdns", format!("failed to advertise: {e}"));
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                #[cfg(not(feature = "mdns"))]
+                if advertise {
+                    diagnostics.record(
+                        "mdns",
+                        "advertise is set but the plugin was built without the `mdns` feature",
+                    );
+                }
+
+                if let Some(timeout) = idle_timeout {
+                    tauri::async_runtime::spawn(idle::shutdown_when_idle(idle_state, timeout));
+                }
+
+                // Both listeners stop on the one shutdown signal.
+                let (stop_tx, stop_rx) = watch::channel(());
+                tauri::async_runtime::spawn(async move {
+                    let _ = shutdown_rx.await;
+                    let _ = stop_tx.send(());
+                });
+
+                let tcp_server = async {
+                    match tcp {
+                        Some(l) => serve_until(l, router.clone(), stop_rx.clone()).await,
+                        None => Ok(()),
+                    }
+                };
+                #[cfg(unix)]
+                let unix_server = async {
+                    match unix {
+                        Some(l) => serve_until(l, router.clone(), stop_rx.clone()).await,
+                        None => Ok(()),
+                    }
+                };
+                #[cfg(not(unix))]
+                let unix_server = std::future::ready(std::io::Result::Ok(()));
+
+                let (tcp_result, unix_result) = tokio::join!(tcp_server, unix_server);
+                for e in [tcp_result, unix_result]
+                    .into_iter()
+                    .filter_map(Result::err)
+                {
+                    diagnostics.record("server", format!("server error: {e}"));
+                }
+
+                #[cfg(feature = "mdns")]
+                if let Some(advertisement) = advertisement {
+                    advertisement.stop();
+                }
+                if let Err(e) = remove_discovery_file(&identifier) {
+                    diagnostics
+                        .record("discovery", format!("failed to remove discovery file: {e}"));
+                }
+                if let Some(path) = &socket {
+                    let _ = std::fs::remove_file(path);
                 }
+                tracing::info!("debug-bridge stopped");
             });
 
             Ok(())
@@ -1,15 +1,238 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
     extract::{
-        State,
+        Query, State,
         ws::{Message, WebSocket, WebSocketUpgrade},
     },
-    response::Response,
+    response::{Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, Runtime, WebviewWindow};
+
+use crate::{
+    ApiError, BridgeState,
+    webview::{eval_with_result, get_window},
 };
-use tauri::{Manager, Runtime};
 
-use crate::BridgeState;
+/// How many console messages are kept for `/console/recent`.
+const CONSOLE_BUFFER_CAPACITY: usize = 500;
+
+/// Messages returned by `/console/recent` when `n` is omitted.
+const DEFAULT_RECENT: usize = 50;
+
+/// Console messages a `/console` stream can fall behind by before it
+/// starts dropping them, unless `console_channel_capacity` is set.
+pub const DEFAULT_CONSOLE_CHANNEL_CAPACITY: usize = 256;
+
+/// How long `/console/poll` holds a request open waiting for a message
+/// when `timeout_ms` is omitted, and the most it may ask for. Kept under
+/// the 30-60s idle timeouts common in proxies.
+const DEFAULT_POLL_TIMEOUT: Duration = Duration::from_secs(25);
+const MAX_POLL_TIMEOUT: Duration = Duration::from_secs(55);
+
+/// Ring buffer of the most recent console messages (serialized JSON),
+/// filled by `console_callback` alongside the live broadcast. Every message
+/// gets a cursor, one more than the message before it, so pollers can ask
+/// for what came after the last one they saw.
+#[derive(Clone)]
+pub struct ConsoleBuffer(Arc<std::sync::Mutex<Ring>>);
+
+struct Ring {
+    messages: VecDeque<String>,
+    /// Cursor the next message will get.
+    next: u64,
+}
+
+impl Default for ConsoleBuffer {
+    fn default() -> Self {
+        Self(Arc::new(std::sync::Mutex::new(Ring {
+            messages: VecDeque::with_capacity(CONSOLE_BUFFER_CAPACITY),
+            next: 0,
+        })))
+    }
+}
+
+/// A `/console/poll` answer.
+#[derive(Debug, Serialize)]
+pub struct Poll {
+    /// Pass back as `since` to get the messages after these.
+    pub cursor: u64,
+    /// Messages that fell out of the buffer before this poll could see them.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub dropped: u64,
+    pub messages: Vec<serde_json::Value>,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+impl ConsoleBuffer {
+    pub fn push(&self, message: String) {
+        let mut ring = self.0.lock().unwrap();
+        if ring.messages.len() == CONSOLE_BUFFER_CAPACITY {
+            ring.messages.pop_front();
+        }
+        ring.messages.push_back(message);
+        ring.next += 1;
+    }
+
+    /// The cursor of the next message to arrive.
+    pub fn head(&self) -> u64 {
+        self.0.lock().unwrap().next
+    }
+
+    /// Messages from windows matching `filter` with a cursor of `since` or
+    /// later. A `since` ahead of the buffer (from before a restart) starts
+    /// over at the head.
+    pub fn since(&self, since: u64, filter: &WindowFilter) -> Poll {
+        let ring = self.0.lock().unwrap();
+        let oldest = ring.next - ring.messages.len() as u64;
+        let since = if since > ring.next { ring.next } else { since };
+        let skip = since.saturating_sub(oldest) as usize;
+        Poll {
+            cursor: ring.next,
+            dropped: oldest.saturating_sub(since),
+            messages: ring
+                .messages
+                .iter()
+                .skip(skip)
+                .filter_map(|m| serde_json::from_str(m).ok())
+                .filter(|m| filter.matches(m))
+                .collect(),
+        }
+    }
+
+    /// The last `n` messages from windows matching `filter`, oldest first.
+    pub fn recent(&self, n: usize, filter: &WindowFilter) -> Vec<serde_json::Value> {
+        let ring = self.0.lock().unwrap();
+        let mut recent: Vec<serde_json::Value> = ring
+            .messages
+            .iter()
+            .rev()
+            .filter_map(|m| serde_json::from_str(m).ok())
+            .filter(|m| filter.matches(m))
+            .take(n)
+            .collect();
+        recent.reverse();
+        recent
+    }
+
+    /// Buffered messages from `window` logged at `level`.
+    pub fn count_level(&self, window: &str, level: &str) -> usize {
+        let filter = WindowFilter::Only(window.to_string());
+        self.0
+            .lock()
+            .unwrap()
+            .messages
+            .iter()
+            .filter_map(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+            .filter(|m| m["level"] == level && filter.matches(m))
+            .count()
+    }
+}
+
+/// Which windows' console messages a consumer wants.
+pub enum WindowFilter {
+    All,
+    Only(String),
+}
+
+impl WindowFilter {
+    /// `?window=<label>` keeps that window's messages; `*` or no window
+    /// keeps every hooked window's.
+    fn from_query(window: Option<&str>) -> Self {
+        match window {
+            None | Some("*") => Self::All,
+            Some(label) => Self::Only(label.to_string()),
+        }
+    }
+
+    fn matches(&self, message: &serde_json::Value) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(label) => message["window"] == label.as_str(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RecentQuery {
+    /// Number of messages to return. Defaults to 50.
+    pub n: Option<usize>,
+    /// Window to hook, as for `/console`.
+    pub window: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PollQuery {
+    /// The `cursor` from the previous poll. Without it, only messages logged
+    /// from now on are returned.
+    pub since: Option<u64>,
+    /// Window to hook, as for `/console`.
+    pub window: Option<String>,
+    /// How long to wait for a message before answering with none. Defaults
+    /// to 25s; capped at 55s.
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct ConsoleQuery {
+    /// Window to hook and stream: a label, or `*` for all windows. Without
+    /// it, the default window is hooked and every hooked window's messages
+    /// are streamed.
+    pub window: Option<String>,
+}
+
+/// Labels of windows whose console is being captured. The hook stays
+/// installed across navigations: a reload or navigation wipes the `console`
+/// overrides (and the hook's guard flag with them), so it is re-injected
+/// whenever a hooked window finishes loading a page. SPA route changes
+/// (`pushState`, hash changes) keep the page and its hook intact.
+#[derive(Clone, Default)]
+pub struct ConsoleHooks(Arc<std::sync::Mutex<HashSet<String>>>);
+
+impl ConsoleHooks {
+    /// Mark a window as hooked. Returns false if it already was.
+    fn insert(&self, label: &str) -> bool {
+        self.0.lock().unwrap().insert(label.to_string())
+    }
+
+    fn forget(&self, label: &str) {
+        self.0.lock().unwrap().remove(label);
+    }
+
+    pub(crate) fn is_hooked(&self, label: &str) -> bool {
+        self.0.lock().unwrap().contains(label)
+    }
+
+    /// Called when a webview finishes loading a page: re-install the hook if
+    /// its window is being captured.
+    pub fn page_loaded<R: Runtime>(&self, webview: &tauri::Webview<R>) {
+        self.rehook(webview.label(), |js| webview.eval(js));
+    }
+
+    /// Run the hook script through `eval` if `label` is hooked. Returns
+    /// whether it did.
+    fn rehook<E: std::fmt::Display>(
+        &self,
+        label: &str,
+        eval: impl FnOnce(&str) -> Result<(), E>,
+    ) -> bool {
+        if !self.is_hooked(label) {
+            return false;
+        }
+        if let Err(e) = eval(CONSOLE_HOOK_JS) {
+            tracing::warn!("debug-bridge: re-injecting console hook into '{label}' failed: {e}");
+        }
+        true
+    }
+}
 
 /// JavaScript that hooks console.log/warn/error/info and forwards messages
 /// to the debug bridge plugin via `__TAURI_INTERNALS__.invoke`.
@@ -19,17 +242,31 @@ const CONSOLE_HOOK_JS: &str = r#"
     if (window.__debugBridgeConsoleHooked) return;
     window.__debugBridgeConsoleHooked = true;
 
+    // Convert one console argument to a JSON-safe value, keeping structure
+    // where possible. Errors keep their stack; values JSON can't represent
+    // (functions, symbols, cyclic objects, undefined) fall back to strings.
+    function serialize(a) {
+        if (a instanceof Error) {
+            return { name: a.name, message: a.message, stack: a.stack };
+        }
+        if (a === undefined || typeof a === 'function' || typeof a === 'symbol' || typeof a === 'bigint') {
+            return String(a);
+        }
+        try {
+            const json = JSON.stringify(a);
+            return json === undefined ? String(a) : JSON.parse(json);
+        } catch {
+            return String(a);
+        }
+    }
+
     function hook(level, origFn) {
         return function(...args) {
             origFn.apply(console, args);
             try {
-                const parts = args.map(a => {
-                    try { return typeof a === 'string' ? a : JSON.stringify(a); }
-                    catch { return String(a); }
-                });
                 window.__TAURI_INTERNALS__.invoke(
                     'plugin:debug-bridge|console_callback',
-                    { level: level, message: parts.join(' ') }
+                    { level: level, args: args.map(serialize) }
                 );
             } catch(e) {}
         };
@@ -45,13 +282,13 @@ const CONSOLE_HOOK_JS: &str = r#"
     window.addEventListener('error', function(e) {
         window.__TAURI_INTERNALS__.invoke(
             'plugin:debug-bridge|console_callback',
-            { level: 'error', message: e.message + ' at ' + e.filename + ':' + e.lineno }
+            { level: 'error', args: [e.message + ' at ' + e.filename + ':' + e.lineno] }
         );
     });
     window.addEventListener('unhandledrejection', function(e) {
         window.__TAURI_INTERNALS__.invoke(
             'plugin:debug-bridge|console_callback',
-            { level: 'error', message: 'Unhandled rejection: ' + String(e.reason) }
+            { level: 'error', args: ['Unhandled rejection:', serialize(e.reason)] }
         );
     });
 })();
@@ -59,10 +296,15 @@ const CONSOLE_HOOK_JS: &str = r#"
 
 /// GET /logs — WebSocket endpoint for streaming Rust-side logs.
 pub async fn logs_ws<R: Runtime>(
-    State(_state): State<Arc<BridgeState<R>>>,
+    State(state): State<Arc<BridgeState<R>>>,
     ws: WebSocketUpgrade,
 ) -> Response {
-    ws.on_upgrade(handle_logs)
+    let stream = state.activity.stream();
+    ws.protocols([crate::WS_PROTOCOL])
+        .on_upgrade(move |socket| async move {
+            let _stream = stream;
+            handle_logs(socket).await
+        })
 }
 
 async fn handle_logs(mut socket: WebSocket) {
@@ -87,46 +329,221 @@ async fn handle_logs(mut socket: WebSocket) {
     }
 }
 
+/// Build the broadcast message for one console call: the source window's
+/// label, the structured `args`, and a space-joined `message` for plain-text
+/// display (strings verbatim, everything else as compact JSON).
+pub fn console_message(
+    window: &str,
+    level: &str,
+    args: Vec<serde_json::Value>,
+) -> serde_json::Value {
+    let message = args
+        .iter()
+        .map(|a| match a {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    serde_json::json!({
+        "window": window,
+        "level": level,
+        "message": message,
+        "args": args,
+    })
+}
+
+/// Inject the console hook into a window (the default window when `None`),
+/// or every window for `*`, skipping windows already hooked. Messages are only captured
+/// (streamed and buffered) once the hook is installed. Returns the windows
+/// targeted, hooked or not.
+fn inject_console_hook<R: Runtime>(
+    state: &BridgeState<R>,
+    window: Option<&str>,
+) -> Result<Vec<WebviewWindow<R>>, ApiError> {
+    let windows = match window {
+        Some("*") => state.app.webview_windows().into_values().collect(),
+        label => vec![get_window(state, label)?],
+    };
+    for window in &windows {
+        if !state.console_hooks.insert(window.label()) {
+            continue;
+        }
+        if let Err(e) = window.eval(CONSOLE_HOOK_JS) {
+            state.console_hooks.forget(window.label());
+            state.diagnostics.record(
+                "console",
+                format!(
+                    "hook injection into window '{}' failed: {e}",
+                    window.label()
+                ),
+            );
+        }
+    }
+    Ok(windows)
+}
+
+/// Ask each window whether its console hook is in place. `eval` rejected
+/// by a CSP, or a callback that never arrives, counts as not installed.
+/// Windows that fail are no longer marked hooked, so the next `/console`
+/// connection injects the hook again instead of skipping them.
+async fn probe_console_hooks<R: Runtime>(
+    state: &BridgeState<R>,
+    windows: &[WebviewWindow<R>],
+) -> BTreeMap<String, Result<(), String>> {
+    let mut results = BTreeMap::new();
+    for window in windows {
+        let result = match eval_with_result(state, window, CONSOLE_HOOK_PROBE_JS).await {
+            Ok(r) if r.success && r.value == Some(serde_json::Value::Bool(true)) => Ok(()),
+            Ok(r) if r.success => Err("hook not present after injection".to_string()),
+            Ok(r) => Err(r.error.unwrap_or_else(|| "probe failed".to_string())),
+            Err(e) => Err(e.message),
+        };
+        if let Err(e) = &result {
+            state.console_hooks.forget(window.label());
+            state.diagnostics.record(
+                "console",
+                format!("hook probe in window '{}' failed: {e}", window.label()),
+            );
+        }
+        results.insert(window.label().to_string(), result);
+    }
+    results
+}
+
+/// Whether the console hook took in the page.
+const CONSOLE_HOOK_PROBE_JS: &str = "window.__debugBridgeConsoleHooked === true";
+
+/// The first frame of a `/console` stream: which windows are hooked, which
+/// aren't and why, and how long the probe took.
+fn hook_status(
+    probes: &BTreeMap<String, Result<(), String>>,
+    elapsed: Duration,
+) -> serde_json::Value {
+    let hooked: Vec<&str> = probes
+        .iter()
+        .filter(|(_, r)| r.is_ok())
+        .map(|(label, _)| label.as_str())
+        .collect();
+    let failed: BTreeMap<&str, &str> = probes
+        .iter()
+        .filter_map(|(label, r)| Some((label.as_str(), r.as_ref().err()?.as_str())))
+        .collect();
+    let (level, message) = if failed.is_empty() {
+        ("info", "console streaming connected".to_string())
+    } else {
+        let reasons: Vec<String> = failed
+            .iter()
+            .map(|(label, e)| format!("'{label}': {e}"))
+            .collect();
+        (
+            "error",
+            format!(
+                "console hook not installed in {}; no output will be captured from {}",
+                reasons.join(", "),
+                if failed.len() == 1 { "it" } else { "them" }
+            ),
+        )
+    };
+    serde_json::json!({
+        "level": level,
+        "message": message,
+        "hooked": hooked,
+        "failed": failed,
+        "probe_ms": elapsed.as_secs_f64() * 1000.0,
+    })
+}
+
+/// GET /console/recent — the last `?n=` buffered console messages as JSON,
+/// filtered by `?window=` like `/console`. Also installs the console hook,
+/// so the buffer fills from then on.
+pub async fn console_recent<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<RecentQuery>,
+) -> Result<Json<Vec<serde_json::Value>>, ApiError> {
+    inject_console_hook(&state, query.window.as_deref())?;
+    Ok(Json(state.console_buffer.recent(
+        query.n.unwrap_or(DEFAULT_RECENT),
+        &WindowFilter::from_query(query.window.as_deref()),
+    )))
+}
+
+/// GET /console/poll — long-poll fallback for `/console`, for networks that
+/// refuse WebSocket upgrades. Hooks `?window=` like `/console`, then answers
+/// as soon as there are messages after `since`, or with none once
+/// `timeout_ms` passes. Each answer carries the cursor for the next poll.
+pub async fn console_poll<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<PollQuery>,
+) -> Result<Json<Poll>, ApiError> {
+    inject_console_hook(&state, query.window.as_deref())?;
+    let filter = WindowFilter::from_query(query.window.as_deref());
+    let timeout = query
+        .timeout_ms
+        .map_or(DEFAULT_POLL_TIMEOUT, Duration::from_millis)
+        .min(MAX_POLL_TIMEOUT);
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    // Subscribe before reading the buffer so nothing logged in between is
+    // slept through; `console_callback` buffers a message before sending it.
+    let mut rx = state.console_tx.subscribe();
+    let mut since = query.since.unwrap_or_else(|| state.console_buffer.head());
+    loop {
+        let poll = state.console_buffer.since(since, &filter);
+        if !poll.messages.is_empty() || poll.dropped > 0 {
+            return Ok(Json(poll));
+        }
+        since = poll.cursor;
+        match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Ok(Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_))) => {}
+            Ok(Err(tokio::sync::broadcast::error::RecvError::Closed)) | Err(_) => {
+                return Ok(Json(poll));
+            }
+        }
+    }
+}
+
 /// GET /console — WebSocket endpoint for streaming JS console output.
-/// Injects a console hook into the webview on first connection, then
-/// streams all console.log/warn/error/info messages to the client.
+/// Injects a console hook into `?window=` (the default window, `*` for all
+/// windows), then streams console.log/warn/error/info messages to the
+/// client: only that window's when a label is given.
 pub async fn console_ws<R: Runtime>(
     State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<ConsoleQuery>,
     ws: WebSocketUpgrade,
-) -> Response {
-    let app = state.app.clone();
-    let console_tx = state.console_tx.clone();
-    ws.on_upgrade(move |socket| handle_console(socket, app, console_tx))
+) -> Result<Response, ApiError> {
+    let windows = inject_console_hook(&state, query.window.as_deref())?;
+    let filter = WindowFilter::from_query(query.window.as_deref());
+    let stream = state.activity.stream();
+    Ok(ws
+        .protocols([crate::WS_PROTOCOL])
+        .on_upgrade(move |socket| async move {
+            let _stream = stream;
+            handle_console(socket, state, windows, filter).await
+        }))
 }
 
 async fn handle_console<R: Runtime>(
     mut socket: WebSocket,
-    app: tauri::AppHandle<R>,
-    console_tx: tokio::sync::broadcast::Sender<String>,
+    state: Arc<BridgeState<R>>,
+    windows: Vec<WebviewWindow<R>>,
+    filter: WindowFilter,
 ) {
-    // Inject the console hook into the main webview.
-    if let Some(window) = app.get_webview_window("main") {
-        let _ = window.eval(CONSOLE_HOOK_JS);
-    }
+    // Subscribe before probing so nothing logged meanwhile is missed.
+    let mut rx = state.console_tx.subscribe();
 
-    // Subscribe to the console broadcast channel.
-    let mut rx = console_tx.subscribe();
-
-    let _ = socket
-        .send(Message::Text(
-            serde_json::json!({
-                "level": "info",
-                "message": "console streaming connected"
-            })
-            .to_string()
-            .into(),
-        ))
-        .await;
+    let started = Instant::now();
+    let probes = probe_console_hooks(&state, &windows).await;
+    let status = hook_status(&probes, started.elapsed());
+    let _ = socket.send(Message::Text(status.to_string().into())).await;
 
     // Forward console messages to the WebSocket client.
     loop {
         tokio::select! {
-            Ok(msg) = rx.recv() => {
+            frame = next_console_frame(&mut rx, &filter) => {
+                let Some(msg) = frame else {
+                    break;
+                };
                 if socket.send(Message::Text(msg.into())).await.is_err() {
                     break;
                 }
@@ -140,3 +557,246 @@ async fn handle_console<R: Runtime>(
         }
     }
 }
+
+/// The next message for a `/console` client, or `None` once the channel
+/// closes. A client that fell too far behind gets a warning counting the
+/// messages it missed (from any window) instead of a silent gap.
+async fn next_console_frame(
+    rx: &mut tokio::sync::broadcast::Receiver<String>,
+    filter: &WindowFilter,
+) -> Option<String> {
+    use tokio::sync::broadcast::error::RecvError;
+    loop {
+        match rx.recv().await {
+            Ok(msg) => {
+                if let WindowFilter::Only(_) = filter {
+                    let keep = serde_json::from_str(&msg).is_ok_and(|m| filter.matches(&m));
+                    if !keep {
+                        continue;
+                    }
+                }
+                return Some(msg);
+            }
+            Err(RecvError::Lagged(dropped)) => {
+                return Some(
+                    serde_json::json!({
+                        "level": "warn",
+                        "message": format!("{dropped} console messages dropped (slow consumer)"),
+                        "dropped": dropped,
+                    })
+                    .to_string(),
+                );
+            }
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn console_message_keeps_args_and_joined_text() {
+        let msg = console_message(
+            "main",
+            "log",
+            vec![
+                serde_json::json!("user"),
+                serde_json::json!({ "id": 1 }),
+                serde_json::json!(42),
+            ],
+        );
+        assert_eq!(msg["window"], "main");
+        assert_eq!(msg["level"], "log");
+        assert_eq!(msg["message"], r#"user {"id":1} 42"#);
+        assert_eq!(msg["args"][1]["id"], 1);
+        assert_eq!(msg["args"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn hook_status_names_windows_that_were_not_hooked() {
+        let mut probes = BTreeMap::new();
+        probes.insert("main".to_string(), Ok(()));
+        let ok = hook_status(&probes, Duration::from_millis(4));
+        assert_eq!(ok["level"], "info");
+        assert_eq!(ok["hooked"], serde_json::json!(["main"]));
+        assert_eq!(ok["failed"], serde_json::json!({}));
+        assert_eq!(ok["probe_ms"], 4.0);
+
+        probes.insert(
+            "settings".to_string(),
+            Err("eval timed out after 10s".to_string()),
+        );
+        let partial = hook_status(&probes, Duration::from_millis(4));
+        assert_eq!(partial["level"], "error");
+        assert_eq!(partial["hooked"], serde_json::json!(["main"]));
+        assert_eq!(partial["failed"]["settings"], "eval timed out after 10s");
+        assert!(
+            partial["message"]
+                .as_str()
+                .unwrap()
+                .contains("'settings': eval timed out after 10s")
+        );
+    }
+
+    #[test]
+    fn console_hooks_track_hooked_windows() {
+        let hooks = ConsoleHooks::default();
+        assert!(hooks.insert("main"));
+        assert!(
+            !hooks.insert("main"),
+            "second hook of the same window is skipped"
+        );
+        hooks.forget("main");
+        assert!(hooks.insert("main"), "a failed injection can be retried");
+    }
+
+    #[test]
+    fn hooked_windows_survive_reloads() {
+        let hooks = ConsoleHooks::default();
+        hooks.insert("main");
+        let injected = std::cell::RefCell::new(Vec::new());
+        let eval = |label: &'static str| {
+            let injected = &injected;
+            move |js: &str| {
+                injected.borrow_mut().push((label, js.to_string()));
+                Ok::<_, String>(())
+            }
+        };
+
+        // A reload finishing in the hooked window re-injects the hook;
+        // other windows are left alone.
+        assert!(hooks.rehook("main", eval("main")));
+        assert!(!hooks.rehook("settings", eval("settings")));
+        assert_eq!(
+            *injected.borrow(),
+            vec![("main", CONSOLE_HOOK_JS.to_string())]
+        );
+
+        // A failed re-injection is logged, and the window stays hooked so
+        // the next load tries again.
+        assert!(hooks.rehook("main", |_| Err("webview gone")));
+        assert!(hooks.rehook("main", eval("main")));
+        assert_eq!(injected.borrow().len(), 2);
+
+        // Hooking again after the reload is still a no-op.
+        assert!(!hooks.insert("main"));
+    }
+
+    #[test]
+    fn console_buffer_counts_by_level() {
+        let buffer = ConsoleBuffer::default();
+        for level in ["log", "error", "warn", "error"] {
+            buffer.push(console_message("main", level, vec![]).to_string());
+        }
+        buffer.push(console_message("settings", "error", vec![]).to_string());
+        assert_eq!(buffer.count_level("main", "error"), 2);
+        assert_eq!(buffer.count_level("main", "info"), 0);
+        assert_eq!(buffer.count_level("settings", "error"), 1);
+    }
+
+    #[test]
+    fn console_messages_filter_by_window() {
+        let buffer = ConsoleBuffer::default();
+        for (i, window) in ["main", "settings", "main", "settings"].iter().enumerate() {
+            buffer.push(console_message(window, "log", vec![serde_json::json!(i)]).to_string());
+        }
+
+        let settings = buffer.recent(10, &WindowFilter::from_query(Some("settings")));
+        let messages: Vec<_> = settings.iter().map(|m| m["message"].clone()).collect();
+        assert_eq!(messages, ["1", "3"]);
+
+        let last = buffer.recent(1, &WindowFilter::from_query(Some("main")));
+        assert_eq!(last[0]["message"], "2");
+
+        assert_eq!(
+            buffer
+                .recent(10, &WindowFilter::from_query(Some("*")))
+                .len(),
+            4
+        );
+        assert_eq!(buffer.recent(10, &WindowFilter::from_query(None)).len(), 4);
+    }
+
+    #[test]
+    fn polls_resume_from_their_cursor() {
+        let buffer = ConsoleBuffer::default();
+        let all = WindowFilter::All;
+        assert_eq!(buffer.head(), 0);
+        for i in 0..3 {
+            buffer.push(console_message("main", "log", vec![serde_json::json!(i)]).to_string());
+        }
+        let first = buffer.since(0, &all);
+        assert_eq!(first.cursor, 3);
+        assert_eq!(first.messages.len(), 3);
+
+        buffer.push(console_message("settings", "log", vec![serde_json::json!(3)]).to_string());
+        let next = buffer.since(first.cursor, &all);
+        assert_eq!(next.cursor, 4);
+        assert_eq!(next.messages[0]["message"], "3");
+        let main_only = buffer.since(first.cursor, &WindowFilter::Only("main".into()));
+        assert_eq!(
+            main_only.cursor, 4,
+            "filtered messages still move the cursor"
+        );
+        assert!(main_only.messages.is_empty());
+
+        // A cursor from before a restart starts over at the head.
+        assert_eq!(buffer.since(99, &all).cursor, 4);
+        assert!(buffer.since(99, &all).messages.is_empty());
+
+        // A poller that fell behind the ring hears how much it missed.
+        for i in 0..CONSOLE_BUFFER_CAPACITY + 2 {
+            buffer.push(console_message("main", "log", vec![serde_json::json!(i)]).to_string());
+        }
+        let behind = buffer.since(next.cursor, &all);
+        assert_eq!(behind.dropped, 2);
+        assert_eq!(behind.messages.len(), CONSOLE_BUFFER_CAPACITY);
+    }
+
+    #[tokio::test]
+    async fn lagging_streams_are_told_what_they_missed() {
+        let (tx, mut rx) = tokio::sync::broadcast::channel(2);
+        for i in 0..5 {
+            tx.send(console_message("main", "log", vec![serde_json::json!(i)]).to_string())
+                .unwrap();
+        }
+
+        let filter = WindowFilter::from_query(Some("main"));
+        let frame = next_console_frame(&mut rx, &filter).await.unwrap();
+        let warning: serde_json::Value = serde_json::from_str(&frame).unwrap();
+        assert_eq!(warning["level"], "warn");
+        assert_eq!(warning["dropped"], 3);
+        assert_eq!(
+            warning["message"],
+            "3 console messages dropped (slow consumer)"
+        );
+
+        // The stream picks up with the oldest message still buffered.
+        let frame = next_console_frame(&mut rx, &filter).await.unwrap();
+        let next: serde_json::Value = serde_json::from_str(&frame).unwrap();
+        assert_eq!(next["message"], "3");
+
+        drop(tx);
+        let _ = next_console_frame(&mut rx, &filter).await;
+        assert_eq!(next_console_frame(&mut rx, &filter).await, None);
+    }
+
+    #[test]
+    fn console_buffer_keeps_the_latest_messages() {
+        let buffer = ConsoleBuffer::default();
+        for i in 0..CONSOLE_BUFFER_CAPACITY + 3 {
+            buffer.push(serde_json::json!({ "level": "log", "message": i }).to_string());
+        }
+
+        let recent = buffer.recent(2, &WindowFilter::All);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0]["message"], CONSOLE_BUFFER_CAPACITY + 1);
+        assert_eq!(recent[1]["message"], CONSOLE_BUFFER_CAPACITY + 2);
+
+        let all = buffer.recent(usize::MAX, &WindowFilter::All);
+        assert_eq!(all.len(), CONSOLE_BUFFER_CAPACITY);
+        assert_eq!(all[0]["message"], 3);
+    }
+}
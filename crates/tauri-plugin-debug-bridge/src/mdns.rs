@@ -0,0 +1,70 @@
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+
+/// DNS-SD service type the bridge registers under; `tauri-browser discover`
+/// browses for it.
+pub const SERVICE_TYPE: &str = "_tauri-debug._tcp.local.";
+
+/// A registered mDNS service, withdrawn by [`Advertisement::stop`].
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+/// Announce the bridge on `port` for the app `identifier`. The auth token is
+/// not advertised: finding a bridge doesn't grant access to it.
+pub fn advertise(identifier: &str, port: u16) -> Result<Advertisement, mdns_sd::Error> {
+    let daemon = ServiceDaemon::new()?;
+    let info = service_info(identifier, port)?;
+    let fullname = info.get_fullname().to_string();
+    daemon.register(info)?;
+    Ok(Advertisement { daemon, fullname })
+}
+
+impl Advertisement {
+    /// Send the goodbye packet and stop the daemon.
+    pub fn stop(self) {
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// The service record: one instance per app, named after its identifier,
+/// on every address of this host.
+fn service_info(identifier: &str, port: u16) -> Result<ServiceInfo, mdns_sd::Error> {
+    // Dots would read as label separators in the instance and host names.
+    let instance = identifier.replace('.', "-");
+    let properties = [
+        ("identifier", identifier),
+        ("version", env!("CARGO_PKG_VERSION")),
+    ];
+    ServiceInfo::new(
+        SERVICE_TYPE,
+        &instance,
+        &format!("{instance}.local."),
+        (),
+        port,
+        &properties[..],
+    )
+    .map(ServiceInfo::enable_addr_auto)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_advertises_port_and_identifier() {
+        let info = service_info("com.example.app", 9229).unwrap();
+        assert_eq!(
+            info.get_fullname(),
+            "com-example-app._tauri-debug._tcp.local."
+        );
+        assert_eq!(info.get_port(), 9229);
+        assert_eq!(
+            info.get_property_val_str("identifier"),
+            Some("com.example.app")
+        );
+        assert_eq!(info.get_property_val_str("token"), None);
+        assert!(info.is_addr_auto());
+    }
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use tauri::{
+    Emitter, Runtime,
+    menu::{Menu, MenuItemKind},
+};
+
+use crate::{ApiError, BridgeState};
+
+/// Event emitted by `POST /menu/click`. Tauri has no API to fire a native menu
+/// event programmatically, so apps that want bridge clicks to reach their
+/// menu handler listen for this event and forward the id.
+pub const MENU_CLICK_EVENT: &str = "debug-bridge://menu-click";
+
+#[derive(Deserialize)]
+pub struct MenuQuery {
+    /// Window whose menu to read. Defaults to the app-wide menu.
+    pub window: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MenuClickRequest {
+    pub id: String,
+    pub window: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MenuItemInfo {
+    pub id: String,
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checked: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<MenuItemInfo>,
+}
+
+#[derive(Serialize)]
+pub struct MenuClickResponse {
+    pub success: bool,
+    pub event: &'static str,
+    pub item: MenuItemInfo,
+}
+
+fn find_menu<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    window: Option<&str>,
+) -> Result<Menu<R>, ApiError> {
+    match window {
+        Some(label) => {
+            let window = crate::webview::find_window(app, label)?;
+            window.menu().ok_or_else(|| {
+                ApiError::new(
+                    StatusCode::NOT_FOUND,
+                    format!("no menu set on window '{label}'"),
+                )
+            })
+        }
+        None => app.menu().ok_or_else(|| {
+            ApiError::new(StatusCode::NOT_FOUND, "no menu set on the app".to_string())
+        }),
+    }
+}
+
+fn describe<R: Runtime>(item: &MenuItemKind<R>) -> MenuItemInfo {
+    let id = item.id().0.clone();
+    match item {
+        MenuItemKind::MenuItem(i) => MenuItemInfo {
+            id,
+            kind: "item",
+            text: i.text().ok(),
+            enabled: i.is_enabled().ok(),
+            checked: None,
+            items: vec![],
+        },
+        MenuItemKind::Submenu(i) => MenuItemInfo {
+            id,
+            kind: "submenu",
+            text: i.text().ok(),
+            enabled: i.is_enabled().ok(),
+            checked: None,
+            items: i.items().unwrap_or_default().iter().map(describe).collect(),
+        },
+        MenuItemKind::Predefined(i) => MenuItemInfo {
+            id,
+            kind: "predefined",
+            text: i.text().ok(),
+            enabled: None,
+            checked: None,
+            items: vec![],
+        },
+        MenuItemKind::Check(i) => MenuItemInfo {
+            id,
+            kind: "check",
+            text: i.text().ok(),
+            enabled: i.is_enabled().ok(),
+            checked: i.is_checked().ok(),
+            items: vec![],
+        },
+        MenuItemKind::Icon(i) => MenuItemInfo {
+            id,
+            kind: "icon",
+            text: i.text().ok(),
+            enabled: i.is_enabled().ok(),
+            checked: None,
+            items: vec![],
+        },
+    }
+}
+
+/// Depth-first search for a menu item by id, descending into submenus.
+fn find_item<R: Runtime>(items: Vec<MenuItemKind<R>>, id: &str) -> Option<MenuItemKind<R>> {
+    for item in items {
+        if item.id() == id {
+            return Some(item);
+        }
+        if let MenuItemKind::Submenu(sub) = &item
+            && let Some(found) = find_item(sub.items().unwrap_or_default(), id)
+        {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// GET /menu — dump the app (or `?window=`) menu structure.
+pub async fn list<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<MenuQuery>,
+) -> Result<Json<Vec<MenuItemInfo>>, ApiError> {
+    let menu = find_menu(&state.app, query.window.as_deref())?;
+    let items = menu
+        .items()
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(items.iter().map(describe).collect()))
+}
+
+/// POST /menu/click — trigger a menu item by id.
+/// Check items are toggled natively. Every click is then emitted as
+/// [`MENU_CLICK_EVENT`] with `{ "id": ... }` for the app to route to its
+/// menu handler.
+pub async fn click<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Json(req): Json<MenuClickRequest>,
+) -> Result<Json<MenuClickResponse>, ApiError> {
+    let menu = find_menu(&state.app, req.window.as_deref())?;
+    let items = menu
+        .items()
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let item = find_item(items, &req.id).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("menu item '{}' not found", req.id),
+        )
+    })?;
+
+    if describe(&item).enabled == Some(false) {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("menu item '{}' is disabled", req.id),
+        ));
+    }
+
+    if let MenuItemKind::Check(check) = &item {
+        let checked = check.is_checked().unwrap_or(false);
+        check
+            .set_checked(!checked)
+            .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    state
+        .app
+        .emit(MENU_CLICK_EVENT, serde_json::json!({ "id": req.id }))
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(MenuClickResponse {
+        success: true,
+        event: MENU_CLICK_EVENT,
+        item: describe(&item),
+    }))
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use tauri::Runtime;
+
+use crate::{ApiError, BridgeState};
+
+#[derive(Deserialize)]
+pub struct StoreQuery {
+    /// Store path as the app passes it to `tauri-plugin-store`, e.g. "settings.json".
+    pub path: String,
+    /// Window whose webview talks to the store plugin. Defaults to the
+    /// configured default window.
+    pub window: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct StoreContents {
+    pub path: String,
+    pub entries: serde_json::Value,
+}
+
+/// Reads a store through the store plugin's IPC commands. Reuses the store if
+/// the app already loaded it, so unsaved in-memory values are included.
+const STORE_ENTRIES_JS: &str = r#"
+const invoke = window.__TAURI_INTERNALS__.invoke;
+const path = __PATH__;
+let rid = await invoke('plugin:store|get_store', { path });
+if (rid == null) {
+    rid = await invoke('plugin:store|load', { path });
+}
+return Object.fromEntries(await invoke('plugin:store|entries', { rid }));
+"#;
+
+/// GET /store?path=<store> — contents of a `tauri-plugin-store` store.
+///
+/// The bridge talks to the store plugin over IPC rather than linking it, so
+/// it works with whichever store plugin version the app registered. The
+/// window needs the store plugin's permissions (e.g. `store:default`).
+pub async fn store<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<StoreQuery>,
+) -> Result<Json<StoreContents>, ApiError> {
+    if query.path.trim().is_empty() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "store path must not be empty".to_string(),
+        ));
+    }
+    let window = crate::webview::get_window(&state, query.window.as_deref())?;
+    let js = STORE_ENTRIES_JS.replace("__PATH__", &serde_json::to_string(&query.path).unwrap());
+    let result = crate::webview::eval_with_result(&state, &window, &js).await?;
+
+    if !result.success {
+        let error = result.error.unwrap_or_default();
+        return Err(store_error(&error));
+    }
+    Ok(Json(StoreContents {
+        path: query.path,
+        entries: result.value.unwrap_or_default(),
+    }))
+}
+
+/// Map an error thrown by the store plugin's commands to a response.
+fn store_error(error: &str) -> ApiError {
+    if error.contains("plugin store not found") {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "tauri-plugin-store is not registered in this app".to_string(),
+        )
+    } else if error.contains("not allowed") {
+        ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!("{error} (grant store:default to the window's capability)"),
+        )
+    } else {
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_errors_are_classified() {
+        let (status, msg) = store_error("plugin store not found");
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(msg.contains("not registered"));
+
+        let (status, msg) = store_error(
+            "store.load not allowed. Permissions associated with this command: store:allow-load",
+        );
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(msg.ends_with("(grant store:default to the window's capability)"));
+
+        let (status, _) = store_error("failed to deserialize store");
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
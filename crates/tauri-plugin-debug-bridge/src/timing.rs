@@ -0,0 +1,93 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    http::{HeaderValue, Request, header::HeaderName},
+    middleware::Next,
+    response::Response,
+};
+
+tokio::task_local! {
+    /// Script round trips of the request being timed.
+    static EVALS: Mutex<Vec<Duration>>;
+}
+
+/// Record one injected script's round trip, from `window.eval` to its
+/// callback, if the current request asked for timing.
+pub(crate) fn record_eval(elapsed: Duration) {
+    let _ = EVALS.try_with(|evals| evals.lock().unwrap().push(elapsed));
+}
+
+/// Middleware for `?timing=true`: answers with a `Server-Timing` header
+/// listing each eval's round trip and the handler's total time, so clients
+/// can tell time spent in the webview from time spent in transit.
+pub async fn server_timing(req: Request<axum::body::Body>, next: Next) -> Response {
+    if !timing_requested(req.uri().query()) {
+        return next.run(req).await;
+    }
+    let start = Instant::now();
+    let (evals, mut response) = EVALS
+        .scope(Mutex::new(Vec::new()), async {
+            let response = next.run(req).await;
+            (
+                EVALS.with(|e| std::mem::take(&mut *e.lock().unwrap())),
+                response,
+            )
+        })
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&header_value(&evals, start.elapsed())) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static("server-timing"), value);
+    }
+    response
+}
+
+fn timing_requested(query: Option<&str>) -> bool {
+    query
+        .unwrap_or_default()
+        .split('&')
+        .any(|pair| pair == "timing=true")
+}
+
+/// `eval;dur=12.3, eval;dur=4.0, total;dur=18.9`, durations in ms.
+fn header_value(evals: &[Duration], total: Duration) -> String {
+    let ms = |d: &Duration| d.as_secs_f64() * 1000.0;
+    evals
+        .iter()
+        .map(|d| format!("eval;dur={:.1}", ms(d)))
+        .chain([format!("total;dur={:.1}", ms(&total))])
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_lists_each_eval_then_the_total() {
+        let evals = [Duration::from_micros(12_340), Duration::from_millis(4)];
+        assert_eq!(
+            header_value(&evals, Duration::from_micros(18_860)),
+            "eval;dur=12.3, eval;dur=4.0, total;dur=18.9"
+        );
+        assert_eq!(header_value(&[], Duration::from_millis(2)), "total;dur=2.0");
+        assert!(timing_requested(Some("window=main&timing=true")));
+        assert!(!timing_requested(Some("timing=false")));
+    }
+
+    #[tokio::test]
+    async fn evals_are_recorded_only_while_timing() {
+        record_eval(Duration::from_millis(1));
+        let recorded = EVALS
+            .scope(Mutex::new(Vec::new()), async {
+                record_eval(Duration::from_millis(5));
+                EVALS.with(|e| e.lock().unwrap().clone())
+            })
+            .await;
+        assert_eq!(recorded, [Duration::from_millis(5)]);
+    }
+}
@@ -0,0 +1,183 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{FromRequest, Multipart, Request},
+    http::{HeaderMap, StatusCode, header},
+};
+use base64::Engine;
+use serde::de::DeserializeOwned;
+
+use crate::ApiError;
+
+/// A request whose files can be attached as multipart parts, and where they
+/// go in its JSON body.
+pub trait FileTarget {
+    /// The body's object field that file parts are added to, e.g. `args`.
+    const FIELD: &'static str;
+}
+
+/// A JSON body, or the same body sent as `multipart/form-data` with files:
+///
+/// - `json`: the usual JSON body (optional; `{}` when omitted)
+/// - `file:<key>`: a file, added to the body's [`FileTarget::FIELD`] object
+///   under `<key>`
+/// - `encoding`: `base64` (default) to pass files as base64 strings, or
+///   `bytes` for arrays of byte values (what a `Vec<u8>` argument expects)
+///
+/// Multipart bodies are held to the `max_body_bytes` limit, not the 1 MiB cap
+/// on JSON bodies.
+pub struct JsonOrMultipart<T>(pub T);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FileEncoding {
+    Base64,
+    Bytes,
+}
+
+pub(crate) fn is_multipart(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("multipart/form-data"))
+}
+
+impl<S, T> FromRequest<S> for JsonOrMultipart<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + FileTarget,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_multipart(req.headers()) {
+            let Json(body) = Json::<T>::from_request(req, state)
+                .await
+                .map_err(|e| ApiError::new(e.status(), e.body_text()))?;
+            return Ok(Self(body));
+        }
+
+        let mut multipart = Multipart::from_request(req, state)
+            .await
+            .map_err(|e| ApiError::new(e.status(), e.body_text()))?;
+        let mut body = serde_json::json!({});
+        let mut encoding = FileEncoding::Base64;
+        let mut files = Vec::new();
+        while let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(|e| ApiError::new(e.status(), e.body_text()))?
+        {
+            let name = field.name().unwrap_or_default().to_string();
+            let data = field
+                .bytes()
+                .await
+                .map_err(|e| ApiError::new(e.status(), e.body_text()))?;
+            match name.as_str() {
+                "json" => {
+                    body = serde_json::from_slice(&data).map_err(|e| {
+                        ApiError::new(StatusCode::BAD_REQUEST, format!("invalid `json` part: {e}"))
+                    })?;
+                }
+                "encoding" => encoding = parse_encoding(&data)?,
+                _ => match name.strip_prefix("file:") {
+                    Some(key) if !key.is_empty() => files.push((key.to_string(), data)),
+                    _ => {
+                        return Err(ApiError::new(
+                            StatusCode::BAD_REQUEST,
+                            format!(
+                                "unexpected part '{name}' (expected json, encoding, or file:<key>)"
+                            ),
+                        ));
+                    }
+                },
+            }
+        }
+
+        attach_files(&mut body, T::FIELD, files, encoding)?;
+        serde_json::from_value(body).map(Self).map_err(|e| {
+            ApiError::new(StatusCode::BAD_REQUEST, format!("invalid `json` part: {e}"))
+        })
+    }
+}
+
+fn parse_encoding(data: &[u8]) -> Result<FileEncoding, ApiError> {
+    match data {
+        b"base64" => Ok(FileEncoding::Base64),
+        b"bytes" => Ok(FileEncoding::Bytes),
+        other => Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "unknown encoding '{}' (expected base64 or bytes)",
+                String::from_utf8_lossy(other)
+            ),
+        )),
+    }
+}
+
+/// Add each file to `body[field]` under its key.
+fn attach_files(
+    body: &mut serde_json::Value,
+    field: &str,
+    files: Vec<(String, Bytes)>,
+    encoding: FileEncoding,
+) -> Result<(), ApiError> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let Some(body) = body.as_object_mut() else {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "`json` part must be an object to attach files".to_string(),
+        ));
+    };
+    let target = body.entry(field).or_insert(serde_json::Value::Null);
+    if target.is_null() {
+        *target = serde_json::json!({});
+    }
+    let Some(target) = target.as_object_mut() else {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("`{field}` must be an object to attach files"),
+        ));
+    };
+    for (key, data) in files {
+        let value = match encoding {
+            FileEncoding::Base64 => base64::engine::general_purpose::STANDARD
+                .encode(&data)
+                .into(),
+            FileEncoding::Bytes => data.iter().map(|b| serde_json::Value::from(*b)).collect(),
+        };
+        target.insert(key, value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_attached_to_the_target_field() {
+        let mut body = serde_json::json!({ "command": "upload", "args": { "name": "logo" } });
+        let files = vec![("data".to_string(), Bytes::from_static(b"\x89PNG"))];
+        attach_files(&mut body, "args", files.clone(), FileEncoding::Base64).unwrap();
+        assert_eq!(body["args"]["name"], "logo");
+        assert_eq!(body["args"]["data"], "iVBORw==");
+
+        let mut body = serde_json::json!({ "event": "drop" });
+        attach_files(&mut body, "payload", files.clone(), FileEncoding::Bytes).unwrap();
+        assert_eq!(
+            body["payload"]["data"],
+            serde_json::json!([137, 80, 78, 71])
+        );
+
+        let mut body = serde_json::json!({ "command": "upload", "args": [1, 2] });
+        let status = attach_files(&mut body, "args", files, FileEncoding::Base64)
+            .unwrap_err()
+            .status;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        assert_eq!(parse_encoding(b"bytes").unwrap(), FileEncoding::Bytes);
+        assert!(parse_encoding(b"hex").is_err());
+    }
+}
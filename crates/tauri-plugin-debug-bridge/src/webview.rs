@@ -1,24 +1,36 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
 use axum::{
+    body::{Body, Bytes},
     extract::{Query, State},
     http::StatusCode,
-    response::{Json, Response},
+    response::{IntoResponse, Json, Response},
 };
 use serde::{Deserialize, Serialize};
-use tauri::{Manager, Runtime, WebviewWindow};
+use tauri::{AppHandle, Manager, Runtime, WebviewWindow};
 use tokio::sync::oneshot;
 
-use crate::{BridgeState, EvalResult};
+use crate::{ApiError, BridgeState, EvalResult};
 
 #[derive(Deserialize)]
 pub struct EvalRequest {
     pub js: String,
-    /// Optional window label. Defaults to "main".
+    /// Values the code can read as `__env.<key>`, instead of splicing them
+    /// into the source.
+    pub bindings: Option<Bindings>,
+    /// Optional window label. Defaults to the configured default window.
     pub window: Option<String>,
+    /// Wait for `document.readyState` to reach `complete` before running
+    /// the code (within the eval timeout).
+    #[serde(default)]
+    pub wait_ready: bool,
 }
 
+/// Named JSON values passed into evaluated code as `__env`.
+pub type Bindings = serde_json::Map<String, serde_json::Value>;
+
 #[derive(Deserialize)]
 pub struct ClickRequest {
     pub selector: String,
@@ -32,6 +44,214 @@ pub struct FillRequest {
     pub window: Option<String>,
 }
 
+#[derive(Deserialize)]
+pub struct SelectTextRequest {
+    pub selector: String,
+    /// Offset of the first selected character. Defaults to the start.
+    pub start: Option<u32>,
+    /// Offset just past the last selected character. Defaults to the end.
+    pub end: Option<u32>,
+    pub window: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SelectTextResponse {
+    pub selector: String,
+    /// The selected range, clamped to the element's text.
+    pub start: u64,
+    pub end: u64,
+    pub text: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct WaitNavigationRequest {
+    /// How long to wait. Defaults to 5 seconds.
+    pub timeout_ms: Option<u64>,
+    /// Only finish once the URL contains this.
+    pub url_contains: Option<String>,
+    pub window: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct WaitNavigationQuery {
+    /// Count loads from this `loads_started` value (as returned by
+    /// `/click`) instead of from when the wait arrived, so a navigation the
+    /// click started, or one that already finished, isn't missed.
+    pub since: Option<u64>,
+}
+
+/// `/click`'s answer: the eval result, plus the page-load counter read just
+/// before clicking, to pass to `/wait/navigation?since=`.
+#[derive(Serialize)]
+pub struct ClickResponse {
+    #[serde(flatten)]
+    pub result: EvalResult,
+    pub loads_started: u64,
+}
+
+#[derive(Serialize)]
+pub struct WaitNavigationResponse {
+    pub url: String,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ScreenshotQuery {
+    pub wait_ready: Option<bool>,
+    /// Capture the whole scrollable document instead of just the viewport.
+    pub full_page: Option<bool>,
+    /// Return the scripts the capture would inject instead of capturing.
+    pub dry_run: Option<bool>,
+}
+
+/// `?dry_run=true` on POST endpoints that inject JS.
+#[derive(Deserialize, Default)]
+pub struct DryRunQuery {
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct EvalQuery {
+    pub dry_run: Option<bool>,
+    /// Return the value as plain text instead of an [`EvalResult`].
+    pub raw: Option<bool>,
+}
+
+/// What a dry run would have injected, in order, exactly as passed to the
+/// webview. Nothing is run.
+#[derive(Serialize)]
+pub struct DryRun {
+    pub dry_run: bool,
+    pub scripts: Vec<String>,
+}
+
+/// Callback id used in dry-run wrappers. No result is ever pending under it,
+/// so running a printed wrapper by hand is harmless.
+const DRY_RUN_ID: &str = "dry-run";
+
+/// Largest full-page capture edge, in CSS pixels. WebKit refuses (or runs out
+/// of memory on) snapshots much beyond this, so taller pages are clipped.
+const MAX_CAPTURE_DIMENSION: f64 = 16384.0;
+
+/// Response header carrying a non-fatal warning about the capture.
+pub const WARNING_HEADER: &str = "X-Debug-Bridge-Warning";
+
+/// Size of the frames a screenshot is streamed in.
+const SCREENSHOT_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Windows `/screenshot/all` captures at once. Each capture holds a full
+/// bitmap in memory until it's encoded.
+const MAX_CONCURRENT_CAPTURES: usize = 4;
+
+#[derive(Deserialize)]
+pub struct StylesQuery {
+    pub selector: String,
+    /// Comma-separated property names. All properties when omitted.
+    pub props: Option<String>,
+    pub window: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct StylesResponse {
+    pub selector: String,
+    pub styles: BTreeMap<String, String>,
+    /// True when all properties were requested and the list was capped.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ClickableQuery {
+    pub selector: String,
+    pub window: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClickableResponse {
+    pub clickable: bool,
+    /// Why a click would miss, when it would.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct HtmlQuery {
+    /// Element ref (@e1) or CSS selector. The whole document when omitted.
+    pub selector: Option<String>,
+    /// Return `innerHTML` instead of `outerHTML`.
+    pub inner: Option<bool>,
+    pub window: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct HtmlResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    pub html: String,
+    /// Length of the full markup in UTF-16 code units, before any capping.
+    pub length: usize,
+    /// True when the markup was longer than [`MAX_HTML_LENGTH`] and was cut.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ClipboardQuery {
+    pub window: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ClipboardWriteRequest {
+    pub text: String,
+    pub window: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ClipboardResponse {
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+pub struct ZoomQuery {
+    pub window: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ZoomRequest {
+    pub factor: f64,
+    pub window: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ZoomResponse {
+    pub window: String,
+    /// The zoom factor in effect; 1.0 is 100%.
+    pub factor: f64,
+    /// True when the requested factor was outside [`ZOOM_RANGE`] and was
+    /// clamped to `factor`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub clamped: bool,
+}
+
+/// Zoom factors the bridge applies; anything outside is clamped.
+const ZOOM_RANGE: std::ops::RangeInclusive<f64> = 0.25..=5.0;
+
+/// Zoom factor the bridge last applied to each window. Tauri can set a
+/// webview's zoom but not read it back, so windows not zoomed through the
+/// bridge report 1.0.
+#[derive(Clone, Default)]
+pub struct ZoomLevels(Arc<std::sync::Mutex<HashMap<String, f64>>>);
+
+impl ZoomLevels {
+    fn get(&self, label: &str) -> f64 {
+        self.0.lock().unwrap().get(label).copied().unwrap_or(1.0)
+    }
+
+    fn set(&self, label: &str, factor: f64) {
+        self.0.lock().unwrap().insert(label.to_string(), factor);
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct SnapshotElement {
     pub tag: String,
@@ -45,7 +265,25 @@ pub struct SnapshotElement {
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
+    /// Only with `?attrs=true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Only with `?attrs=true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
     pub interactive: bool,
+    /// Whether the element is rendered. Only with `?include_hidden=true`;
+    /// otherwise hidden elements are left out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+    /// The element's test id (`Config.testid_attr`, `data-testid` by
+    /// default), targetable as `testid=<id>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub testid: Option<String>,
+    /// ARIA state attributes set on the element, keyed without the `aria-`
+    /// prefix, e.g. `{"expanded": "true"}`. Omitted when there are none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aria: Option<BTreeMap<String, String>>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub children: Vec<SnapshotElement>,
 }
@@ -55,29 +293,446 @@ pub struct SnapshotResponse {
     pub title: String,
     pub url: String,
     pub elements: Vec<SnapshotElement>,
+    /// True when the walk ran out of time and `elements` covers only the
+    /// part of the DOM visited before the budget (see [`snapshot_budget`]).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Elements the walk visited and kept, hidden or collapsed into their
+    /// parent or not, before `?interactive` pruning. Of a truncated walk,
+    /// only the part it reached.
+    #[serde(default)]
+    pub element_count: usize,
+    /// Of those, the interactive ones (each given a ref).
+    #[serde(default)]
+    pub interactive_count: usize,
 }
 
 #[derive(Deserialize, Default)]
 pub struct SnapshotQuery {
     pub interactive: Option<bool>,
+    /// Include each element's `id` and `class` attributes.
+    pub attrs: Option<bool>,
+    /// Keep hidden elements, marking every element with `visible`.
+    pub include_hidden: Option<bool>,
+    /// `tree` (default) or `locators` for a flat list of Playwright locators.
+    pub format: Option<String>,
+    /// Wait for `document.readyState` to be `complete` before walking.
+    pub wait_ready: Option<bool>,
+    /// Remove every `data-debug-ref` left by earlier snapshots first, so only
+    /// this snapshot's refs remain in the DOM.
+    pub reset_refs: Option<bool>,
+    /// Comma-separated element fields to keep (see [`SNAPSHOT_FIELDS`]);
+    /// `children` always stays. Tree format only.
+    pub fields: Option<String>,
+    /// Return the snapshot script instead of running it.
+    pub dry_run: Option<bool>,
+}
+
+/// Element fields `?fields=` can keep, as they are serialized.
+const SNAPSHOT_FIELDS: &[&str] = &[
+    "tag",
+    "ref",
+    "role",
+    "text",
+    "name",
+    "value",
+    "id",
+    "class",
+    "interactive",
+    "visible",
+    "testid",
+    "aria",
+];
+
+/// A suggested Playwright locator for one interactive element.
+#[derive(Serialize)]
+pub struct LocatorSuggestion {
+    pub r#ref: String,
+    /// How the locator was derived: `role`, `text`, or `css`.
+    pub strategy: &'static str,
+    pub locator: String,
+}
+
+#[derive(Deserialize)]
+pub struct PageStateQuery {
+    pub window: Option<String>,
+}
+
+/// One-call overview of a page.
+#[derive(Serialize, Deserialize)]
+pub struct PageState {
+    pub url: String,
+    pub title: String,
+    /// `document.readyState`: "loading", "interactive", or "complete".
+    pub ready_state: String,
+    /// The focused element as `tag#id` (plus its `@ref` if it has one), or
+    /// null when nothing but the body has focus.
+    pub focused_element: Option<String>,
+    /// Whether `console.error` was called, as far as the console buffer
+    /// knows; null until the window's console is captured.
+    #[serde(default)]
+    pub has_errors: Option<bool>,
+    pub element_count: usize,
+    pub interactive_count: usize,
+}
+
+#[derive(Deserialize)]
+pub struct RefsQuery {
+    pub window: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ClearedRefs {
+    /// Elements a `data-debug-ref` was removed from.
+    pub cleared: usize,
+}
+
+/// An element currently carrying a `data-debug-ref` attribute.
+#[derive(Serialize, Deserialize)]
+pub struct LiveRef {
+    /// The ref as used on the command line, e.g. `@e3`.
+    pub r#ref: String,
+    pub tag: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Whether the element is currently rendered (refs on hidden elements
+    /// are still clickable from script but may not behave as expected).
+    pub visible: bool,
+}
+
+/// Windows whose current page has the snapshot walker
+/// ([`SNAPSHOT_WALKER_JS`]) installed. Cleared on every page load, since a
+/// new page starts without it.
+#[derive(Clone, Default)]
+pub struct SnapshotWalkers(Arc<std::sync::Mutex<HashSet<String>>>);
+
+impl SnapshotWalkers {
+    fn is_installed(&self, label: &str) -> bool {
+        self.0.lock().unwrap().contains(label)
+    }
+
+    fn mark(&self, label: &str) {
+        self.0.lock().unwrap().insert(label.to_string());
+    }
+
+    pub fn forget(&self, label: &str) {
+        self.0.lock().unwrap().remove(label);
+    }
+}
+
+/// How long eval waits for a loading page before giving up.
+const READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Page-load state per webview label, fed by the plugin's `on_page_load` hook.
+/// A page counts as ready once it has finished loading, by which point the
+/// Tauri IPC (`__TAURI_INTERNALS__`) the eval callback relies on exists.
+#[derive(Clone, Default)]
+pub struct WebviewReadiness(Arc<std::sync::Mutex<HashMap<String, PageLoads>>>);
+
+#[derive(Clone, Default)]
+pub struct PageLoads {
+    ready: bool,
+    /// Page loads started so far, to tell a navigation happened.
+    started: u64,
+    /// Why the web content process died, until the next page load.
+    crashed: Option<&'static str>,
+}
+
+impl WebviewReadiness {
+    /// Record a page load starting or finishing. Either means the webview
+    /// is alive again after a crash.
+    pub fn set(&self, label: &str, ready: bool) {
+        let mut loads = self.0.lock().unwrap();
+        let entry = loads.entry(label.to_string()).or_default();
+        entry.ready = ready;
+        entry.crashed = None;
+        if !ready {
+            entry.started += 1;
+        }
+    }
+
+    pub fn mark_crashed(&self, label: &str, reason: &'static str) {
+        let mut loads = self.0.lock().unwrap();
+        loads.entry(label.to_string()).or_default().crashed = Some(reason);
+    }
+
+    /// Why the webview crashed, if it has and hasn't loaded a page since.
+    pub fn crashed(&self, label: &str) -> Option<&'static str> {
+        self.0.lock().unwrap().get(label).and_then(|l| l.crashed)
+    }
+
+    /// Resolves with the reason once the webview is known to have crashed.
+    async fn crash(&self, label: &str) -> &'static str {
+        loop {
+            if let Some(reason) = self.crashed(label) {
+                return reason;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// `None` when no page load has been seen for this webview.
+    pub fn get(&self, label: &str) -> Option<bool> {
+        self.0.lock().unwrap().get(label).map(|l| l.ready)
+    }
+
+    /// How many page loads have started in this webview.
+    pub fn loads_started(&self, label: &str) -> u64 {
+        self.0.lock().unwrap().get(label).map_or(0, |l| l.started)
+    }
+
+    /// `"ready"`, `"loading"`, or `"crashed"` per webview label, for `/health`.
+    pub fn states(&self) -> BTreeMap<String, &'static str> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(label, l)| {
+                let state = match (l.crashed, l.ready) {
+                    (Some(_), _) => "crashed",
+                    (None, true) => "ready",
+                    (None, false) => "loading",
+                };
+                (label.clone(), state)
+            })
+            .collect()
+    }
+}
+
+/// Mark `webview` crashed in `readiness` when its web content process dies,
+/// so evals fail at once instead of timing out. Only WebKitGTK lets a plugin
+/// watch for this; on other platforms a crash still shows up as a timeout.
+pub fn watch_for_crashes<R: Runtime>(webview: &tauri::Webview<R>, readiness: WebviewReadiness) {
+    #[cfg(target_os = "linux")]
+    {
+        let label = webview.label().to_string();
+        let app = webview.app_handle().clone();
+        let _ = webview.with_webview(move |platform| {
+            use webkit2gtk::{WebProcessTerminationReason, WebViewExt};
+            platform
+                .inner()
+                .connect_web_process_terminated(move |_, reason| {
+                    let reason = match reason {
+                        WebProcessTerminationReason::Crashed => "web content process crashed",
+                        WebProcessTerminationReason::ExceededMemoryLimit => {
+                            "web content process exceeded its memory limit"
+                        }
+                        _ => "web content process was terminated",
+                    };
+                    readiness.mark_crashed(&label, reason);
+                    if let Some(state) = app.try_state::<Arc<BridgeState<R>>>() {
+                        state
+                            .diagnostics
+                            .record("webview", format!("webview '{label}': {reason}"));
+                    }
+                });
+        });
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = (webview, readiness);
 }
 
-fn get_window<R: Runtime>(
-    app: &tauri::AppHandle<R>,
+/// The 503 for a request to a webview that crashed.
+fn crashed_error(label: &str, reason: &str) -> ApiError {
+    ApiError::new(
+        StatusCode::SERVICE_UNAVAILABLE,
+        format!("webview '{label}' crashed ({reason}); reload the window or restart the app"),
+    )
+}
+
+/// Wait (bounded by [`READY_TIMEOUT`]) for a loading webview to finish before
+/// injecting into it, so early-startup evals fail fast with 503 instead of
+/// timing out on a callback that can never fire. Webviews whose load was
+/// never observed are assumed ready.
+pub(crate) async fn wait_until_ready<R: Runtime>(
+    state: &BridgeState<R>,
+    window: &WebviewWindow<R>,
+) -> Result<(), ApiError> {
+    let deadline = tokio::time::Instant::now() + READY_TIMEOUT;
+    loop {
+        if let Some(reason) = state.readiness.crashed(window.label()) {
+            return Err(crashed_error(window.label(), reason));
+        }
+        if state.readiness.get(window.label()) != Some(false) {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            let message = format!(
+                "webview '{}' not ready: page still loading after {}s",
+                window.label(),
+                READY_TIMEOUT.as_secs()
+            );
+            state.diagnostics.record("eval", message.clone());
+            return Err(ApiError::new(StatusCode::SERVICE_UNAVAILABLE, message));
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// The Content-Security-Policy in effect and whether injected scripts run
+/// under it, per webview. Probed after every page load, since a navigation
+/// can bring a different `<meta>` policy.
+#[derive(Clone, Default)]
+pub struct CspStatus {
+    /// The app's configured policy (`devCsp` in debug builds, when set).
+    configured: Option<String>,
+    probes: Arc<std::sync::Mutex<HashMap<String, CspProbe>>>,
+}
+
+#[derive(Clone)]
+struct CspProbe {
+    /// Whether the injected probe called back.
+    ok: bool,
+    /// The page's `<meta http-equiv="Content-Security-Policy">`, if any.
+    meta_policy: Option<String>,
+}
+
+/// Reports the page's `<meta>` CSP, which applies on top of the configured
+/// one.
+const CSP_PROBE_JS: &str = r#"
+const meta = document.querySelector('meta[http-equiv="Content-Security-Policy" i]');
+return meta ? meta.content : null;
+"#;
+
+impl CspStatus {
+    pub fn new<R: Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let security = &app.config().app.security;
+        let configured = if cfg!(debug_assertions) {
+            security.dev_csp.as_ref().or(security.csp.as_ref())
+        } else {
+            security.csp.as_ref()
+        };
+        Self {
+            configured: configured.map(|csp| csp.to_string()),
+            probes: Default::default(),
+        }
+    }
+
+    /// `false` if the probe failed in any webview, `None` before any probe.
+    pub fn ok(&self) -> Option<bool> {
+        let probes = self.probes.lock().unwrap();
+        (!probes.is_empty()).then(|| probes.values().all(|p| p.ok))
+    }
+
+    /// The policy for `label`: the page's `<meta>` policy if it has one,
+    /// otherwise the configured one.
+    pub fn policy(&self, label: &str) -> Option<String> {
+        let meta = self
+            .probes
+            .lock()
+            .unwrap()
+            .get(label)
+            .and_then(|p| p.meta_policy.clone());
+        meta.or_else(|| self.configured.clone())
+    }
+
+    fn record(&self, label: &str, probe: CspProbe) {
+        self.probes.lock().unwrap().insert(label.to_string(), probe);
+    }
+
+    /// Hint for an eval in `label` that never called back, when the probe
+    /// there failed. A probe that ran shows injected scripts do, whatever the
+    /// policy says, so the timeout has some other cause.
+    pub(crate) fn timeout_hint(&self, label: &str) -> Option<String> {
+        let failed = self.probes.lock().unwrap().get(label).map(|p| !p.ok);
+        if failed != Some(true) {
+            return None;
+        }
+        let policy = self.policy(label);
+        Some(format!(
+            "injected scripts are likely blocked by the Content-Security-Policy ({}); allow 'unsafe-inline' in script-src for dev builds, e.g. via app.security.devCsp",
+            policy.as_deref().unwrap_or("policy unknown")
+        ))
+    }
+}
+
+/// Inject a trivial script into a freshly loaded webview and record whether
+/// it called back, so `/health` can flag a CSP that blocks the bridge before
+/// an eval times out.
+pub(crate) fn probe_csp<R: Runtime>(state: Arc<BridgeState<R>>, label: String) {
+    tauri::async_runtime::spawn(async move {
+        let Some(window) = state.app.get_webview_window(&label) else {
+            return;
+        };
+        let probe = match eval_with_result(&state, &window, CSP_PROBE_JS).await {
+            Ok(result) => CspProbe {
+                ok: true,
+                meta_policy: result.value.and_then(|v| v.as_str().map(String::from)),
+            },
+            Err(e) if e.status == StatusCode::GATEWAY_TIMEOUT => {
+                state.diagnostics.record(
+                    "csp",
+                    format!(
+                        "injected probe never ran in window '{label}' (policy: {})",
+                        state.csp.policy(&label).as_deref().unwrap_or("unknown")
+                    ),
+                );
+                CspProbe {
+                    ok: false,
+                    meta_policy: None,
+                }
+            }
+            // Navigated away or closed mid-probe; the next load re-probes.
+            Err(_) => return,
+        };
+        state.csp.record(&label, probe);
+    });
+}
+
+/// The window labeled `label`, or the configured default window. A label
+/// that isn't open is a 404 naming the windows that are, in the same shape
+/// from every endpoint, so custom routes can use this too.
+pub fn get_window<R: Runtime>(
+    state: &BridgeState<R>,
     label: Option<&str>,
-) -> Result<WebviewWindow<R>, (StatusCode, String)> {
-    let label = label.unwrap_or("main");
+) -> Result<WebviewWindow<R>, ApiError> {
+    find_window(&state.app, state.default_window.resolve(label))
+}
+
+/// [`get_window`] for an exact label, without the default.
+pub(crate) fn find_window<R: Runtime>(
+    app: &AppHandle<R>,
+    label: &str,
+) -> Result<WebviewWindow<R>, ApiError> {
     app.get_webview_window(label)
-        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("window '{label}' not found")))
+        .ok_or_else(|| window_not_found(label, app.webview_windows().into_keys().collect()))
+}
+
+/// The 404 for a window that isn't open, listing the `open` labels as
+/// `available`.
+fn window_not_found(label: &str, mut open: Vec<String>) -> ApiError {
+    open.sort();
+    let message = if open.is_empty() {
+        format!("window '{label}' not found (no windows are open)")
+    } else {
+        format!("window '{label}' not found (open: {})", open.join(", "))
+    };
+    ApiError::new(StatusCode::NOT_FOUND, message).with_detail("available", open)
 }
 
 /// Inject JS that evaluates code and sends the result back via the plugin's
 /// `eval_callback` Tauri command. Returns the result via a oneshot channel.
-async fn eval_with_result<R: Runtime>(
+pub(crate) async fn eval_with_result<R: Runtime>(
     state: &BridgeState<R>,
     window: &WebviewWindow<R>,
     js_code: &str,
-) -> Result<EvalResult, (StatusCode, String)> {
+) -> Result<EvalResult, ApiError> {
+    eval_with_bindings(state, window, js_code, None, false, false).await
+}
+
+/// [`eval_with_result`], with `bindings` in scope as `__env`, and with
+/// `wait_ready`, only once `document.readyState` is `complete`. With `raw`,
+/// the value comes back as a string (see [`RAW_VALUE_JS`]).
+pub(crate) async fn eval_with_bindings<R: Runtime>(
+    state: &BridgeState<R>,
+    window: &WebviewWindow<R>,
+    js_code: &str,
+    bindings: Option<&Bindings>,
+    wait_ready: bool,
+    raw: bool,
+) -> Result<EvalResult, ApiError> {
+    wait_until_ready(state, window).await?;
+
     let id = uuid_v4();
     let (tx, rx) = oneshot::channel();
 
@@ -86,6 +741,80 @@ async fn eval_with_result<R: Runtime>(
         pending.insert(id.clone(), tx);
     }
 
+    let wrapped = wrap_eval(js_code, &id, bindings, wait_ready, raw);
+    let injected = std::time::Instant::now();
+    if let Err(e) = window.eval(&wrapped) {
+        state.pending.lock().await.remove(&id);
+        state.diagnostics.record(
+            "eval",
+            format!("injection into window '{}' failed: {e}", window.label()),
+        );
+        return Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+        ));
+    }
+
+    // Wait for result with timeout, giving up early if the webview crashes.
+    let timeout = state.timeouts.eval;
+    let outcome = tokio::select! {
+        outcome = tokio::time::timeout(timeout, rx) => outcome,
+        reason = state.readiness.crash(window.label()) => {
+            state.pending.lock().await.remove(&id);
+            return Err(crashed_error(window.label(), reason));
+        }
+    };
+    match outcome {
+        Ok(Ok(result)) => {
+            crate::timing::record_eval(injected.elapsed());
+            Ok(result)
+        }
+        Ok(Err(_)) => Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "eval callback channel dropped".to_string(),
+        )),
+        Err(_) => {
+            // Clean up the pending entry.
+            let mut pending = state.pending.lock().await;
+            pending.remove(&id);
+            state.diagnostics.record(
+                "eval",
+                format!(
+                    "no callback from window '{}' within {}s (check the debug-bridge:default capability)",
+                    window.label(),
+                    timeout.as_secs()
+                ),
+            );
+            let message = format!("eval timed out after {}s", timeout.as_secs());
+            let message = match state.csp.timeout_hint(window.label()) {
+                Some(hint) => format!("{message}: {hint}"),
+                None => message,
+            };
+            Err(ApiError::new(StatusCode::GATEWAY_TIMEOUT, message))
+        }
+    }
+}
+
+/// Polls until the document has finished loading. Kept to one line so the
+/// user's code stays [`USER_CODE_LINE_OFFSET`] lines below `__probe`.
+const READY_STATE_PRELUDE: &str =
+    " while (document.readyState !== 'complete') await new Promise(r => setTimeout(r, 20));";
+
+/// Converts `__result` to the string a raw eval returns: strings as they
+/// are, `undefined` as nothing, anything else as JSON (or `String()` for
+/// what JSON can't hold, like functions).
+const RAW_VALUE_JS: &str = "__result === undefined ? '' : typeof __result === 'string' ? __result : (JSON.stringify(__result) ?? String(__result))";
+
+/// The script [`eval_with_result`] injects to run `js_code` and report its
+/// result back under callback `id`, first polling `document.readyState`
+/// with `wait_ready`, and sending the value as a string with `raw`.
+fn wrap_eval(
+    js_code: &str,
+    id: &str,
+    bindings: Option<&Bindings>,
+    wait_ready: bool,
+    raw: bool,
+) -> String {
     // Wrap the user's JS so it evaluates and calls back with the result.
     // Use __TAURI_INTERNALS__ which is always available in the Tauri webview,
     // unlike window.__TAURI__ which requires the @tauri-apps/api import.
@@ -94,99 +823,351 @@ async fn eval_with_result<R: Runtime>(
     // For multi-line code or code with statement keywords, pass through as-is
     // (callers must use `return` explicitly).
     // Note: we avoid eval() since webview CSP may not include 'unsafe-eval'.
-    let code_body = if looks_like_expression(js_code) {
-        format!("return ({js_code})")
+    //
+    // The user's code sits on its own lines, exactly USER_CODE_LINE_OFFSET
+    // lines below `__probe`, so error stacks can be mapped back to the
+    // snippet's own line numbers (see map_stack).
+    let (prefix, suffix) = if looks_like_expression(js_code) {
+        ("return (", ")")
     } else {
-        js_code.to_string()
+        ("", "")
     };
+    // Bindings arrive as the inner function's `__env` parameter, serialized
+    // as JSON (a valid JS literal), so no value is ever spliced into code and
+    // the user's lines stay where map_stack expects them.
+    let (param, env) = match bindings {
+        Some(bindings) => (
+            "__env",
+            serde_json::to_string(bindings).unwrap_or_else(|_| "{}".to_string()),
+        ),
+        None => ("", String::new()),
+    };
+
+    let prelude = if wait_ready { READY_STATE_PRELUDE } else { "" };
+    let value = if raw { RAW_VALUE_JS } else { "__result" };
 
-    let wrapped = format!(
-        r#"(async () => {{
+    format!(
+        r#"(async () => {{{prelude}
+            const __probe = new Error();
             try {{
-                const __result = await (async () => {{ {code} }})();
+                const __result = await (async ({param}) => {{ {prefix}
+{code}
+{suffix} }})({env});
                 await window.__TAURI_INTERNALS__.invoke(
                     'plugin:debug-bridge|eval_callback',
-                    {{ id: '{id}', success: true, value: __result, error: null }}
+                    {{ id: '{id}', success: true, value: {value}, undefined: __result === undefined, error: null }}
                 );
             }} catch(__e) {{
                 await window.__TAURI_INTERNALS__.invoke(
                     'plugin:debug-bridge|eval_callback',
-                    {{ id: '{id}', success: false, value: null, error: __e.toString() }}
+                    {{
+                        id: '{id}', success: false, value: null, error: String(__e),
+                        trace: (__e && __e.stack) ? {{
+                            stack: String(__e.stack), probe: __probe.stack || null, code_lines: {code_lines}
+                        }} : null
+                    }}
                 );
             }}
         }})()"#,
-        code = code_body,
+        code = js_code,
+        code_lines = js_code.lines().count().max(1),
         id = id,
-    );
-
-    window
-        .eval(&wrapped)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    )
+}
 
-    // Wait for result with timeout.
-    match tokio::time::timeout(Duration::from_secs(10), rx).await {
-        Ok(Ok(result)) => Ok(result),
-        Ok(Err(_)) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "eval callback channel dropped".to_string(),
-        )),
-        Err(_) => {
-            // Clean up the pending entry.
-            let mut pending = state.pending.lock().await;
-            pending.remove(&id);
-            Err((
-                StatusCode::GATEWAY_TIMEOUT,
-                "eval timed out after 10s".to_string(),
-            ))
-        }
-    }
+/// Respond with the wrapped `scripts` instead of running them.
+fn dry_run(scripts: &[&str], wait_ready: bool) -> Response {
+    Json(DryRun {
+        dry_run: true,
+        scripts: scripts
+            .iter()
+            .map(|js| wrap_eval(js, DRY_RUN_ID, None, wait_ready, false))
+            .collect(),
+    })
+    .into_response()
 }
 
 /// POST /eval — execute JS in the webview and return the result.
+/// With `?dry_run=true`, return the wrapped script instead. With
+/// `?raw=true`, return the value as `text/plain` rather than JSON, and a
+/// thrown error as a 422.
 pub async fn webview_eval<R: Runtime>(
     State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<EvalQuery>,
     Json(req): Json<EvalRequest>,
-) -> Result<Json<EvalResult>, (StatusCode, String)> {
-    let window = get_window(&state.app, req.window.as_deref())?;
-    let result = eval_with_result(&state, &window, &req.js).await?;
-    Ok(Json(result))
+) -> Result<Response, ApiError> {
+    let window = get_window(&state, req.window.as_deref())?;
+    let bindings = req.bindings.as_ref();
+    let raw = query.raw == Some(true);
+    if query.dry_run == Some(true) {
+        return Ok(Json(DryRun {
+            dry_run: true,
+            scripts: vec![wrap_eval(
+                &req.js,
+                DRY_RUN_ID,
+                bindings,
+                req.wait_ready,
+                raw,
+            )],
+        })
+        .into_response());
+    }
+    let mut result =
+        eval_with_bindings(&state, &window, &req.js, bindings, req.wait_ready, raw).await?;
+    // Code written for the app's own frontend often reaches for
+    // `window.__TAURI__`, which only exists with `app.withGlobalTauri`. The
+    // TypeError it throws (which on WebView2 doesn't even name it) hides
+    // that; check for the global and say so.
+    if !result.success && uses_global_tauri(&req.js) {
+        let missing = eval_with_result(&state, &window, "typeof window.__TAURI__ === 'undefined'")
+            .await
+            .is_ok_and(|r| r.value == Some(serde_json::Value::Bool(true)));
+        if missing {
+            result.error = Some(global_tauri_missing(result.error.as_deref()));
+        }
+    }
+    if raw {
+        return raw_response(result);
+    }
+    Ok(Json(result).into_response())
+}
+
+/// The body of a `?raw=true` eval: the value as sent by [`RAW_VALUE_JS`],
+/// or the error, with its stack, as a 422.
+fn raw_response(result: EvalResult) -> Result<Response, ApiError> {
+    if !result.success {
+        let message = result.error.unwrap_or_else(|| "eval failed".to_string());
+        let mut error = ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, message);
+        if let Some(stack) = result.stack {
+            error = error.with_detail("stack", stack);
+        }
+        return Err(error);
+    }
+    let text = match result.value {
+        Some(serde_json::Value::String(text)) => text,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    Ok(([("Content-Type", "text/plain; charset=utf-8")], text).into_response())
+}
+
+/// Whether `js` refers to `window.__TAURI__` (not the bridge's own
+/// `__TAURI_INTERNALS__`).
+fn uses_global_tauri(js: &str) -> bool {
+    js.contains("__TAURI__")
+}
+
+/// The error for an eval that failed because `window.__TAURI__` isn't
+/// defined, keeping the webview's own message.
+fn global_tauri_missing(error: Option<&str>) -> String {
+    let mut message = "window.__TAURI__ is not defined: set app.withGlobalTauri to true in \
+        tauri.conf.json, or import the API from @tauri-apps/api in the frontend \
+        (`tauri-browser invoke` works either way)"
+        .to_string();
+    if let Some(error) = error {
+        message.push_str(&format!(" [{error}]"));
+    }
+    message
 }
 
 /// GET /screenshot — capture the webview as a PNG image.
+/// Pass `?wait_ready=true` to wait for `document.readyState` to be
+/// `complete`, then (up to 5s) for web fonts and images to finish loading
+/// before capturing, and `?full_page=true` to capture the
+/// whole scrollable document. Full-page captures are capped at
+/// [`MAX_CAPTURE_DIMENSION`] per edge; when capped, the response carries an
+/// `X-Debug-Bridge-Warning` header. `?dry_run=true` returns the scripts
+/// those options inject (none for a plain capture) without capturing.
 pub async fn screenshot<R: Runtime>(
     State(state): State<Arc<BridgeState<R>>>,
-) -> Result<Response, (StatusCode, String)> {
-    let window = get_window(&state.app, None)?;
+    Query(query): Query<ScreenshotQuery>,
+) -> Result<Response, ApiError> {
+    let window = get_window(&state, None)?;
+    let wait_ready = query.wait_ready == Some(true);
+
+    if query.dry_run == Some(true) {
+        let mut scripts = Vec::new();
+        if wait_ready {
+            scripts.push(READY_WAIT_JS);
+        }
+        if query.full_page == Some(true) {
+            scripts.push(PAGE_SIZE_JS);
+        }
+        return Ok(dry_run(&scripts, wait_ready));
+    }
+
+    if wait_ready {
+        let result = eval_with_bindings(&state, &window, READY_WAIT_JS, None, true, false).await?;
+        if !result.success {
+            tracing::warn!(
+                "screenshot readiness wait failed, capturing anyway: {}",
+                result.error.unwrap_or_default()
+            );
+        }
+    }
+
+    let mut warning = None;
+    let full_page = if query.full_page == Some(true) {
+        let result = eval_with_result(&state, &window, PAGE_SIZE_JS).await?;
+        let size = result
+            .value
+            .as_ref()
+            .and_then(|v| Some((v.get(0)?.as_f64()?, v.get(1)?.as_f64()?)))
+            .ok_or_else(|| {
+                ApiError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!(
+                        "could not measure page size: {}",
+                        result.error.unwrap_or_default()
+                    ),
+                )
+            })?;
+        let (capped, was_capped) = cap_capture_size(size);
+        if was_capped {
+            let message = format!(
+                "page is {}x{}px; capture capped to {}x{}px",
+                size.0, size.1, capped.0, capped.1
+            );
+            tracing::warn!("screenshot: {message}");
+            warning = Some(message);
+        }
+        Some(capped)
+    } else {
+        None
+    };
+
+    let png_data = native_screenshot(&window, full_page).await?;
+    Ok(png_response(png_data, warning))
+}
+
+/// A PNG sent in chunks, with its full `Content-Length` so clients can
+/// preallocate and notice a short read.
+fn png_response(png_data: Vec<u8>, warning: Option<String>) -> Response {
+    let mut response = axum::response::Response::builder()
+        .header("Content-Type", "image/png")
+        .header("Content-Length", png_data.len());
+    if let Some(warning) = warning {
+        response = response.header(WARNING_HEADER, warning);
+    }
+    response.body(chunked_body(png_data)).unwrap()
+}
+
+#[derive(Serialize)]
+pub struct ScreenshotAllResponse {
+    /// Base64-encoded PNG per window label.
+    pub screenshots: BTreeMap<String, String>,
+    /// Why each window that couldn't be captured failed, by label.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, String>,
+}
+
+/// GET /screenshot/all — a viewport capture of every open window, taken
+/// concurrently, at most [`MAX_CONCURRENT_CAPTURES`] at a time. A window
+/// that can't be captured is listed under `errors`; the rest still succeed.
+pub async fn screenshot_all<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+) -> Json<ScreenshotAllResponse> {
+    use base64::Engine;
+
+    let limit = Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_CAPTURES));
+    let mut captures = tokio::task::JoinSet::new();
+    let mut labels = HashMap::new();
+    for (label, window) in state.app.webview_windows() {
+        let limit = limit.clone();
+        let task = captures.spawn(async move {
+            let _permit = limit.acquire_owned().await;
+            native_screenshot(&window, None).await
+        });
+        labels.insert(task.id(), label);
+    }
 
-    let png_data = native_screenshot(&window).await?;
+    let mut response = ScreenshotAllResponse {
+        screenshots: BTreeMap::new(),
+        errors: BTreeMap::new(),
+    };
+    while let Some(joined) = captures.join_next_with_id().await {
+        let (label, result) = match joined {
+            Ok((id, result)) => (labels.remove(&id), result.map_err(|e| e.message)),
+            Err(e) => (labels.remove(&e.id()), Err(format!("capture failed: {e}"))),
+        };
+        let label = label.unwrap_or_default();
+        match result {
+            Ok(png) => {
+                let png = base64::engine::general_purpose::STANDARD.encode(png);
+                response.screenshots.insert(label, png);
+            }
+            Err(error) => {
+                response.errors.insert(label, error);
+            }
+        }
+    }
+    Json(response)
+}
+
+/// Send `data` as [`SCREENSHOT_CHUNK_BYTES`] slices of the one buffer. The
+/// bridge still holds the whole capture; the chunks let clients write it
+/// out as it arrives instead of buffering it too.
+fn chunked_body(data: Vec<u8>) -> Body {
+    let data = Bytes::from(data);
+    let len = data.len();
+    let chunks = (0..len).step_by(SCREENSHOT_CHUNK_BYTES).map(move |start| {
+        let end = (start + SCREENSHOT_CHUNK_BYTES).min(len);
+        Ok::<_, std::convert::Infallible>(data.slice(start..end))
+    });
+    Body::from_stream(futures_util::stream::iter(chunks))
+}
 
-    Ok(axum::response::Response::builder()
-        .header("Content-Type", "image/png")
-        .body(axum::body::Body::from(png_data))
-        .unwrap())
+/// Clamp a full-page capture size to [`MAX_CAPTURE_DIMENSION`] per edge.
+/// Returns the clamped size and whether anything was cut off.
+fn cap_capture_size((width, height): (f64, f64)) -> ((f64, f64), bool) {
+    let capped = (
+        width.clamp(1.0, MAX_CAPTURE_DIMENSION),
+        height.clamp(1.0, MAX_CAPTURE_DIMENSION),
+    );
+    (capped, width > capped.0 || height > capped.1)
 }
 
 /// macOS: Use WKWebView's native takeSnapshot API.
+/// For full-page captures the view is temporarily resized to the document
+/// size so WebKit renders the off-screen content, then restored.
 #[cfg(target_os = "macos")]
 async fn native_screenshot<R: Runtime>(
     window: &WebviewWindow<R>,
-) -> Result<Vec<u8>, (StatusCode, String)> {
+    full_page: Option<(f64, f64)>,
+) -> Result<Vec<u8>, ApiError> {
     let (tx, rx) = oneshot::channel::<Result<Vec<u8>, String>>();
 
     window
         .with_webview(move |platform_webview| {
+            use objc2::Message;
             use objc2_app_kit::NSImage;
-            use objc2_web_kit::WKWebView;
+            use objc2_foundation::{NSPoint, NSRect, NSSize};
+            use objc2_web_kit::{WKSnapshotConfiguration, WKWebView};
 
             let ptr = platform_webview.inner();
             // SAFETY: Tauri guarantees this is a valid WKWebView pointer.
             let wkwebview: &WKWebView = unsafe { &*(ptr as *const WKWebView) };
 
+            // Remember the viewport size so it can be restored after capture.
+            // with_webview runs on the main thread.
+            let restore = full_page.map(|_| (wkwebview.retain(), wkwebview.frame().size));
+            let config = full_page.and_then(|(width, height)| {
+                let mtm = objc2::MainThreadMarker::new()?;
+                let size = NSSize::new(width, height);
+                wkwebview.setFrameSize(size);
+                let config = unsafe { WKSnapshotConfiguration::new(mtm) };
+                unsafe { config.setRect(NSRect::new(NSPoint::new(0.0, 0.0), size)) };
+                Some(config)
+            });
+
             // Use a Mutex to allow the closure to be called as Fn (block2 requires Fn).
             // The completion handler is only called once by WKWebView.
             let tx = std::sync::Mutex::new(Some(tx));
             let block = block2::RcBlock::new(
                 move |image: *mut NSImage, error: *mut objc2_foundation::NSError| {
+                    if let Some((view, size)) = &restore {
+                        view.setFrameSize(*size);
+                    }
                     let result = if !image.is_null() && error.is_null() {
                         // SAFETY: WKWebView guarantees image is valid when error is nil.
                         let image: &NSImage = unsafe { &*image };
@@ -207,11 +1188,12 @@ async fn native_screenshot<R: Runtime>(
             );
 
             unsafe {
-                wkwebview.takeSnapshotWithConfiguration_completionHandler(None, &block);
+                wkwebview
+                    .takeSnapshotWithConfiguration_completionHandler(config.as_deref(), &block);
             }
         })
         .map_err(|e| {
-            (
+            ApiError::new(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("with_webview failed: {e}"),
             )
@@ -219,12 +1201,12 @@ async fn native_screenshot<R: Runtime>(
 
     match tokio::time::timeout(Duration::from_secs(10), rx).await {
         Ok(Ok(Ok(data))) => Ok(data),
-        Ok(Ok(Err(e))) => Err((StatusCode::INTERNAL_SERVER_ERROR, e)),
-        Ok(Err(_)) => Err((
+        Ok(Ok(Err(e))) => Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e)),
+        Ok(Err(_)) => Err(ApiError::new(
             StatusCode::INTERNAL_SERVER_ERROR,
             "screenshot channel dropped".to_string(),
         )),
-        Err(_) => Err((
+        Err(_) => Err(ApiError::new(
             StatusCode::GATEWAY_TIMEOUT,
             "screenshot timed out after 10s".to_string(),
         )),
@@ -257,46 +1239,421 @@ fn image_to_png(image: &objc2_app_kit::NSImage) -> Option<Vec<u8>> {
 #[cfg(not(target_os = "macos"))]
 async fn native_screenshot<R: Runtime>(
     _window: &WebviewWindow<R>,
-) -> Result<Vec<u8>, (StatusCode, String)> {
-    Err((
+    _full_page: Option<(f64, f64)>,
+) -> Result<Vec<u8>, ApiError> {
+    Err(ApiError::new(
         StatusCode::NOT_IMPLEMENTED,
         "screenshot not yet implemented on this platform".to_string(),
     ))
 }
 
 /// GET /snapshot — dump the DOM as a ref-based accessibility tree.
-/// Pass `?interactive=true` to prune non-interactive leaf nodes.
+/// Pass `?interactive=true` to prune non-interactive leaf nodes, or
+/// `?dry_run=true` for the snapshot script itself.
 pub async fn snapshot<R: Runtime>(
     State(state): State<Arc<BridgeState<R>>>,
     Query(query): Query<SnapshotQuery>,
-) -> Result<Json<SnapshotResponse>, (StatusCode, String)> {
-    let window = get_window(&state.app, None)?;
+) -> Result<Response, ApiError> {
+    let locators = match query.format.as_deref() {
+        None | Some("tree") => false,
+        Some("locators") => true,
+        Some(other) => {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                format!("unknown snapshot format '{other}' (expected tree or locators)"),
+            ));
+        }
+    };
+    let fields = query.fields.as_deref().map(parse_fields).transpose()?;
+    if locators && fields.is_some() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "fields only applies to the tree format".to_string(),
+        ));
+    }
+    let window = get_window(&state, None)?;
 
-    let js = SNAPSHOT_JS;
-    let result = eval_with_result(&state, &window, js).await?;
+    let args = snapshot_args(
+        &query,
+        snapshot_budget(state.timeouts.eval),
+        &state.testid_attr,
+    );
+    let install = snapshot_install_js(&args);
+    let wait_ready = query.wait_ready == Some(true);
+    if query.dry_run == Some(true) {
+        return Ok(dry_run(&[&install], wait_ready));
+    }
+
+    // Call the walker cached by an earlier snapshot of this page; it comes
+    // back null if the page was replaced without the bridge noticing.
+    let mut cached = None;
+    if state.snapshot_walkers.is_installed(window.label()) {
+        let call = snapshot_call_js(&args);
+        let result = eval_with_bindings(&state, &window, &call, None, wait_ready, false).await?;
+        if !result.success || result.value.is_some() {
+            cached = Some(result);
+        }
+    }
+    let result = match cached {
+        Some(result) => result,
+        None => {
+            let result =
+                eval_with_bindings(&state, &window, &install, None, wait_ready, false).await?;
+            if result.success {
+                state.snapshot_walkers.mark(window.label());
+            }
+            result
+        }
+    };
 
     match result.value {
         Some(val) => {
             let mut snapshot: SnapshotResponse = serde_json::from_value(val).map_err(|e| {
-                (
+                ApiError::new(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     format!("failed to parse snapshot: {e}"),
                 )
             })?;
 
+            if locators {
+                return Ok(locators_response(&snapshot));
+            }
+
             if query.interactive == Some(true) {
                 snapshot.elements = prune_non_interactive(snapshot.elements);
             }
 
-            Ok(Json(snapshot))
+            let Some(fields) = fields else {
+                return Ok(Json(snapshot).into_response());
+            };
+            let mut snapshot = serde_json::to_value(snapshot)
+                .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            project_elements(&mut snapshot["elements"], &fields);
+            Ok(Json(snapshot).into_response())
         }
-        None => Err((
+        None => Err(ApiError::new(
             StatusCode::INTERNAL_SERVER_ERROR,
             format!("snapshot failed: {}", result.error.unwrap_or_default()),
         )),
     }
 }
 
+/// ARIA state attributes the walker reports under `aria`, without the
+/// `aria-` prefix. Static ones like `aria-label` feed `name` instead.
+const SNAPSHOT_ARIA_STATES: &[&str] = &[
+    "expanded", "checked", "selected", "disabled", "pressed", "current", "invalid", "busy",
+    "hidden",
+];
+
+/// Arguments to `window.__debugBridgeSnapshot`, in the walker's order.
+fn snapshot_args(query: &SnapshotQuery, budget: Duration, testid_attr: &str) -> String {
+    format!(
+        "{}, {}, {}, {}, {}, {}",
+        query.attrs == Some(true),
+        budget.as_millis(),
+        query.include_hidden == Some(true),
+        serde_json::to_string(testid_attr).unwrap(),
+        query.reset_refs == Some(true),
+        serde_json::to_string(SNAPSHOT_ARIA_STATES).unwrap(),
+    )
+}
+
+/// Install the snapshot walker in the page and run it with `args`.
+fn snapshot_install_js(args: &str) -> String {
+    format!("{SNAPSHOT_WALKER_JS}\nreturn window.__debugBridgeSnapshot({args});")
+}
+
+/// Run the walker an earlier snapshot installed, or return null if the page
+/// no longer has it.
+fn snapshot_call_js(args: &str) -> String {
+    format!(
+        "typeof window.__debugBridgeSnapshot === 'function' ? window.__debugBridgeSnapshot({args}) : null"
+    )
+}
+
+/// How long the snapshot walk may run before returning what it has. Leaves
+/// room within the eval timeout to serialize the tree and send it back, so
+/// a huge DOM yields a partial snapshot rather than a timeout.
+fn snapshot_budget(eval_timeout: Duration) -> Duration {
+    eval_timeout
+        .saturating_sub(Duration::from_secs(2))
+        .max(eval_timeout / 2)
+}
+
+/// GET /refs — refs assigned by previous snapshots that are still in the DOM.
+/// A ref missing here no longer exists; take a new snapshot.
+pub async fn refs<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<RefsQuery>,
+) -> Result<Json<Vec<LiveRef>>, ApiError> {
+    let window = get_window(&state, query.window.as_deref())?;
+    live_refs(&state, &window).await.map(Json)
+}
+
+/// POST /refs/clear — remove every `data-debug-ref` from the window's
+/// document, so the attributes don't pile up over a long session or trip
+/// the app's own selectors. Refs stop resolving until the next snapshot.
+pub async fn clear_refs<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Json(req): Json<RefsQuery>,
+) -> Result<Json<ClearedRefs>, ApiError> {
+    let window = get_window(&state, req.window.as_deref())?;
+    let result = eval_with_result(&state, &window, CLEAR_REFS_JS).await?;
+    match result.value.as_ref().and_then(serde_json::Value::as_u64) {
+        Some(cleared) => Ok(Json(ClearedRefs {
+            cleared: cleared as usize,
+        })),
+        None => Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("clearing refs failed: {}", result.error.unwrap_or_default()),
+        )),
+    }
+}
+
+async fn live_refs<R: Runtime>(
+    state: &BridgeState<R>,
+    window: &WebviewWindow<R>,
+) -> Result<Vec<LiveRef>, ApiError> {
+    let result = eval_with_result(state, window, LIVE_REFS_JS).await?;
+
+    match result.value {
+        Some(val) => serde_json::from_value(val).map_err(|e| {
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to parse refs: {e}"),
+            )
+        }),
+        None => Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("refs failed: {}", result.error.unwrap_or_default()),
+        )),
+    }
+}
+
+/// GET /pagestate?window=... — URL, title, load state, focus, and element
+/// counts in one round trip.
+pub async fn page_state<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<PageStateQuery>,
+) -> Result<Json<PageState>, ApiError> {
+    let window = get_window(&state, query.window.as_deref())?;
+    let result = eval_with_result(&state, &window, PAGE_STATE_JS).await?;
+
+    let mut page: PageState = match result.value {
+        Some(val) => serde_json::from_value(val).map_err(|e| {
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to parse page state: {e}"),
+            )
+        })?,
+        None => {
+            return Err(ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("pagestate failed: {}", result.error.unwrap_or_default()),
+            ));
+        }
+    };
+    if state.console_hooks.is_hooked(window.label()) {
+        page.has_errors = Some(state.console_buffer.count_level(window.label(), "error") > 0);
+    }
+    Ok(Json(page))
+}
+
+const DEFAULT_NAVIGATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// POST /wait/navigation — wait until the webview has navigated and the new
+/// page's `document.readyState` is `complete`. A navigation is a URL change
+/// (including `history.pushState`) or a new page load since the request
+/// arrived, or since `?since=` (the `loads_started` a click returned); with
+/// `url_contains`, a URL containing it is enough, even if the page got
+/// there before the wait began.
+pub async fn wait_navigation<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<WaitNavigationQuery>,
+    Json(req): Json<WaitNavigationRequest>,
+) -> Result<Json<WaitNavigationResponse>, ApiError> {
+    let window = get_window(&state, req.window.as_deref())?;
+    let timeout = req
+        .timeout_ms
+        .map_or(DEFAULT_NAVIGATION_TIMEOUT, Duration::from_millis);
+    let start = tokio::time::Instant::now();
+    let deadline = start + timeout;
+    let start_url = current_url(&window);
+    let start_loads = query
+        .since
+        .unwrap_or_else(|| state.readiness.loads_started(window.label()));
+
+    loop {
+        let url = current_url(&window);
+        let new_load = state.readiness.loads_started(window.label()) != start_loads;
+        if navigated(&start_url, &url, new_load, req.url_contains.as_deref())
+            && state.readiness.get(window.label()) != Some(false)
+            && document_complete(&state, &window, deadline).await
+        {
+            return Ok(Json(WaitNavigationResponse {
+                url,
+                elapsed_ms: start.elapsed().as_millis() as u64,
+            }));
+        }
+        if tokio::time::Instant::now() >= deadline {
+            let expected = match &req.url_contains {
+                Some(s) => format!("a URL containing '{s}'"),
+                None => "a navigation".to_string(),
+            };
+            return Err(ApiError::new(
+                StatusCode::GATEWAY_TIMEOUT,
+                format!(
+                    "no {expected} within {}ms (still at {url})",
+                    timeout.as_millis()
+                ),
+            ));
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+fn current_url<R: Runtime>(window: &WebviewWindow<R>) -> String {
+    window.url().map(|u| u.to_string()).unwrap_or_default()
+}
+
+/// Whether the webview counts as navigated: see [`wait_navigation`].
+fn navigated(start_url: &str, url: &str, new_load: bool, url_contains: Option<&str>) -> bool {
+    match url_contains {
+        Some(s) => url.contains(s),
+        None => url != start_url || new_load,
+    }
+}
+
+/// Whether the page reports `document.readyState === "complete"`. An eval
+/// that fails (the page went away mid-check) or is still running at
+/// `deadline` counts as no.
+async fn document_complete<R: Runtime>(
+    state: &BridgeState<R>,
+    window: &WebviewWindow<R>,
+    deadline: tokio::time::Instant,
+) -> bool {
+    let check = eval_with_result(state, window, "document.readyState");
+    match tokio::time::timeout_at(deadline, check).await {
+        Ok(Ok(result)) => result.value.as_ref().and_then(|v| v.as_str()) == Some("complete"),
+        Ok(Err(_)) | Err(_) => false,
+    }
+}
+
+/// `?format=locators`: a bare array of suggestions, as before, with a
+/// [`WARNING_HEADER`] when the walk was cut short so the list is partial.
+fn locators_response(snapshot: &SnapshotResponse) -> Response {
+    let mut response = Json(suggest_locators(&snapshot.elements)).into_response();
+    if snapshot.truncated {
+        response.headers_mut().insert(
+            WARNING_HEADER,
+            axum::http::HeaderValue::from_static("snapshot truncated: the walk ran out of time"),
+        );
+    }
+    response
+}
+
+/// Flatten the interactive elements of a snapshot tree into Playwright
+/// locators, preferring role + accessible name, then visible text, then a
+/// CSS tag selector with its index among interactive elements of that tag.
+fn suggest_locators(elements: &[SnapshotElement]) -> Vec<LocatorSuggestion> {
+    fn walk<'a>(elements: &'a [SnapshotElement], out: &mut Vec<&'a SnapshotElement>) {
+        for el in elements {
+            if el.interactive && el.r#ref.is_some() {
+                out.push(el);
+            }
+            walk(&el.children, out);
+        }
+    }
+    let mut interactive = Vec::new();
+    walk(elements, &mut interactive);
+
+    let quote = |s: &str| serde_json::to_string(s).unwrap();
+    let mut tag_counts: HashMap<&str, usize> = HashMap::new();
+    interactive
+        .into_iter()
+        .map(|el| {
+            let nth = tag_counts.entry(el.tag.as_str()).or_default();
+            let index = *nth;
+            *nth += 1;
+
+            let role = el.role.as_deref().or(implicit_role(&el.tag));
+            let name = el.name.as_deref().or(el.text.as_deref());
+            let (strategy, locator) = match (role, name, el.text.as_deref()) {
+                (Some(role), Some(name), _) => (
+                    "role",
+                    format!("getByRole({}, {{ name: {} }})", quote(role), quote(name)),
+                ),
+                (_, _, Some(text)) => ("text", format!("getByText({})", quote(text))),
+                _ => ("css", format!("locator({}).nth({index})", quote(&el.tag))),
+            };
+            LocatorSuggestion {
+                r#ref: format!("@{}", el.r#ref.as_deref().unwrap_or_default()),
+                strategy,
+                locator,
+            }
+        })
+        .collect()
+}
+
+/// ARIA roles implied by interactive tags. Shared by the locator suggestions
+/// and the `role=` selector matcher so the two agree.
+const IMPLICIT_ROLES: &[(&str, &str)] = &[
+    ("a", "link"),
+    ("button", "button"),
+    ("input", "textbox"),
+    ("textarea", "textbox"),
+    ("select", "combobox"),
+    ("option", "option"),
+    ("details", "group"),
+];
+
+/// ARIA role implied by an interactive tag, for elements without `role`.
+fn implicit_role(tag: &str) -> Option<&'static str> {
+    IMPLICIT_ROLES
+        .iter()
+        .find(|(t, _)| *t == tag)
+        .map(|(_, role)| *role)
+}
+
+/// The field names in a `?fields=` list, checked against [`SNAPSHOT_FIELDS`].
+fn parse_fields(list: &str) -> Result<Vec<&str>, ApiError> {
+    let fields: Vec<&str> = list
+        .split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .collect();
+    if let Some(unknown) = fields.iter().find(|f| !SNAPSHOT_FIELDS.contains(f)) {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "unknown snapshot field '{unknown}' (expected {})",
+                SNAPSHOT_FIELDS.join(", ")
+            ),
+        ));
+    }
+    if fields.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "fields is empty".to_string(),
+        ));
+    }
+    Ok(fields)
+}
+
+/// Drop every key but `fields` (and `children`, recursed into) from each
+/// serialized element.
+fn project_elements(elements: &mut serde_json::Value, fields: &[&str]) {
+    let Some(elements) = elements.as_array_mut() else {
+        return;
+    };
+    for element in elements.iter_mut().filter_map(|e| e.as_object_mut()) {
+        element.retain(|key, _| key == "children" || fields.contains(&key.as_str()));
+        if let Some(children) = element.get_mut("children") {
+            project_elements(children, fields);
+        }
+    }
+}
+
 /// Recursively prune non-interactive leaf nodes from the snapshot tree.
 /// Keeps any element that is interactive, or that has a descendant that is interactive.
 fn prune_non_interactive(elements: Vec<SnapshotElement>) -> Vec<SnapshotElement> {
@@ -313,58 +1670,121 @@ fn prune_non_interactive(elements: Vec<SnapshotElement>) -> Vec<SnapshotElement>
         .collect()
 }
 
+/// What the click and fill scripts return, instead of throwing, when their
+/// `@ref` is no longer in the page.
+const REF_NOT_FOUND: &str = "{ refNotFound: true }";
+
+fn is_ref_not_found(result: &EvalResult) -> bool {
+    result.success && result.value == Some(serde_json::json!({ "refNotFound": true }))
+}
+
+/// A 404 for a missing `@ref`, listing the refs that are still live (see
+/// `/refs`) so the caller can pick the right one or re-snapshot.
+async fn ref_not_found<R: Runtime>(
+    state: &BridgeState<R>,
+    window: &WebviewWindow<R>,
+    selector: &str,
+) -> ApiError {
+    let live = live_refs(state, window).await.unwrap_or_default();
+    ApiError::new(
+        StatusCode::NOT_FOUND,
+        ref_not_found_message(selector, &live),
+    )
+    .with_detail("live_refs", &live)
+}
+
+/// Most refs named in a ref-not-found message; the body lists them all.
+const MAX_SUGGESTED_REFS: usize = 10;
+
+fn ref_not_found_message(selector: &str, live: &[LiveRef]) -> String {
+    if live.is_empty() {
+        return format!(
+            "ref {selector} not found and the page has no live refs; take a snapshot to assign them"
+        );
+    }
+    let mut suggested: Vec<String> = live
+        .iter()
+        .take(MAX_SUGGESTED_REFS)
+        .map(|r| match &r.text {
+            Some(text) => format!("{} ({} \"{text}\")", r.r#ref, r.tag),
+            None => format!("{} ({})", r.r#ref, r.tag),
+        })
+        .collect();
+    if live.len() > MAX_SUGGESTED_REFS {
+        suggested.push(format!("{} more", live.len() - MAX_SUGGESTED_REFS));
+    }
+    format!(
+        "ref {selector} not found; live refs: {}. Take a new snapshot if none of these is it",
+        suggested.join(", ")
+    )
+}
+
 /// POST /click — click an element by @ref or CSS selector.
+/// With `?dry_run=true`, return the script instead of clicking.
 pub async fn click<R: Runtime>(
     State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<DryRunQuery>,
     Json(req): Json<ClickRequest>,
-) -> Result<Json<EvalResult>, (StatusCode, String)> {
-    let window = get_window(&state.app, req.window.as_deref())?;
+) -> Result<Response, ApiError> {
+    let window = get_window(&state, req.window.as_deref())?;
 
     let js = if req.selector.starts_with('@') {
         // Ref-based: find element by data-debug-ref attribute
         format!(
             r#"
-            const el = document.querySelector('[data-debug-ref="{}"]');
-            if (!el) throw new Error('Ref not found: {}');
+            const el = {lookup};
+            if (!el) return {REF_NOT_FOUND};
             el.scrollIntoView({{block: 'center'}});
             el.click();
             return true;
             "#,
-            &req.selector[1..],
-            req.selector,
+            lookup = element_lookup_js(&req.selector, &state.testid_attr),
         )
     } else {
         format!(
             r#"
-            const el = document.querySelector({});
-            if (!el) throw new Error('Element not found: {}');
+            const el = {};
+            if (!el) throw new Error('Element not found: ' + {});
             el.scrollIntoView({{block: 'center'}});
             el.click();
             return true;
             "#,
+            element_lookup_js(&req.selector, &state.testid_attr),
             serde_json::to_string(&req.selector).unwrap(),
-            req.selector,
         )
     };
 
+    if query.dry_run == Some(true) {
+        return Ok(dry_run(&[&js], false));
+    }
+    let loads_started = state.readiness.loads_started(window.label());
     let result = eval_with_result(&state, &window, &js).await?;
-    Ok(Json(result))
+    if is_ref_not_found(&result) {
+        return Err(ref_not_found(&state, &window, &req.selector).await);
+    }
+    Ok(Json(ClickResponse {
+        result,
+        loads_started,
+    })
+    .into_response())
 }
 
 /// POST /fill — fill an input element with text.
+/// With `?dry_run=true`, return the script instead of filling.
 pub async fn fill<R: Runtime>(
     State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<DryRunQuery>,
     Json(req): Json<FillRequest>,
-) -> Result<Json<EvalResult>, (StatusCode, String)> {
-    let window = get_window(&state.app, req.window.as_deref())?;
+) -> Result<Response, ApiError> {
+    let window = get_window(&state, req.window.as_deref())?;
 
     let text_json = serde_json::to_string(&req.text).unwrap();
 
     let js = if req.selector.starts_with('@') {
         format!(
             r#"
-            const el = document.querySelector('[data-debug-ref="{}"]');
-            if (!el) throw new Error('Ref not found: {}');
+            const el = {lookup};
+            if (!el) return {REF_NOT_FOUND};
             el.scrollIntoView({{block: 'center'}});
             el.focus();
             el.value = {text};
@@ -372,15 +1792,14 @@ pub async fn fill<R: Runtime>(
             el.dispatchEvent(new Event('change', {{bubbles: true}}));
             return true;
             "#,
-            &req.selector[1..],
-            req.selector,
+            lookup = element_lookup_js(&req.selector, &state.testid_attr),
             text = text_json,
         )
     } else {
         format!(
             r#"
-            const el = document.querySelector({selector});
-            if (!el) throw new Error('Element not found: {}');
+            const el = {lookup};
+            if (!el) throw new Error('Element not found: ' + {selector});
             el.scrollIntoView({{block: 'center'}});
             el.focus();
             el.value = {text};
@@ -388,14 +1807,599 @@ pub async fn fill<R: Runtime>(
             el.dispatchEvent(new Event('change', {{bubbles: true}}));
             return true;
             "#,
-            req.selector,
+            lookup = element_lookup_js(&req.selector, &state.testid_attr),
             selector = serde_json::to_string(&req.selector).unwrap(),
             text = text_json,
         )
     };
 
-    let result = eval_with_result(&state, &window, &js).await?;
-    Ok(Json(result))
+    if query.dry_run == Some(true) {
+        return Ok(dry_run(&[&js], false));
+    }
+    let result = eval_with_result(&state, &window, &js).await?;
+    if is_ref_not_found(&result) {
+        return Err(ref_not_found(&state, &window, &req.selector).await);
+    }
+    Ok(Json(result).into_response())
+}
+
+/// POST /select-text — select a range of an element's text: the value of an
+/// input or textarea, or the text content of anything else (e.g. a
+/// contenteditable). Selects everything when the range is omitted and
+/// returns the selected string.
+pub async fn select_text<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Json(req): Json<SelectTextRequest>,
+) -> Result<Json<SelectTextResponse>, ApiError> {
+    if let (Some(start), Some(end)) = (req.start, req.end)
+        && end < start
+    {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("end ({end}) is before start ({start})"),
+        ));
+    }
+    let window = get_window(&state, req.window.as_deref())?;
+
+    let js = select_text_js(&req.selector, &state.testid_attr, req.start, req.end);
+    let result = eval_with_result(&state, &window, &js).await?;
+    if !result.success {
+        return Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("select-text failed: {}", result.error.unwrap_or_default()),
+        ));
+    }
+
+    let value = result.value.unwrap_or_default();
+    if value.is_null() {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("element not found: {}", req.selector),
+        ));
+    }
+    Ok(Json(SelectTextResponse {
+        selector: req.selector,
+        start: value["start"].as_u64().unwrap_or(0),
+        end: value["end"].as_u64().unwrap_or(0),
+        text: value["text"].as_str().unwrap_or_default().to_string(),
+    }))
+}
+
+/// Script selecting `start..end` of an element's text, or null when the
+/// element doesn't exist. Inputs use `setSelectionRange`; other elements get
+/// a DOM `Range` mapped onto their text nodes.
+fn select_text_js(
+    selector: &str,
+    testid_attr: &str,
+    start: Option<u32>,
+    end: Option<u32>,
+) -> String {
+    let offset = |o: Option<u32>| o.map_or("null".to_string(), |o| o.to_string());
+    format!(
+        r#"
+        const el = {lookup};
+        if (!el) return null;
+        el.scrollIntoView({{block: 'center'}});
+        const from = {start}, to = {end};
+        if (typeof el.setSelectionRange === 'function' && typeof el.value === 'string') {{
+            const len = el.value.length;
+            const s = Math.min(from ?? 0, len), e = Math.min(to ?? len, len);
+            el.focus();
+            el.setSelectionRange(s, e);
+            return {{ start: s, end: e, text: el.value.slice(s, e) }};
+        }}
+        const len = el.textContent.length;
+        const s = Math.min(from ?? 0, len), e = Math.min(to ?? len, len);
+        const range = document.createRange();
+        range.selectNodeContents(el);
+        const walker = document.createTreeWalker(el, NodeFilter.SHOW_TEXT);
+        let offset = 0, started = false, node;
+        while ((node = walker.nextNode())) {{
+            const end = offset + node.data.length;
+            if (!started && s <= end) {{
+                range.setStart(node, s - offset);
+                started = true;
+            }}
+            if (e <= end) {{
+                range.setEnd(node, e - offset);
+                break;
+            }}
+            offset = end;
+        }}
+        if (el.isContentEditable) el.focus();
+        const selection = window.getSelection();
+        selection.removeAllRanges();
+        selection.addRange(range);
+        return {{ start: s, end: e, text: range.toString() }};
+        "#,
+        lookup = element_lookup_js(selector, testid_attr),
+        start = offset(start),
+        end = offset(end),
+    )
+}
+
+/// Upper bound on markup returned by `/html`, in UTF-16 code units. Keeps a
+/// huge page from producing a response too large to pass through IPC.
+const MAX_HTML_LENGTH: usize = 4 * 1024 * 1024;
+
+/// GET /html?selector=...&inner=true — raw markup of the page or an element.
+pub async fn html<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<HtmlQuery>,
+) -> Result<Json<HtmlResponse>, ApiError> {
+    let window = get_window(&state, query.window.as_deref())?;
+
+    let lookup = match &query.selector {
+        Some(selector) => element_lookup_js(selector, &state.testid_attr),
+        None => "document.documentElement".to_string(),
+    };
+    let js = format!(
+        r#"
+        const el = {lookup};
+        if (!el) return null;
+        const html = el.{property};
+        return {{ html: html.slice(0, {max}), length: html.length }};
+        "#,
+        property = if query.inner == Some(true) {
+            "innerHTML"
+        } else {
+            "outerHTML"
+        },
+        max = MAX_HTML_LENGTH,
+    );
+
+    let result = eval_with_result(&state, &window, &js).await?;
+    if !result.success {
+        return Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("html failed: {}", result.error.unwrap_or_default()),
+        ));
+    }
+
+    let value = result.value.unwrap_or_default();
+    if value.is_null() {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!(
+                "element not found: {}",
+                query.selector.as_deref().unwrap_or_default()
+            ),
+        ));
+    }
+
+    let length = value["length"].as_u64().unwrap_or(0) as usize;
+    Ok(Json(HtmlResponse {
+        selector: query.selector,
+        html: value["html"].as_str().unwrap_or_default().to_string(),
+        length,
+        truncated: length > MAX_HTML_LENGTH,
+    }))
+}
+
+/// Upper bound on properties returned when `/styles` is asked for all of them.
+const MAX_STYLE_PROPS: usize = 400;
+
+/// GET /styles?selector=...&props=display,color — computed CSS for an element.
+/// Properties the browser doesn't know are omitted rather than reported as errors.
+pub async fn styles<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<StylesQuery>,
+) -> Result<Json<StylesResponse>, ApiError> {
+    let window = get_window(&state, query.window.as_deref())?;
+
+    let props: Option<Vec<&str>> = query.props.as_deref().map(|p| {
+        p.split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .collect()
+    });
+
+    let js = format!(
+        r#"
+        const el = {lookup};
+        if (!el) return null;
+        const style = window.getComputedStyle(el);
+        const names = {props} || Array.from(style).slice(0, {max});
+        const styles = {{}};
+        for (const name of names) {{
+            const value = style.getPropertyValue(name);
+            if (value !== '') styles[name] = value;
+        }}
+        return {{ styles: styles, total: style.length }};
+        "#,
+        lookup = element_lookup_js(&query.selector, &state.testid_attr),
+        props = serde_json::to_string(&props).unwrap(),
+        max = MAX_STYLE_PROPS,
+    );
+
+    let result = eval_with_result(&state, &window, &js).await?;
+    if !result.success {
+        return Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("styles failed: {}", result.error.unwrap_or_default()),
+        ));
+    }
+
+    let value = result.value.unwrap_or_default();
+    if value.is_null() {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("element not found: {}", query.selector),
+        ));
+    }
+
+    let styles: BTreeMap<String, String> = serde_json::from_value(value["styles"].clone())
+        .map_err(|e| {
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to parse styles: {e}"),
+            )
+        })?;
+    let total = value["total"].as_u64().unwrap_or(0) as usize;
+
+    Ok(Json(StylesResponse {
+        selector: query.selector,
+        styles,
+        truncated: props.is_none() && total > MAX_STYLE_PROPS,
+    }))
+}
+
+/// GET /clickable?selector=... — whether a real click at the element's
+/// center would land on it: it has a size, is visible, takes pointer events,
+/// is in the viewport, and isn't covered by another element (the
+/// "element intercepted" failure).
+pub async fn clickable<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<ClickableQuery>,
+) -> Result<Json<ClickableResponse>, ApiError> {
+    let window = get_window(&state, query.window.as_deref())?;
+
+    let js = format!(
+        r#"
+        const el = {lookup};
+        if (!el) return null;
+        const no = (reason) => ({{ clickable: false, reason: reason }});
+        const describe = (node) => {{
+            let s = node.tagName.toLowerCase();
+            if (node.id) s += '#' + node.id;
+            const cls = (node.getAttribute('class') || '').trim().split(/\s+/).filter(Boolean);
+            if (cls.length) s += '.' + cls.slice(0, 2).join('.');
+            return s;
+        }};
+        const rect = el.getBoundingClientRect();
+        if (rect.width === 0 || rect.height === 0) return no('element has no size (display: none or collapsed)');
+        const style = window.getComputedStyle(el);
+        if (style.visibility !== 'visible') return no('visibility: ' + style.visibility);
+        if (style.pointerEvents === 'none') return no('pointer-events: none');
+        if (el.disabled) return no('element is disabled');
+        const x = rect.left + rect.width / 2;
+        const y = rect.top + rect.height / 2;
+        if (x < 0 || y < 0 || x >= window.innerWidth || y >= window.innerHeight) {{
+            return no('center is outside the viewport (click scrolls it into view first)');
+        }}
+        const hit = document.elementFromPoint(x, y);
+        if (!hit) return no('nothing is hit at the element center');
+        if (hit !== el && !el.contains(hit)) return no('covered by ' + describe(hit));
+        return {{ clickable: true }};
+        "#,
+        lookup = element_lookup_js(&query.selector, &state.testid_attr),
+    );
+
+    let result = eval_with_result(&state, &window, &js).await?;
+    clickable_response(&query.selector, result).map(Json)
+}
+
+/// Map the clickable script's result: its verdict, a 404 when the selector
+/// matched nothing, or a 500 when the script itself failed.
+fn clickable_response(selector: &str, result: EvalResult) -> Result<ClickableResponse, ApiError> {
+    if !result.success {
+        return Err(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "clickable check failed: {}",
+                result.error.unwrap_or_default()
+            ),
+        ));
+    }
+
+    let value = result.value.unwrap_or_default();
+    if value.is_null() {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("element not found: {selector}"),
+        ));
+    }
+    serde_json::from_value(value).map_err(|e| {
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("failed to parse clickable check: {e}"),
+        )
+    })
+}
+
+/// GET /zoom?window=... — the window's zoom factor.
+pub async fn zoom<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<ZoomQuery>,
+) -> Result<Json<ZoomResponse>, ApiError> {
+    let window = get_window(&state, query.window.as_deref())?;
+    Ok(Json(ZoomResponse {
+        window: window.label().to_string(),
+        factor: state.zoom.get(window.label()),
+        clamped: false,
+    }))
+}
+
+/// POST /zoom — set the window's zoom factor, clamped to [`ZOOM_RANGE`].
+/// Affects layout and screenshots like the user zooming the page.
+pub async fn set_zoom<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Json(req): Json<ZoomRequest>,
+) -> Result<Json<ZoomResponse>, ApiError> {
+    let window = get_window(&state, req.window.as_deref())?;
+    let factor = clamp_zoom(req.factor)?;
+    window.set_zoom(factor).map_err(|e| {
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("set_zoom failed: {e}"),
+        )
+    })?;
+    state.zoom.set(window.label(), factor);
+    Ok(Json(ZoomResponse {
+        window: window.label().to_string(),
+        factor,
+        clamped: factor != req.factor,
+    }))
+}
+
+fn clamp_zoom(factor: f64) -> Result<f64, ApiError> {
+    if !factor.is_finite() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("zoom factor must be a number, got {factor}"),
+        ));
+    }
+    Ok(factor.clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end()))
+}
+
+/// GET /clipboard — read clipboard text through the webview's async clipboard API.
+pub async fn clipboard_read<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Query(query): Query<ClipboardQuery>,
+) -> Result<Json<ClipboardResponse>, ApiError> {
+    let window = get_window(&state, query.window.as_deref())?;
+
+    let js = r#"
+        if (!navigator.clipboard || !navigator.clipboard.readText) {
+            throw new Error('NotAllowedError: async clipboard API unavailable in this webview');
+        }
+        return await navigator.clipboard.readText();
+    "#;
+
+    let result = eval_with_result(&state, &window, js).await?;
+    if !result.success {
+        return Err(clipboard_error(result.error));
+    }
+
+    let text = result
+        .value
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default();
+    Ok(Json(ClipboardResponse { text }))
+}
+
+/// POST /clipboard — write clipboard text. Falls back to `execCommand('copy')`
+/// when the async clipboard API rejects the write.
+pub async fn clipboard_write<R: Runtime>(
+    State(state): State<Arc<BridgeState<R>>>,
+    Json(req): Json<ClipboardWriteRequest>,
+) -> Result<Json<EvalResult>, ApiError> {
+    let window = get_window(&state, req.window.as_deref())?;
+
+    let js = format!(
+        r#"
+        const text = {text};
+        try {{
+            await navigator.clipboard.writeText(text);
+        }} catch (e) {{
+            const ta = document.createElement('textarea');
+            ta.value = text;
+            ta.style.position = 'fixed';
+            ta.style.opacity = '0';
+            document.body.appendChild(ta);
+            ta.select();
+            const ok = document.execCommand('copy');
+            ta.remove();
+            if (!ok) throw e;
+        }}
+        return true;
+        "#,
+        text = serde_json::to_string(&req.text).unwrap(),
+    );
+
+    let result = eval_with_result(&state, &window, &js).await?;
+    if !result.success {
+        return Err(clipboard_error(result.error));
+    }
+    Ok(Json(result))
+}
+
+/// Map a failed clipboard eval to a status: permission denials become 403 so
+/// callers can tell them apart from bridge failures.
+fn clipboard_error(error: Option<String>) -> ApiError {
+    let error = error.unwrap_or_default();
+    if error.contains("NotAllowedError") || error.contains("denied") {
+        ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!("clipboard access denied by the webview (it may need focus): {error}"),
+        )
+    } else {
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("clipboard failed: {error}"),
+        )
+    }
+}
+
+/// Attribute `testid=` selectors match unless `Config.testid_attr` says
+/// otherwise.
+pub const DEFAULT_TESTID_ATTR: &str = "data-testid";
+
+/// How a selector string targets an element. `@` prefixes a ref, `role=` a
+/// role query, `testid=` a test id, and `text=` or `text*=` visible text;
+/// anything else is CSS (no CSS selector starts with any of these).
+#[derive(Debug, PartialEq)]
+enum Target<'a> {
+    Ref(&'a str),
+    TestId(&'a str),
+    /// `role=button` or `role=button[name="Submit"]`.
+    Role {
+        role: &'a str,
+        name: Option<String>,
+    },
+    /// `text="Submit"` (the whole text, exactly) or `text*="Sub"` (any part
+    /// of it, ignoring case).
+    Text {
+        text: String,
+        exact: bool,
+    },
+    Css(&'a str),
+}
+
+fn parse_target(selector: &str) -> Result<Target<'_>, String> {
+    if let Some(r) = selector.strip_prefix('@') {
+        return Ok(Target::Ref(r));
+    }
+    if let Some(id) = selector.strip_prefix("testid=") {
+        return Ok(Target::TestId(id));
+    }
+    let text = selector
+        .strip_prefix("text*=")
+        .map(|t| (t, false))
+        .or_else(|| selector.strip_prefix("text=").map(|t| (t, true)));
+    if let Some((text, exact)) = text {
+        let text = unquote(text);
+        if text.trim().is_empty() {
+            return Err(format!("empty text in {selector}"));
+        }
+        return Ok(Target::Text { text, exact });
+    }
+    let Some(query) = selector.strip_prefix("role=") else {
+        return Ok(Target::Css(selector));
+    };
+    let (role, name) = match query.split_once('[') {
+        None => (query, None),
+        Some((role, rest)) => {
+            let value = rest
+                .strip_prefix("name=")
+                .and_then(|v| v.strip_suffix(']'))
+                .ok_or_else(|| format!("expected role=<role>[name=\"...\"], got {selector}"))?;
+            (role, Some(unquote(value)))
+        }
+    };
+    if role.is_empty() || !role.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("invalid role in {selector}"));
+    }
+    Ok(Target::Role { role, name })
+}
+
+/// Strip matching single or double quotes, unescaping `\"`, `\'` and `\\`.
+fn unquote(value: &str) -> String {
+    let quoted = ['"', '\'']
+        .into_iter()
+        .find_map(|q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q)));
+    let Some(inner) = quoted else {
+        return value.to_string();
+    };
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(next @ ('"' | '\'' | '\\'))) => {
+                out.push(next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// What a `text=` match resolves to when it lands inside one of these:
+/// the control, rather than the `<span>` holding its label. Mirrors the
+/// snapshot walker's idea of interactive.
+const TEXT_MATCH_TARGETS: &str = "a, button, input, select, textarea, details, summary, label, \
+    option, [role=button], [role=link], [role=checkbox], [role=radio], [role=tab], \
+    [role=menuitem], [role=option], [role=switch], [role=treeitem], [tabindex], [onclick]";
+
+/// JS expression resolving an `@ref`, `testid=` id (matched against
+/// `testid_attr`), `role=` query, `text=` query, or CSS selector to an
+/// element (or null). A role query matches the explicit `role` attribute or
+/// the implied one, and a name equal (whitespace-collapsed) to the
+/// element's `aria-label`, `name`, `placeholder`, or text, the same fields
+/// `/snapshot` reports. A text query matches the innermost visible element
+/// whose whitespace-collapsed text (a button input's value) is the text, or
+/// with `text*=` contains it in any case, and resolves to its closest
+/// [`TEXT_MATCH_TARGETS`] ancestor if any. Either way the first match in
+/// document order wins. A malformed query throws.
+fn element_lookup_js(selector: &str, testid_attr: &str) -> String {
+    let quote = |s: &str| serde_json::to_string(s).unwrap();
+    match parse_target(selector) {
+        Ok(Target::Ref(r)) => format!(
+            "document.querySelector({})",
+            quote(&format!("[data-debug-ref=\"{}\"]", css_string_escape(r)))
+        ),
+        Ok(Target::TestId(id)) => format!(
+            "document.querySelector({})",
+            quote(&format!("[{testid_attr}=\"{}\"]", css_string_escape(id)))
+        ),
+        Ok(Target::Css(css)) => format!("document.querySelector({})", quote(css)),
+        Ok(Target::Role { role, name }) => {
+            let implicit: BTreeMap<&str, &str> = IMPLICIT_ROLES.iter().copied().collect();
+            format!(
+                r#"((role, name, implicit) => {{
+            const norm = (s) => (s || '').replace(/\s+/g, ' ').trim();
+            for (const el of document.body.querySelectorAll('*')) {{
+                if ((el.getAttribute('role') || implicit[el.tagName.toLowerCase()]) !== role) continue;
+                if (name === null) return el;
+                const names = [el.getAttribute('aria-label'), el.getAttribute('name'),
+                    el.getAttribute('placeholder'), el.textContent];
+                if (names.some((n) => n !== null && norm(n) === name)) return el;
+            }}
+            return null;
+        }})({}, {}, {})"#,
+                quote(role),
+                serde_json::to_string(&name).unwrap(),
+                serde_json::to_string(&implicit).unwrap(),
+            )
+        }
+        Ok(Target::Text { text, exact }) => format!(
+            r#"((wanted, exact, targets) => {{
+            const norm = (s) => (s || '').replace(/\s+/g, ' ').trim();
+            const text = (el) => el.matches('input[type=button], input[type=submit], input[type=reset]')
+                ? el.value : el.textContent;
+            const hit = exact
+                ? (el) => norm(text(el)) === wanted
+                : (el) => norm(text(el)).toLowerCase().includes(wanted.toLowerCase());
+            const visible = (el) => el.checkVisibility
+                ? el.checkVisibility({{ visibilityProperty: true }})
+                : el.getClientRects().length > 0;
+            for (const el of document.body.querySelectorAll('*')) {{
+                if (!hit(el) || Array.from(el.children).some(hit) || !visible(el)) continue;
+                return el.closest(targets) || el;
+            }}
+            return null;
+        }})({}, {exact}, {})"#,
+            quote(&text.split_whitespace().collect::<Vec<_>>().join(" ")),
+            quote(TEXT_MATCH_TARGETS),
+        ),
+        Err(e) => format!("(() => {{ throw new Error({}); }})()", quote(&e)),
+    }
+}
+
+/// Escape `"` and `\` for a double-quoted CSS string.
+fn css_string_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
 }
 
 /// Detect if JS code is a simple expression (no statements).
@@ -429,6 +2433,75 @@ fn looks_like_expression(code: &str) -> bool {
     !keywords.iter().any(|kw| trimmed.starts_with(kw))
 }
 
+/// Raw stack of an error thrown by evaluated code, sent by the eval wrapper.
+#[derive(Deserialize)]
+pub struct ErrorTrace {
+    pub stack: String,
+    /// Stack of an error created a fixed distance above the user's code.
+    pub probe: Option<String>,
+    /// Number of lines in the user's code.
+    pub code_lines: u32,
+}
+
+/// Lines between the `__probe` error in the eval wrapper and the first line
+/// of user code.
+const USER_CODE_LINE_OFFSET: u32 = 3;
+
+/// Parse the `source:line:column` location at the end of a stack frame, in
+/// either V8 (`at fn (src:3:7)`, `at src:3:7`) or WebKit/Gecko (`fn@src:3:7`)
+/// format.
+fn frame_location(frame: &str) -> Option<(&str, u32, u32)> {
+    let frame = frame.trim().trim_end_matches(')');
+    let (rest, column) = frame.rsplit_once(':')?;
+    let (rest, line) = rest.rsplit_once(':')?;
+    let source_start = rest
+        .rfind(['(', '@'])
+        .map(|i| i + 1)
+        .or_else(|| rest.strip_prefix("at ").map(|_| 3))
+        .unwrap_or(0);
+    Some((
+        &rest[source_start..],
+        line.parse().ok()?,
+        column.parse().ok()?,
+    ))
+}
+
+/// Rewrite the frames of `stack` that point into the eval wrapper so their
+/// line numbers are relative to the user's snippet (`<eval>:line:column`),
+/// dropping frames from the wrapper itself. `probe` is the stack of an error
+/// created at a known distance above the snippet. When the probe can't be
+/// parsed (unknown stack format), the raw stack is returned unchanged.
+pub fn map_stack(stack: &str, probe: Option<&str>, code_lines: u32) -> String {
+    let Some((source, probe_line, _)) = probe.and_then(|p| p.lines().find_map(frame_location))
+    else {
+        return stack.to_string();
+    };
+    let first = probe_line + USER_CODE_LINE_OFFSET;
+    let last = first + code_lines.max(1) - 1;
+
+    stack
+        .lines()
+        .filter_map(|frame| match frame_location(frame) {
+            Some((src, line, column)) if src == source => {
+                if !(first..=last).contains(&line) {
+                    // A frame in the wrapper, not the user's code.
+                    return None;
+                }
+                let location = format!("{src}:{line}:{column}");
+                let at = frame.rfind(&location)?;
+                Some(format!(
+                    "{}<eval>:{}:{column}{}",
+                    &frame[..at],
+                    line - first + 1,
+                    &frame[at + location.len()..]
+                ))
+            }
+            _ => Some(frame.to_string()),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Generate a random 128-bit hex ID for correlating eval requests.
 pub fn uuid_v4() -> String {
     use rand::Rng;
@@ -436,10 +2509,244 @@ pub fn uuid_v4() -> String {
     bytes.iter().map(|b| format!("{b:02x}")).collect()
 }
 
+/// JavaScript that waits for web fonts and pending `<img>` loads (bounded to
+/// 5s, well inside the eval timeout), then for two frames so they get painted.
+const READY_WAIT_JS: &str = r#"
+    const deadline = Date.now() + 5000;
+    const remaining = () => new Promise(r => setTimeout(r, Math.max(0, deadline - Date.now())));
+    if (document.fonts) {
+        await Promise.race([document.fonts.ready, remaining()]);
+    }
+    const pending = Array.from(document.images).filter(img => !img.complete);
+    await Promise.race([
+        Promise.all(pending.map(img => new Promise(r => {
+            img.addEventListener('load', r, { once: true });
+            img.addEventListener('error', r, { once: true });
+        }))),
+        remaining(),
+    ]);
+    await new Promise(r => requestAnimationFrame(() => requestAnimationFrame(r)));
+    return true;
+"#;
+
+/// JavaScript that measures the full scrollable document size.
+const PAGE_SIZE_JS: &str = r#"
+    const el = document.documentElement;
+    const body = document.body || el;
+    return [
+        Math.max(el.scrollWidth, body.scrollWidth, el.clientWidth),
+        Math.max(el.scrollHeight, body.scrollHeight, el.clientHeight),
+    ];
+"#;
+
+/// JavaScript that lists elements carrying a `data-debug-ref`, in ref order.
+const LIVE_REFS_JS: &str = r#"
+    return Array.from(document.querySelectorAll('[data-debug-ref]'))
+        .map(el => {
+            const text = (el.innerText || el.value || el.getAttribute('aria-label') || '').trim();
+            return {
+                ref: '@' + el.getAttribute('data-debug-ref'),
+                tag: el.tagName.toLowerCase(),
+                text: text ? text.slice(0, 80) : null,
+                visible: el.getClientRects().length > 0,
+            };
+        })
+        .sort((a, b) => parseInt(a.ref.slice(2)) - parseInt(b.ref.slice(2)));
+"#;
+
+/// JavaScript that removes every `data-debug-ref`, returning how many.
+const CLEAR_REFS_JS: &str = r#"
+    const marked = document.querySelectorAll('[data-debug-ref]');
+    for (const el of marked) el.removeAttribute('data-debug-ref');
+    return marked.length;
+"#;
+
+/// Counts interactive elements with the same rules as the snapshot (tags,
+/// interactive roles, tabindex, onclick), without assigning refs.
+const PAGE_STATE_JS: &str = r#"
+    const interactive = [
+        'a', 'button', 'input', 'select', 'textarea', 'details', 'summary',
+        'label', 'option', '[tabindex]', '[onclick]',
+        ...['button', 'link', 'textbox', 'checkbox', 'radio', 'combobox',
+            'listbox', 'menuitem', 'tab', 'switch', 'slider', 'spinbutton',
+            'searchbox', 'option', 'menuitemcheckbox', 'menuitemradio',
+            'treeitem'].map(r => `[role="${r}"]`),
+    ].join(',');
+    const active = document.activeElement;
+    let focused = null;
+    if (active && active !== document.body && active !== document.documentElement) {
+        focused = active.tagName.toLowerCase() + (active.id ? '#' + active.id : '');
+        const ref = active.getAttribute('data-debug-ref');
+        if (ref) focused += ' @' + ref;
+    }
+    return {
+        url: window.location.href,
+        title: document.title,
+        ready_state: document.readyState,
+        focused_element: focused,
+        element_count: document.getElementsByTagName('*').length,
+        interactive_count: document.querySelectorAll(interactive).length,
+    };
+"#;
+
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn cached_snapshot_walker_shrinks_the_payload() {
+        let args = snapshot_args(
+            &SnapshotQuery::default(),
+            Duration::from_secs(8),
+            DEFAULT_TESTID_ATTR,
+        );
+        let call_js = snapshot_call_js(&args);
+        let install_js = snapshot_install_js(&args);
+        assert!(call_js.contains(&format!("window.__debugBridgeSnapshot({args}) : null")));
+        assert!(install_js.starts_with(SNAPSHOT_WALKER_JS));
+        assert!(install_js.ends_with(&format!("return window.__debugBridgeSnapshot({args});")));
+
+        let call = wrap_eval(&call_js, "id", None, false, false);
+        let full = wrap_eval(&install_js, "id", None, false, false);
+        let walker_bytes = full.len() - wrap_eval("", "id", None, false, false).len();
+        assert!(walker_bytes > 4000, "walker is {walker_bytes} bytes");
+        assert!(
+            call.len() * 3 < full.len(),
+            "call {} vs full {}",
+            call.len(),
+            full.len()
+        );
+    }
+
+    #[test]
+    fn dry_run_wraps_scripts_without_a_pending_id() {
+        let wrapped = wrap_eval("document.title", DRY_RUN_ID, None, false, false);
+        assert!(wrapped.contains("return (\ndocument.title\n)"));
+        assert!(wrapped.contains("id: 'dry-run'"));
+
+        let statements = wrap_eval("const a = 1;\nreturn a;", "abc", None, false, false);
+        assert!(statements.contains("{ \nconst a = 1;\nreturn a;\n }"));
+        assert!(statements.contains("code_lines: 2"));
+    }
+
+    #[test]
+    fn missing_global_tauri_gets_its_own_error() {
+        assert!(uses_global_tauri("window.__TAURI__.core.invoke('greet')"));
+        assert!(uses_global_tauri("const { invoke } = __TAURI__.core;"));
+        assert!(!uses_global_tauri(
+            "window.__TAURI_INTERNALS__.invoke('greet')"
+        ));
+        assert!(!uses_global_tauri("document.title"));
+
+        let error = global_tauri_missing(Some(
+            "TypeError: Cannot read properties of undefined (reading 'core')",
+        ));
+        assert!(error.starts_with("window.__TAURI__ is not defined: set app.withGlobalTauri"));
+        assert!(
+            error.ends_with("[TypeError: Cannot read properties of undefined (reading 'core')]")
+        );
+    }
+
+    #[test]
+    fn undefined_results_are_told_apart_from_null() {
+        let wrapped = wrap_eval("const a = 1;", "abc", None, false, false);
+        assert!(wrapped.contains("value: __result, undefined: __result === undefined"));
+
+        let nothing: EvalResult =
+            serde_json::from_str(r#"{"success":true,"value":null,"undefined":true,"error":null}"#)
+                .unwrap();
+        assert!(nothing.undefined);
+        let null: EvalResult =
+            serde_json::from_str(r#"{"success":true,"value":null,"error":null}"#).unwrap();
+        assert!(!null.undefined);
+        let json = serde_json::to_value(&null).unwrap();
+        assert!(json.get("undefined").is_none(), "only sent when true");
+    }
+
+    #[test]
+    fn ready_wait_runs_before_the_code_without_moving_it() {
+        let plain = wrap_eval("document.title", "abc", None, false, false);
+        let gated = wrap_eval("document.title", "abc", None, true, false);
+        assert!(!plain.contains("readyState"));
+        assert!(gated.starts_with(&format!("(async () => {{{READY_STATE_PRELUDE}\n")));
+        assert_eq!(gated.lines().count(), plain.lines().count());
+    }
+
+    #[tokio::test]
+    async fn missing_windows_list_the_open_ones() {
+        let resp =
+            window_not_found("prefs", vec!["settings".into(), "main".into()]).into_response();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "error": "window 'prefs' not found (open: main, settings)",
+                "code": "not_found",
+                "available": ["main", "settings"],
+            })
+        );
+
+        let none = window_not_found("main", Vec::new());
+        assert_eq!(
+            none.message,
+            "window 'main' not found (no windows are open)"
+        );
+        assert_eq!(none.details["available"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn raw_evals_return_plain_text() {
+        let wrapped = wrap_eval("document.body.innerHTML", "abc", None, false, true);
+        assert!(wrapped.contains(&format!("value: {RAW_VALUE_JS},")));
+
+        let ok = |value| EvalResult {
+            success: true,
+            value,
+            undefined: false,
+            error: None,
+            stack: None,
+        };
+        let resp = raw_response(ok(Some("<p>a \"b\"</p>".into()))).unwrap();
+        assert_eq!(resp.headers()["content-type"], "text/plain; charset=utf-8");
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"<p>a \"b\"</p>");
+
+        let err = raw_response(EvalResult {
+            success: false,
+            error: Some("ReferenceError: x is not defined".into()),
+            stack: Some("at <eval>:1:1".into()),
+            ..ok(None)
+        })
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err.details["stack"], "at <eval>:1:1");
+    }
+
+    #[test]
+    fn bindings_are_passed_as_json() {
+        let mut bindings = Bindings::new();
+        bindings.insert("userId".into(), 42.into());
+        bindings.insert("name".into(), "'); alert(1); ('".into());
+        let wrapped = wrap_eval("__env.userId", "abc", Some(&bindings), false, false);
+        assert!(wrapped.contains(
+            r#"(async (__env) => { return (
+__env.userId
+) })({"name":"'); alert(1); ('","userId":42});"#
+        ));
+
+        // The user's code keeps its position below the probe.
+        let unbound = wrap_eval("__env.userId", "abc", None, false, false);
+        assert_eq!(wrapped.lines().count(), unbound.lines().count());
+        assert!(unbound.contains("(async () => { return ("));
+    }
+
     #[test]
     fn expression_detection() {
         // Simple expressions — should auto-return
@@ -490,7 +2797,12 @@ mod tests {
             text: Some("container".to_string()),
             name: None,
             value: None,
+            id: None,
+            class: None,
             interactive: false,
+            visible: None,
+            testid: None,
+            aria: None,
             children: vec![
                 SnapshotElement {
                     tag: "button".to_string(),
@@ -499,7 +2811,12 @@ mod tests {
                     text: Some("Click me".to_string()),
                     name: None,
                     value: None,
+                    id: None,
+                    class: None,
                     interactive: true,
+                    visible: None,
+                    testid: None,
+                    aria: None,
                     children: vec![],
                 },
                 SnapshotElement {
@@ -509,7 +2826,12 @@ mod tests {
                     text: Some("static text".to_string()),
                     name: None,
                     value: None,
+                    id: None,
+                    class: None,
                     interactive: false,
+                    visible: None,
+                    testid: None,
+                    aria: None,
                     children: vec![],
                 },
             ],
@@ -525,6 +2847,101 @@ mod tests {
         assert_eq!(pruned[0].children[0].tag, "button");
     }
 
+    #[test]
+    fn optional_element_fields_are_omitted_when_unset() {
+        let full = serde_json::json!({
+            "tag": "button",
+            "ref": "e4",
+            "interactive": true,
+            "visible": false,
+            "testid": "save",
+            "aria": { "expanded": "false", "pressed": "mixed" },
+        });
+        let el: SnapshotElement = serde_json::from_value(full.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&el).unwrap(), full);
+
+        let plain = serde_json::json!({ "tag": "div", "interactive": false });
+        let el: SnapshotElement = serde_json::from_value(plain.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&el).unwrap(), plain);
+    }
+
+    #[test]
+    fn snapshot_args_follow_the_query_and_config() {
+        let query = SnapshotQuery {
+            attrs: Some(true),
+            reset_refs: Some(true),
+            ..Default::default()
+        };
+        let args = snapshot_args(&query, Duration::from_secs(8), r#"data-qa"x"#);
+        assert_eq!(
+            args,
+            r#"true, 8000, false, "data-qa\"x", true, ["expanded","checked","selected","disabled","pressed","current","invalid","busy","hidden"]"#
+        );
+        assert!(
+            snapshot_args(
+                &SnapshotQuery::default(),
+                Duration::from_millis(1500),
+                "data-testid"
+            )
+            .starts_with(r#"false, 1500, false, "data-testid", false, "#)
+        );
+    }
+
+    #[test]
+    fn snapshot_fields_name_every_element_field() {
+        let el = SnapshotElement {
+            tag: "input".to_string(),
+            r#ref: Some("e1".to_string()),
+            role: Some("textbox".to_string()),
+            text: Some("Email".to_string()),
+            name: Some("Email".to_string()),
+            value: Some("a@b.c".to_string()),
+            id: Some("email".to_string()),
+            class: Some("field".to_string()),
+            interactive: true,
+            visible: Some(true),
+            testid: Some("email".to_string()),
+            aria: Some(BTreeMap::from([(
+                "invalid".to_string(),
+                "true".to_string(),
+            )])),
+            children: vec![],
+        };
+        let json = serde_json::to_value(&el).unwrap();
+        let mut keys: Vec<&str> = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        let mut expected = SNAPSHOT_FIELDS.to_vec();
+        expected.sort_unstable();
+        assert_eq!(keys, expected, "every field can be kept with ?fields=");
+    }
+
+    #[test]
+    fn snapshot_fields_narrow_every_element() {
+        let fields = parse_fields("ref, text").unwrap();
+        let mut elements = serde_json::json!([{
+            "tag": "form",
+            "interactive": false,
+            "children": [
+                {"tag": "button", "ref": "e1", "text": "Save", "role": "button", "interactive": true},
+            ],
+        }]);
+        project_elements(&mut elements, &fields);
+        assert_eq!(
+            elements,
+            serde_json::json!([{ "children": [{ "ref": "e1", "text": "Save" }] }])
+        );
+
+        let err = parse_fields("ref,label").unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert!(err.message.contains("'label'"), "{}", err.message);
+        assert!(parse_fields(" , ").is_err());
+    }
+
     #[test]
     fn prune_removes_empty_branches() {
         let tree = vec![SnapshotElement {
@@ -534,7 +2951,12 @@ mod tests {
             text: None,
             name: None,
             value: None,
+            id: None,
+            class: None,
             interactive: false,
+            visible: None,
+            testid: None,
+            aria: None,
             children: vec![SnapshotElement {
                 tag: "p".to_string(),
                 r#ref: None,
@@ -542,7 +2964,12 @@ mod tests {
                 text: Some("just text".to_string()),
                 name: None,
                 value: None,
+                id: None,
+                class: None,
                 interactive: false,
+                visible: None,
+                testid: None,
+                aria: None,
                 children: vec![],
             }],
         }];
@@ -553,14 +2980,542 @@ mod tests {
             "no interactive elements means empty result"
         );
     }
+
+    #[test]
+    fn locators_prefer_role_then_text_then_css() {
+        let el =
+            |tag: &str, r: &str, role: Option<&str>, text: Option<&str>, name: Option<&str>| {
+                SnapshotElement {
+                    tag: tag.to_string(),
+                    r#ref: Some(r.to_string()),
+                    role: role.map(String::from),
+                    text: text.map(String::from),
+                    name: name.map(String::from),
+                    value: None,
+                    id: None,
+                    class: None,
+                    interactive: true,
+                    visible: None,
+                    testid: None,
+                    aria: None,
+                    children: vec![],
+                }
+            };
+        let mut form = el("form", "e0", None, None, None);
+        form.interactive = false;
+        form.r#ref = None;
+        form.children = vec![
+            el("button", "e1", None, Some("Save"), None),
+            el("input", "e2", None, None, Some("Email")),
+            el("label", "e3", None, Some("Remember me"), None),
+            el("input", "e4", None, None, None),
+            el("div", "e5", Some("tab"), None, Some("Settings")),
+        ];
+
+        let locators = suggest_locators(&[form]);
+        let rendered: Vec<_> = locators
+            .iter()
+            .map(|l| (l.r#ref.as_str(), l.strategy, l.locator.as_str()))
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                ("@e1", "role", r#"getByRole("button", { name: "Save" })"#),
+                ("@e2", "role", r#"getByRole("textbox", { name: "Email" })"#),
+                ("@e3", "text", r#"getByText("Remember me")"#),
+                ("@e4", "css", r#"locator("input").nth(1)"#),
+                ("@e5", "role", r#"getByRole("tab", { name: "Settings" })"#),
+            ]
+        );
+    }
+
+    #[test]
+    fn snapshot_budget_leaves_room_before_the_timeout() {
+        assert_eq!(
+            snapshot_budget(Duration::from_secs(10)),
+            Duration::from_secs(8)
+        );
+        assert_eq!(
+            snapshot_budget(Duration::from_secs(60)),
+            Duration::from_secs(58)
+        );
+        assert_eq!(
+            snapshot_budget(Duration::from_secs(3)),
+            Duration::from_millis(1500)
+        );
+    }
+
+    #[tokio::test]
+    async fn truncated_walks_are_flagged_in_locators_output() {
+        let snapshot: SnapshotResponse = serde_json::from_value(serde_json::json!({
+            "title": "Big table",
+            "url": "tauri://localhost/",
+            "elements": [
+                {"tag": "button", "ref": "e1", "text": "Edit", "interactive": true},
+            ],
+            "truncated": true,
+        }))
+        .unwrap();
+        let response = locators_response(&snapshot);
+        assert_eq!(
+            response.headers()[WARNING_HEADER],
+            "snapshot truncated: the walk ran out of time"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body[0]["ref"], "@e1");
+        assert_eq!(
+            body[0]["locator"],
+            r#"getByRole("button", { name: "Edit" })"#
+        );
+
+        let complete = SnapshotResponse {
+            truncated: false,
+            ..snapshot
+        };
+        assert!(
+            locators_response(&complete)
+                .headers()
+                .get(WARNING_HEADER)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn zoom_factors_are_clamped() {
+        assert_eq!(clamp_zoom(1.25).unwrap(), 1.25);
+        assert_eq!(clamp_zoom(0.1).unwrap(), 0.25);
+        assert_eq!(clamp_zoom(12.0).unwrap(), 5.0);
+        assert_eq!(
+            clamp_zoom(f64::NAN).unwrap_err().status,
+            StatusCode::BAD_REQUEST
+        );
+
+        let levels = ZoomLevels::default();
+        assert_eq!(levels.get("main"), 1.0);
+        levels.set("main", 1.5);
+        assert_eq!(levels.get("main"), 1.5);
+        assert_eq!(levels.get("settings"), 1.0);
+    }
+
+    #[test]
+    fn csp_hint_follows_probe_and_policy() {
+        let status = CspStatus::default();
+        assert_eq!(status.ok(), None);
+        assert_eq!(status.timeout_hint("main"), None);
+
+        status.record(
+            "main",
+            CspProbe {
+                ok: true,
+                meta_policy: Some("script-src 'self'".to_string()),
+            },
+        );
+        assert_eq!(status.ok(), Some(true));
+        assert_eq!(status.policy("main").as_deref(), Some("script-src 'self'"));
+        // The probe ran, so the policy lets injected scripts through.
+        assert_eq!(status.timeout_hint("main"), None);
+
+        status.record(
+            "settings",
+            CspProbe {
+                ok: false,
+                meta_policy: None,
+            },
+        );
+        assert_eq!(status.ok(), Some(false));
+        assert!(
+            status
+                .timeout_hint("settings")
+                .unwrap()
+                .contains("policy unknown")
+        );
+
+        status.record(
+            "main",
+            CspProbe {
+                ok: false,
+                meta_policy: Some("script-src 'self'".to_string()),
+            },
+        );
+        assert!(
+            status
+                .timeout_hint("main")
+                .unwrap()
+                .contains("(script-src 'self')")
+        );
+    }
+
+    #[test]
+    fn element_lookup_resolves_refs_and_css() {
+        assert_eq!(
+            element_lookup_js("@e3", DEFAULT_TESTID_ATTR),
+            r#"document.querySelector("[data-debug-ref=\"e3\"]")"#
+        );
+        assert_eq!(
+            element_lookup_js("button.primary", DEFAULT_TESTID_ATTR),
+            r#"document.querySelector("button.primary")"#
+        );
+
+        // A ref can't break out of the selector string or the script.
+        let js = element_lookup_js(r#"@e1"]'); alert(1); ('"#, DEFAULT_TESTID_ATTR);
+        let arg = js
+            .strip_prefix("document.querySelector(")
+            .and_then(|s| s.strip_suffix(')'))
+            .unwrap();
+        let css: String = serde_json::from_str(arg).unwrap();
+        assert_eq!(css, r#"[data-debug-ref="e1\"]'); alert(1); ('"]"#);
+    }
+
+    #[test]
+    fn testid_selectors_use_the_configured_attribute() {
+        assert_eq!(parse_target("testid=save"), Ok(Target::TestId("save")));
+        assert_eq!(
+            element_lookup_js("testid=save", DEFAULT_TESTID_ATTR),
+            r#"document.querySelector("[data-testid=\"save\"]")"#
+        );
+        assert_eq!(
+            element_lookup_js(r#"testid=say "hi""#, "data-qa"),
+            r#"document.querySelector("[data-qa=\"say \\\"hi\\\"\"]")"#
+        );
+    }
+
+    #[test]
+    fn role_selectors_parse_role_and_name() {
+        assert_eq!(parse_target("@e3"), Ok(Target::Ref("e3")));
+        assert_eq!(
+            parse_target("[role=button]"),
+            Ok(Target::Css("[role=button]"))
+        );
+        assert_eq!(
+            parse_target("role=button"),
+            Ok(Target::Role {
+                role: "button",
+                name: None
+            })
+        );
+        assert_eq!(
+            parse_target(r#"role=button[name="Say \"hi\""]"#),
+            Ok(Target::Role {
+                role: "button",
+                name: Some(r#"Say "hi""#.to_string())
+            })
+        );
+        assert_eq!(
+            parse_target("role=textbox[name='Email']"),
+            Ok(Target::Role {
+                role: "textbox",
+                name: Some("Email".to_string())
+            })
+        );
+        assert!(parse_target("role=button[label=x]").is_err());
+        assert!(parse_target("role=").is_err());
+        assert!(parse_target("role=a b").is_err());
+    }
+
+    #[test]
+    fn role_selectors_become_matchers() {
+        let js = element_lookup_js(r#"role=button[name="Save"]"#, DEFAULT_TESTID_ATTR);
+        assert!(js.ends_with(r#"})("button", "Save", {"a":"link","button":"button","details":"group","input":"textbox","option":"option","select":"combobox","textarea":"textbox"})"#), "{js}");
+        assert!(element_lookup_js("role=link", DEFAULT_TESTID_ATTR).contains(r#"("link", null, "#));
+        assert!(
+            element_lookup_js("role=", DEFAULT_TESTID_ATTR)
+                .contains("throw new Error(\"invalid role in role=\")")
+        );
+    }
+
+    #[test]
+    fn text_selectors_parse_exact_and_contains() {
+        assert_eq!(
+            parse_target(r#"text="Save draft""#),
+            Ok(Target::Text {
+                text: "Save draft".to_string(),
+                exact: true
+            })
+        );
+        assert_eq!(
+            parse_target("text*=save"),
+            Ok(Target::Text {
+                text: "save".to_string(),
+                exact: false
+            })
+        );
+        assert!(parse_target(r#"text="""#).is_err());
+        assert_eq!(
+            parse_target("[text=x]"),
+            Ok(Target::Css("[text=x]")),
+            "only a leading text= is a text query"
+        );
+    }
+
+    #[test]
+    fn text_selectors_become_matchers() {
+        let js = element_lookup_js(r#"text="Save   draft""#, DEFAULT_TESTID_ATTR);
+        assert!(js.contains(r#"})("Save draft", true, "a, button,"#), "{js}");
+        let js = element_lookup_js("text*=sav", DEFAULT_TESTID_ATTR);
+        assert!(js.contains(r#"})("sav", false, "#), "{js}");
+        assert!(js.contains("el.closest(targets) || el"));
+    }
+
+    #[test]
+    fn clipboard_denial_maps_to_forbidden() {
+        let status = clipboard_error(Some(
+            "NotAllowedError: The request is not allowed".to_string(),
+        ))
+        .status;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let status = clipboard_error(Some("TypeError: boom".to_string())).status;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn full_page_size_is_capped() {
+        assert_eq!(
+            cap_capture_size((1280.0, 4000.0)),
+            ((1280.0, 4000.0), false)
+        );
+        assert_eq!(
+            cap_capture_size((1280.0, 50000.0)),
+            ((1280.0, MAX_CAPTURE_DIMENSION), true)
+        );
+        assert_eq!(cap_capture_size((0.0, 0.0)), ((1.0, 1.0), false));
+    }
+
+    #[test]
+    fn missing_refs_suggest_live_ones() {
+        let live: Vec<LiveRef> = (1..=12)
+            .map(|n| LiveRef {
+                r#ref: format!("@e{n}"),
+                tag: "button".to_string(),
+                text: (n == 1).then(|| "Save".to_string()),
+                visible: true,
+            })
+            .collect();
+        let message = ref_not_found_message("@e20", &live);
+        assert!(
+            message.starts_with(
+                r#"ref @e20 not found; live refs: @e1 (button "Save"), @e2 (button), "#
+            )
+        );
+        assert!(message.contains("@e10 (button), 2 more."));
+
+        assert!(ref_not_found_message("@e1", &[]).contains("no live refs"));
+
+        let missing = EvalResult {
+            success: true,
+            value: Some(serde_json::json!({ "refNotFound": true })),
+            undefined: false,
+            error: None,
+            stack: None,
+        };
+        assert!(is_ref_not_found(&missing));
+    }
+
+    #[test]
+    fn clickable_checks_map_to_a_verdict() {
+        let result = |value: serde_json::Value| EvalResult {
+            success: true,
+            value: Some(value),
+            undefined: false,
+            error: None,
+            stack: None,
+        };
+        let verdict = |value| clickable_response("#save", result(value)).unwrap();
+
+        let ok = verdict(serde_json::json!({ "clickable": true }));
+        assert!(ok.clickable);
+        assert_eq!(ok.reason, None);
+
+        for reason in [
+            "covered by div#modal.overlay",
+            "center is outside the viewport (click scrolls it into view first)",
+            "visibility: hidden",
+        ] {
+            let no = verdict(serde_json::json!({ "clickable": false, "reason": reason }));
+            assert!(!no.clickable);
+            assert_eq!(no.reason.as_deref(), Some(reason));
+        }
+
+        let missing = clickable_response("@e9", result(serde_json::Value::Null)).unwrap_err();
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+        assert_eq!(missing.message, "element not found: @e9");
+
+        let failed = clickable_response(
+            "#save",
+            EvalResult {
+                success: false,
+                value: None,
+                undefined: false,
+                error: Some("ReferenceError: x is not defined".to_string()),
+                stack: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(failed.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(failed.message.contains("ReferenceError"));
+
+        let garbled = clickable_response("#save", result(serde_json::json!(42))).unwrap_err();
+        assert_eq!(garbled.status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn select_text_defaults_to_the_whole_element() {
+        let js = select_text_js("@e3", DEFAULT_TESTID_ATTR, None, None);
+        assert!(js.contains(r#"document.querySelector("[data-debug-ref=\"e3\"]")"#));
+        assert!(js.contains("const from = null, to = null;"));
+
+        let js = select_text_js("#bio", DEFAULT_TESTID_ATTR, Some(2), Some(10));
+        assert!(js.contains("const from = 2, to = 10;"));
+    }
+
+    #[tokio::test]
+    async fn screenshots_stream_in_chunks() {
+        use http_body_util::BodyExt;
+
+        let data: Vec<u8> = (0..SCREENSHOT_CHUNK_BYTES * 2 + 10)
+            .map(|i| i as u8)
+            .collect();
+        let mut body = chunked_body(data.clone());
+        let mut frames = Vec::new();
+        while let Some(frame) = body.frame().await {
+            frames.push(frame.unwrap().into_data().unwrap());
+        }
+        assert_eq!(
+            frames.iter().map(Bytes::len).collect::<Vec<_>>(),
+            [SCREENSHOT_CHUNK_BYTES, SCREENSHOT_CHUNK_BYTES, 10]
+        );
+        assert_eq!(frames.concat(), data);
+    }
+
+    #[tokio::test]
+    async fn chunked_screenshots_keep_their_length() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let data = vec![7u8; SCREENSHOT_CHUNK_BYTES * 2 + 10];
+        let len = data.len();
+        let app = axum::Router::new().route(
+            "/screenshot",
+            axum::routing::get(move || async move { png_response(data, None) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /screenshot HTTP/1.1\r\nhost: x\r\nconnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        let head_end = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8_lossy(&response[..head_end]).to_ascii_lowercase();
+        assert!(head.contains(&format!("content-length: {len}")), "{head}");
+        assert!(!head.contains("transfer-encoding"), "{head}");
+        assert_eq!(response.len() - head_end - 4, len);
+    }
+
+    #[test]
+    fn readiness_reports_per_webview_state() {
+        let readiness = WebviewReadiness::default();
+        assert_eq!(readiness.get("main"), None);
+
+        readiness.set("main", false);
+        readiness.set("settings", true);
+        assert_eq!(readiness.get("main"), Some(false));
+        assert_eq!(readiness.states()["main"], "loading");
+        assert_eq!(readiness.states()["settings"], "ready");
+
+        readiness.mark_crashed("main", "web content process crashed");
+        assert_eq!(readiness.states()["main"], "crashed");
+        assert_eq!(
+            readiness.crashed("main"),
+            Some("web content process crashed")
+        );
+        assert_eq!(readiness.crashed("settings"), None);
+
+        // A reload brings it back.
+        readiness.set("main", true);
+        assert_eq!(readiness.crashed("main"), None);
+        assert_eq!(readiness.get("main"), Some(true));
+        assert_eq!(readiness.loads_started("main"), 1);
+        assert_eq!(readiness.loads_started("settings"), 0);
+    }
+
+    #[test]
+    fn navigation_needs_a_new_page_or_a_matching_url() {
+        let start = "http://localhost:1420/";
+        assert!(!navigated(start, start, false, None));
+        assert!(navigated(
+            start,
+            "http://localhost:1420/settings",
+            false,
+            None
+        ));
+        assert!(navigated(start, start, true, None));
+
+        // With a substring, only the URL matters, so a navigation that
+        // finished before the wait began still counts.
+        assert!(navigated(
+            start,
+            "http://localhost:1420/settings",
+            false,
+            Some("/settings")
+        ));
+        assert!(!navigated(
+            start,
+            "http://localhost:1420/about",
+            true,
+            Some("/settings")
+        ));
+    }
+
+    #[test]
+    fn stacks_map_to_snippet_lines() {
+        let code_lines = 2;
+        // V8: probe on line 2, so user code starts on line 5.
+        let probe = "Error\n    at <anonymous>:2:29\n    at <anonymous>:20:11";
+        let stack = "TypeError: Cannot read properties of null\n    at <anonymous>:6:6\n    at <anonymous>:7:3";
+        assert_eq!(
+            map_stack(stack, Some(probe), code_lines),
+            "TypeError: Cannot read properties of null\n    at <eval>:2:6"
+        );
+
+        // WebKit: `fn@source:line:col`.
+        let probe = "@user-script:1:2:29";
+        let stack = "f@user-script:1:5:10\nglobal code@user-script:1:9:3";
+        assert_eq!(map_stack(stack, Some(probe), code_lines), "f@<eval>:1:10");
+    }
+
+    #[test]
+    fn unparsable_stacks_pass_through() {
+        let stack = "Error: boom\n    at somewhere";
+        assert_eq!(map_stack(stack, Some("Error"), 1), stack);
+        assert_eq!(map_stack(stack, None, 1), stack);
+    }
 }
 
 /// JavaScript that walks the DOM and builds a ref-based accessibility tree.
 /// Same pattern as agent-browser — assigns data-debug-ref attributes to
 /// interactive elements and returns a structured tree.
-const SNAPSHOT_JS: &str = r#"
-    return (() => {
+///
+/// Defines the walker as `window.__debugBridgeSnapshot(...)`, taking
+/// [`snapshot_args`], rather than running it, so it is sent once per page:
+/// later snapshots inject a one-line call (~100 bytes instead of ~4 KB).
+const SNAPSHOT_WALKER_JS: &str = r#"
+    Object.defineProperty(window, '__debugBridgeSnapshot', { configurable: true, value: (WITH_ATTRS, budgetMs, INCLUDE_HIDDEN, TESTID_ATTR, RESET_REFS, ARIA_STATES) => {
+        if (RESET_REFS) {
+            for (const el of document.querySelectorAll('[data-debug-ref]')) {
+                el.removeAttribute('data-debug-ref');
+            }
+        }
         let refCounter = 0;
+        let elementCount = 0;
+        const deadline = performance.now() + budgetMs;
+        let truncated = false;
 
         const INTERACTIVE_TAGS = new Set([
             'A', 'BUTTON', 'INPUT', 'SELECT', 'TEXTAREA', 'DETAILS',
@@ -605,12 +3560,19 @@ const SNAPSHOT_JS: &str = r#"
 
         function walkNode(el) {
             if (el.nodeType !== Node.ELEMENT_NODE) return null;
-            if (!isVisible(el)) return null;
+            // Out of time: stop descending and return what was walked so far.
+            if (truncated || performance.now() > deadline) {
+                truncated = true;
+                return null;
+            }
+            const visible = isVisible(el);
+            if (!visible && !INCLUDE_HIDDEN) return null;
 
             const tag = el.tagName.toLowerCase();
 
             // Skip script, style, and other non-visual elements
             if (['script', 'style', 'noscript', 'template'].includes(tag)) return null;
+            elementCount++;
 
             const interactive = isInteractive(el);
             let ref_id = null;
@@ -628,7 +3590,8 @@ const SNAPSHOT_JS: &str = r#"
 
             // Skip non-interactive containers with no text and only one child
             const text = getTextContent(el);
-            if (!interactive && !text && children.length <= 1 && !el.getAttribute('role')) {
+            const testid = TESTID_ATTR ? el.getAttribute(TESTID_ATTR) : null;
+            if (!interactive && !text && children.length <= 1 && !el.getAttribute('role') && !testid) {
                 return children[0] || null;
             }
 
@@ -637,10 +3600,12 @@ const SNAPSHOT_JS: &str = r#"
                 ref: ref_id,
                 interactive: interactive,
             };
+            if (INCLUDE_HIDDEN) node.visible = visible;
 
             const role = el.getAttribute('role');
             if (role) node.role = role;
             if (text) node.text = text;
+            if (testid) node.testid = testid;
 
             const ariaLabel = el.getAttribute('aria-label');
             const name = ariaLabel || el.getAttribute('name') || el.getAttribute('placeholder');
@@ -650,6 +3615,20 @@ const SNAPSHOT_JS: &str = r#"
                 node.value = String(el.value);
             }
 
+            for (const state of ARIA_STATES) {
+                const v = el.getAttribute('aria-' + state);
+                if (v === null) continue;
+                node.aria = node.aria || {};
+                node.aria[state] = v;
+            }
+
+            if (WITH_ATTRS) {
+                if (el.id) node.id = el.id;
+                // getAttribute, since className is an object on SVG elements.
+                const cls = (el.getAttribute('class') || '').trim().replace(/\s+/g, ' ');
+                if (cls) node.class = cls;
+            }
+
             if (children.length > 0) node.children = children;
 
             return node;
@@ -660,6 +3639,9 @@ const SNAPSHOT_JS: &str = r#"
             title: document.title,
             url: window.location.href,
             elements: tree ? (tree.children || [tree]) : [],
+            truncated: truncated,
+            element_count: elementCount,
+            interactive_count: refCounter,
         };
-    })();
+    } });
 "#;